@@ -0,0 +1,159 @@
+-- Ingredients table
+CREATE TABLE ingredients (
+  id BIGINT AUTO_INCREMENT PRIMARY KEY,
+  name VARCHAR(255) NOT NULL,
+  quantity VARCHAR(50) NOT NULL,
+  version BIGINT NOT NULL DEFAULT 1, -- Bumped on every update, used for the ETag
+  deleted_at TIMESTAMP NULL DEFAULT NULL -- Set when moved to the trash
+);
+
+-- Recipes table
+CREATE TABLE recipes (
+  id BIGINT AUTO_INCREMENT PRIMARY KEY,
+  title TEXT NOT NULL,
+  cid BIGINT DEFAULT 0,
+  ctime TIMESTAMP DEFAULT CURRENT_TIMESTAMP,
+  mtime TIMESTAMP DEFAULT CURRENT_TIMESTAMP ON UPDATE CURRENT_TIMESTAMP,
+  version BIGINT NOT NULL DEFAULT 1, -- Bumped on every update (mtime has second precision), used for the ETag
+  deleted_at TIMESTAMP NULL DEFAULT NULL, -- Set when moved to the trash
+  rating_avg DOUBLE NULL DEFAULT NULL, -- Average of recipe_reviews.rating, NULL when not rated yet
+  rating_count BIGINT NOT NULL DEFAULT 0 -- Number of recipe_reviews
+);
+
+-- Recipe-Ingredient relationship table
+CREATE TABLE recipe_ingredients (
+  id BIGINT AUTO_INCREMENT PRIMARY KEY,
+  recipe_id BIGINT NOT NULL,
+  ingredient_id BIGINT NOT NULL,
+  ingredient_name varchar(255) DEFAULT 'unknown',
+  quantity VARCHAR(50),
+  cid BIGINT DEFAULT 0,
+  ctime TIMESTAMP DEFAULT CURRENT_TIMESTAMP,
+  mtime TIMESTAMP DEFAULT CURRENT_TIMESTAMP ON UPDATE CURRENT_TIMESTAMP, -- Modification timestamp
+  deleted_at TIMESTAMP NULL DEFAULT NULL, -- Set when the ingredient is moved to the trash
+  FOREIGN KEY (recipe_id) REFERENCES recipes(id) ON DELETE CASCADE, -- Reference to the recipes table
+  FOREIGN KEY (ingredient_id) REFERENCES ingredients(id) ON DELETE CASCADE -- Reference to the ingredients table
+);
+
+-- Recipe revisions table (snapshot of a recipe and its ingredients after each change)
+CREATE TABLE recipe_revisions (
+  id BIGINT AUTO_INCREMENT PRIMARY KEY,
+  recipe_id BIGINT NOT NULL,
+  rev BIGINT NOT NULL, -- 1-based revision number, per recipe
+  title TEXT NOT NULL,
+  recipe_cid BIGINT DEFAULT 0, -- recipes.cid at the time of the snapshot
+  ingredients JSON NOT NULL, -- Array of RecipeIngredientPatch
+  cid BIGINT DEFAULT 0, -- User who made the change
+  ctime TIMESTAMP DEFAULT CURRENT_TIMESTAMP,
+  UNIQUE (recipe_id, rev),
+  FOREIGN KEY (recipe_id) REFERENCES recipes(id) ON DELETE CASCADE
+);
+
+-- Recipe reviews table (one per user per recipe)
+CREATE TABLE recipe_reviews (
+  id BIGINT AUTO_INCREMENT PRIMARY KEY,
+  recipe_id BIGINT NOT NULL,
+  rating TINYINT NOT NULL, -- 1 to 5
+  body TEXT NOT NULL,
+  cid BIGINT NOT NULL, -- User who wrote the review
+  ctime TIMESTAMP DEFAULT CURRENT_TIMESTAMP,
+  mtime TIMESTAMP DEFAULT CURRENT_TIMESTAMP ON UPDATE CURRENT_TIMESTAMP,
+  UNIQUE (recipe_id, cid),
+  FOREIGN KEY (recipe_id) REFERENCES recipes(id) ON DELETE CASCADE
+);
+
+-- Recipe comments table (threads of replies)
+CREATE TABLE recipe_comments (
+  id BIGINT AUTO_INCREMENT PRIMARY KEY,
+  recipe_id BIGINT NOT NULL,
+  parent_id BIGINT NULL DEFAULT NULL, -- Comment replied to, NULL for a thread root
+  root_id BIGINT NULL DEFAULT NULL, -- Thread root, NULL for a thread root
+  body TEXT NOT NULL, -- Markdown source
+  body_html TEXT NOT NULL, -- Sanitized HTML rendering of body
+  cid BIGINT NOT NULL, -- Author
+  ctime TIMESTAMP DEFAULT CURRENT_TIMESTAMP,
+  mtime TIMESTAMP DEFAULT CURRENT_TIMESTAMP ON UPDATE CURRENT_TIMESTAMP,
+  deleted_at TIMESTAMP NULL DEFAULT NULL, -- Set on delete, the comment stays as a placeholder for its replies
+  FOREIGN KEY (recipe_id) REFERENCES recipes(id) ON DELETE CASCADE
+);
+
+-- Recipe favorites table (per user)
+CREATE TABLE recipe_favorites (
+  recipe_id BIGINT NOT NULL,
+  cid BIGINT NOT NULL, -- User who favorited the recipe
+  ctime TIMESTAMP DEFAULT CURRENT_TIMESTAMP,
+  PRIMARY KEY (recipe_id, cid),
+  FOREIGN KEY (recipe_id) REFERENCES recipes(id) ON DELETE CASCADE
+);
+
+-- Collections table (personal, named lists of recipes)
+CREATE TABLE collections (
+  id BIGINT AUTO_INCREMENT PRIMARY KEY,
+  name VARCHAR(255) NOT NULL,
+  description TEXT NULL,
+  cid BIGINT NOT NULL, -- Owner of the collection
+  ctime TIMESTAMP DEFAULT CURRENT_TIMESTAMP,
+  mtime TIMESTAMP DEFAULT CURRENT_TIMESTAMP ON UPDATE CURRENT_TIMESTAMP
+);
+
+-- Collection-Recipe relationship table
+CREATE TABLE collection_recipes (
+  collection_id BIGINT NOT NULL,
+  recipe_id BIGINT NOT NULL,
+  position BIGINT NOT NULL, -- 0-based order in the collection
+  PRIMARY KEY (collection_id, recipe_id),
+  FOREIGN KEY (collection_id) REFERENCES collections(id) ON DELETE CASCADE,
+  FOREIGN KEY (recipe_id) REFERENCES recipes(id) ON DELETE CASCADE
+);
+
+-- Share links table (read-only public access to a recipe or a collection)
+CREATE TABLE share_links (
+  id BIGINT AUTO_INCREMENT PRIMARY KEY,
+  token VARCHAR(64) NOT NULL UNIQUE, -- Random, unguessable
+  typ VARCHAR(16) NOT NULL, -- 'recipe' or 'collection'
+  target_id BIGINT NOT NULL,
+  cid BIGINT NOT NULL, -- Owner of the link
+  expires_at TIMESTAMP NULL DEFAULT NULL, -- NULL never expires
+  revoked_at TIMESTAMP NULL DEFAULT NULL,
+  view_count BIGINT NOT NULL DEFAULT 0,
+  ctime TIMESTAMP DEFAULT CURRENT_TIMESTAMP
+);
+
+-- Change feed table (append-only, one row per write, read by offline clients to sync)
+CREATE TABLE changes (
+  seq BIGINT AUTO_INCREMENT PRIMARY KEY, -- Cursor of the feed
+  typ VARCHAR(32) NOT NULL, -- 'recipe', 'ingredient' or 'recipe_ingredients' (all the links of a recipe)
+  entity_id BIGINT NOT NULL, -- recipes.id for 'recipe_ingredients'
+  action VARCHAR(16) NOT NULL, -- 'created', 'updated' or 'deleted'
+  cid BIGINT NOT NULL, -- User who made the change
+  ctime TIMESTAMP DEFAULT CURRENT_TIMESTAMP,
+  INDEX (typ, entity_id)
+);
+
+-- Audit log table (append-only, never updated nor deleted, no foreign keys so it outlives the purges)
+CREATE TABLE audit_log (
+  id BIGINT AUTO_INCREMENT PRIMARY KEY,
+  typ VARCHAR(32) NOT NULL, -- 'recipe', 'ingredient' or 'recipe_ingredients' (all the links of a recipe)
+  entity_id BIGINT NOT NULL, -- recipes.id for 'recipe_ingredients'
+  action VARCHAR(16) NOT NULL, -- 'created', 'updated', 'deleted' or 'restored'
+  before_data JSON NULL, -- NULL on create and restore
+  after_data JSON NULL, -- NULL on delete
+  cid BIGINT NOT NULL, -- User who made the change
+  request_id VARCHAR(64) NULL, -- X-Request-Id of the web request
+  ctime TIMESTAMP DEFAULT CURRENT_TIMESTAMP,
+  INDEX (typ, entity_id),
+  INDEX (cid)
+);
+
+-- Applied sql files (filled by init_db, checked by the readiness endpoint)
+CREATE TABLE schema_migrations (
+  file VARCHAR(255) PRIMARY KEY,
+  applied_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP
+);
+
+-- Set the starting point for recipe and ingredient IDs (optional)
+ALTER TABLE recipes AUTO_INCREMENT = 1000;
+ALTER TABLE ingredients AUTO_INCREMENT = 1000;
+ALTER TABLE recipe_ingredients AUTO_INCREMENT = 1000;
+ALTER TABLE collections AUTO_INCREMENT = 1000;
+ALTER TABLE share_links AUTO_INCREMENT = 1000;
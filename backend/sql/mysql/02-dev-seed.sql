@@ -6,3 +6,6 @@ INSERT INTO recipes (title, cid) VALUES ('spaghetti', 123);
 
 -- Dev seed for recipe_ingredients
 INSERT INTO recipe_ingredients (recipe_id, ingredient_id, ingredient_name, quantity) VALUES (1000, 1000, 'tomatoes', '200 g');
+
+-- Dev seed for changes (so a client syncing from scratch gets the seed too)
+INSERT INTO changes (typ, entity_id, action, cid) VALUES ('ingredient', 1000, 'created', 0), ('recipe', 1000, 'created', 123), ('recipe_ingredients', 1000, 'created', 123);
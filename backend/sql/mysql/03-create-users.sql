@@ -0,0 +1,10 @@
+-- Users table (managed with the `user` subcommands of the CLI)
+CREATE TABLE users (
+  id BIGINT AUTO_INCREMENT PRIMARY KEY,
+  username VARCHAR(64) NOT NULL UNIQUE,
+  password_hash VARCHAR(255) NOT NULL, -- Argon2 PHC string
+  ctime TIMESTAMP DEFAULT CURRENT_TIMESTAMP,
+  mtime TIMESTAMP DEFAULT CURRENT_TIMESTAMP ON UPDATE CURRENT_TIMESTAMP
+);
+
+ALTER TABLE users AUTO_INCREMENT = 1000;
@@ -0,0 +1,2 @@
+-- Reverts 03-create-users.sql (`migrate down`)
+DROP TABLE users;
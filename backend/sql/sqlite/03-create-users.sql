@@ -0,0 +1,16 @@
+-- SQLite flavor of sql/mysql/03-create-users.sql, keep both in sync
+
+-- Users table (managed with the `user` subcommands of the CLI)
+CREATE TABLE users (
+  id INTEGER PRIMARY KEY AUTOINCREMENT,
+  username VARCHAR(64) NOT NULL UNIQUE,
+  password_hash VARCHAR(255) NOT NULL, -- Argon2 PHC string
+  ctime TIMESTAMP DEFAULT CURRENT_TIMESTAMP,
+  mtime TIMESTAMP DEFAULT CURRENT_TIMESTAMP
+);
+CREATE TRIGGER users_mtime AFTER UPDATE ON users FOR EACH ROW WHEN NEW.mtime = OLD.mtime
+BEGIN
+  UPDATE users SET mtime = CURRENT_TIMESTAMP WHERE id = NEW.id;
+END;
+
+INSERT INTO sqlite_sequence (name, seq) VALUES ('users', 999);
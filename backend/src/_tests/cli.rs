@@ -0,0 +1,142 @@
+use super::{check, export, import, run, seed, Cli, Command, Dataset, Error, MigrateCommand};
+use crate::model::fixture::TestDb;
+use crate::model::{RecipeMac, UserMac};
+use crate::security::utx_system;
+use anyhow::Result;
+use clap::Parser;
+
+#[test]
+fn cli_parse() -> Result<()> {
+    // -- ACTION - no command, the web folder as before
+    let command = Cli::try_parse_from(["cookbook", "../frontend/web-folder"])?.command();
+
+    // -- CHECK
+    match command {
+        Command::Serve(args) => {
+            assert_eq!("../frontend/web-folder", args.web_folder);
+            assert_eq!(8080, args.port);
+        }
+        _ => panic!("expected serve"),
+    }
+
+    // -- ACTION
+    let serve = Cli::try_parse_from(["cookbook", "serve", "--port", "9000", "--no-trash-purge"])?;
+    let migrate = Cli::try_parse_from(["cookbook", "migrate", "status"])?;
+    let unknown = Cli::try_parse_from(["cookbook", "seed", "unknown"]);
+
+    // -- CHECK
+    assert!(
+        matches!(serve.command(), Command::Serve(args) if args.port == 9000 && args.no_trash_purge)
+    );
+    assert!(matches!(
+        migrate.command(),
+        Command::Migrate(MigrateCommand::Status)
+    ));
+    assert!(unknown.is_err());
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn cli_seed_export_import() -> Result<()> {
+    // -- FIXTURE
+    let db = TestDb::new().await?;
+    let utx = utx_system();
+    let (ingredients, recipes) = seed(&db, &utx, Dataset::Demo).await?;
+
+    // -- ACTION - round trip through JSON, into another db
+    let exported = serde_json::to_string(&export(&db, &utx).await?)?;
+    let other_db = TestDb::new().await?;
+    let imported = import(&other_db, &utx, serde_json::from_str(&exported)?).await?;
+
+    // -- CHECK - same recipes, linked to the new ingredients
+    assert_eq!((ingredients, recipes), imported);
+    let recipes = RecipeMac::list(&db, &utx).await?;
+    let other_recipes = RecipeMac::list(&other_db, &utx).await?;
+    let titles = |recipes: &[(crate::model::Recipe, Vec<_>)]| {
+        recipes
+            .iter()
+            .map(|(recipe, links)| (recipe.title.clone(), recipe.cid, links.len()))
+            .collect::<Vec<_>>()
+    };
+    assert_eq!(titles(&recipes), titles(&other_recipes));
+    assert!(check(&other_db).await?.is_empty());
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn cli_import_missing_ingredient() -> Result<()> {
+    // -- FIXTURE - the recipe without its ingredient
+    let db = TestDb::new().await?;
+    let utx = utx_system();
+    seed(&db, &utx, Dataset::Dev).await?;
+    let mut data = export(&db, &utx).await?;
+    data.ingredients.clear();
+    let other_db = TestDb::new().await?;
+
+    // -- ACTION
+    let result = import(&other_db, &utx, data).await;
+
+    // -- CHECK - nothing created
+    assert!(
+        matches!(result, Err(Error::ImportMissingIngredient(title, _)) if title == "spaghetti")
+    );
+    assert!(RecipeMac::list(&other_db, &utx).await?.is_empty());
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn cli_check() -> Result<()> {
+    // -- FIXTURE
+    let db = TestDb::new().await?;
+    seed(&db, &utx_system(), Dataset::Dev).await?;
+
+    // -- ACTION
+    let result = run(Command::Check, &db).await;
+
+    // -- CHECK
+    assert!(result.is_ok());
+
+    // -- FIXTURE - the ingredient trashed without its recipe link
+    sqlx::query("UPDATE ingredients SET deleted_at = CURRENT_TIMESTAMP")
+        .execute(&*db)
+        .await?;
+
+    // -- ACTION
+    let issues = check(&db).await?;
+    let result = run(Command::Check, &db).await;
+
+    // -- CHECK
+    assert_eq!(1, issues.len());
+    assert!(issues[0].contains("tomatoes"), "{}", issues[0]);
+    assert!(matches!(result, Err(Error::CheckFailed(1))));
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn cli_migrate_down_up() -> Result<()> {
+    // -- FIXTURE
+    let db = TestDb::new().await?;
+
+    // -- ACTION
+    run(Command::Migrate(MigrateCommand::Down), &db).await?;
+    let after_down = check(&db).await?;
+    let users = UserMac::list(&db, &utx_system()).await;
+    run(Command::Migrate(MigrateCommand::Up), &db).await?;
+
+    // -- CHECK - users is the last migration
+    assert_eq!(1, after_down.len());
+    assert!(
+        after_down[0].contains("03-create-users"),
+        "{}",
+        after_down[0]
+    );
+    assert!(users.is_err());
+    assert!(check(&db).await?.is_empty());
+    assert!(UserMac::list(&db, &utx_system()).await?.is_empty());
+
+    Ok(())
+}
@@ -0,0 +1,37 @@
+use super::{mac_timer, observe_request, route_label};
+use std::time::Duration;
+
+#[test]
+fn metrics_route_label() {
+    assert_eq!("/api/recipes/:id", route_label("/api/recipes/1000"));
+    assert_eq!(
+        "/api/recipes/:id/comments/:id",
+        route_label("/api/recipes/1000/comments/3")
+    );
+    assert_eq!(
+        "/api/shared/:param",
+        route_label("/api/shared/Xy7kPq2Lm9RtZ4bN")
+    );
+    assert_eq!("/metrics", route_label("/metrics"));
+    assert_eq!("/health/ready", route_label("/health/ready"));
+    assert_eq!("static", route_label("/js/app.js"));
+    assert_eq!("static", route_label("/"));
+}
+
+#[test]
+fn metrics_observe() {
+    // -- ACTION
+    observe_request("/api/recipes/1000", "GET", 200, Duration::from_millis(3));
+    drop(mac_timer("RecipeMac::get"));
+
+    // -- CHECK
+    let families = super::metrics().registry.gather();
+    let names: Vec<&str> = families.iter().map(|f| f.get_name()).collect();
+    assert!(names.contains(&"cookbook_http_requests_total"));
+    assert!(names.contains(&"cookbook_mac_duration_seconds"));
+    let requests = super::metrics()
+        .http_requests
+        .with_label_values(&["/api/recipes/:id", "GET", "200"])
+        .get();
+    assert!(requests >= 1);
+}
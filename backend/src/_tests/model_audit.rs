@@ -0,0 +1,118 @@
+use super::{AuditFilter, AuditMac};
+use crate::{
+    model::{
+        self,
+        fixture::{Seed, TestDb},
+        IngredientMac, IngredientPatch, PatchValue, RecipeMac,
+    },
+    security::{utx_from_token, UserCtx},
+};
+
+fn admin_utx() -> UserCtx {
+    UserCtx {
+        user_id: 1,
+        is_admin: true,
+        request_id: Some("req-admin".to_string()),
+    }
+}
+
+#[tokio::test]
+async fn model_audit_record_mutations() -> Result<(), Box<dyn std::error::Error>> {
+    // -- FIXTURE
+    let db = TestDb::new().await?;
+    let mut utx = utx_from_token("123").await?;
+    let seed = Seed::create(&db, &utx).await?;
+    utx.request_id = Some("req-1".to_string());
+    let patch = IngredientPatch {
+        name: PatchValue::Value("salt".to_string()),
+        ..Default::default()
+    };
+
+    // -- ACTION
+    IngredientMac::update(&db, &utx, seed.ingredient.id, patch).await?;
+    IngredientMac::delete(&db, &utx, seed.ingredient.id).await?;
+
+    // -- CHECK - newest first, with before and after
+    let filter = AuditFilter {
+        request_id: Some("req-1".to_string()),
+        ..Default::default()
+    };
+    let entries = AuditMac::list(&db, &admin_utx(), filter).await?;
+    let summary: Vec<(&str, i64, &str)> = entries
+        .iter()
+        .map(|e| (e.typ.as_str(), e.entity_id, e.action.as_str()))
+        .collect();
+    assert_eq!(
+        vec![
+            ("recipe_ingredients", seed.recipe.id, "updated"),
+            ("ingredient", seed.ingredient.id, "deleted"),
+            ("ingredient", seed.ingredient.id, "updated")
+        ],
+        summary
+    );
+    let updated = &entries[2];
+    assert_eq!(123, updated.cid);
+    assert_eq!("tomatoes", updated.before_data.as_ref().unwrap()["name"]);
+    assert_eq!("salt", updated.after_data.as_ref().unwrap()["name"]);
+    assert!(entries[1].after_data.is_none());
+    assert_eq!(
+        Some(serde_json::json!([])),
+        entries[0].after_data.as_ref().map(|d| d.0.clone())
+    );
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn model_audit_recipe_links_cid() -> Result<(), Box<dyn std::error::Error>> {
+    // -- FIXTURE
+    let db = TestDb::new().await?;
+    let utx = utx_from_token("123").await?;
+    let seed = Seed::create(&db, &utx).await?;
+    let data = serde_json::from_value(serde_json::json!({
+        "recipe_patch": {"title": "soup"},
+        "ingredients": [{"ingredient_id": seed.ingredient.id, "ingredient_name": "tomatoes", "quantity": "2"}]
+    }))?;
+
+    // -- ACTION
+    let (recipe, ingredients) = RecipeMac::create(&db, &utx, data).await?;
+
+    // -- CHECK - the creator of the links is recorded
+    assert_eq!(123, ingredients[0].cid);
+    let filter = AuditFilter {
+        typ: Some("recipe_ingredients".to_string()),
+        entity_id: Some(recipe.id),
+        ..Default::default()
+    };
+    let entries = AuditMac::list(&db, &admin_utx(), filter).await?;
+    assert_eq!(1, entries.len());
+    assert_eq!("created", entries[0].action);
+    assert_eq!(
+        seed.ingredient.id,
+        entries[0].after_data.as_ref().unwrap()[0]["ingredient_id"]
+    );
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn model_audit_list_admin_only() -> Result<(), Box<dyn std::error::Error>> {
+    // -- FIXTURE
+    let db = TestDb::new().await?;
+    let utx = utx_from_token("123").await?;
+
+    // -- ACTION
+    let result = AuditMac::list(&db, &utx, AuditFilter::default()).await;
+
+    // -- CHECK
+    match result {
+        Ok(_) => panic!("Should not succeed"),
+        Err(model::Error::AccessDenied(typ, id)) => {
+            assert_eq!("audit_log", typ);
+            assert_eq!("123", id);
+        }
+        other_error => panic!("Wrong Error: {:?}", other_error),
+    }
+
+    Ok(())
+}
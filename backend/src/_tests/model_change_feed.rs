@@ -0,0 +1,95 @@
+use super::{ChangeFeedMac, ClientChange, ClientChangeResult, ClientChangeTyp};
+use crate::{
+    model::{
+        fixture::{Seed, TestDb},
+        ChangeAction, IngredientMac, RecipeMac,
+    },
+    security::utx_from_token,
+};
+use serde_json::json;
+
+#[tokio::test]
+async fn model_change_feed_since() -> Result<(), Box<dyn std::error::Error>> {
+    // -- FIXTURE
+    let db = TestDb::new().await?;
+    let utx = utx_from_token("123").await?;
+    let seed_fx = Seed::create(&db, &utx).await?;
+    let seed = ChangeFeedMac::since(&db, &utx, 0, 100).await?;
+    IngredientMac::delete(&db, &utx, seed_fx.ingredient.id).await?;
+    let (recipe, _) = RecipeMac::create(&db, &utx, Default::default()).await?;
+    RecipeMac::update(&db, &utx, recipe.id, Default::default()).await?;
+
+    // -- ACTION
+    let feed = ChangeFeedMac::since(&db, &utx, seed.cursor, 100).await?;
+
+    // -- CHECK - seed
+    assert_eq!(3, seed.changes.len());
+    assert!(!seed.has_more);
+
+    // -- CHECK - one change per entity, tombstone for the delete
+    let summary: Vec<(&str, i64, ChangeAction)> = feed
+        .changes
+        .iter()
+        .map(|c| (c.typ.as_str(), c.id, c.action))
+        .collect();
+    assert_eq!(
+        vec![
+            ("ingredient", seed_fx.ingredient.id, ChangeAction::Deleted),
+            (
+                "recipe_ingredients",
+                seed_fx.recipe.id,
+                ChangeAction::Updated
+            ),
+            ("recipe", recipe.id, ChangeAction::Updated),
+        ],
+        summary
+    );
+    assert!(feed.changes[0].data.is_none());
+    assert_eq!(Some(json!([])), feed.changes[1].data);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn model_change_feed_apply_conflict() -> Result<(), Box<dyn std::error::Error>> {
+    // -- FIXTURE
+    let db = TestDb::new().await?;
+    let utx = utx_from_token("123").await?;
+    let seed = Seed::create(&db, &utx).await?;
+    let update = |base_version: i64, name: &str| ClientChange {
+        typ: ClientChangeTyp::Ingredient,
+        id: Some(seed.ingredient.id),
+        base_version: Some(base_version),
+        action: ChangeAction::Updated,
+        data: json!({ "name": name }),
+    };
+
+    // -- ACTION - two clients based on version 1
+    let results = ChangeFeedMac::apply(
+        &db,
+        &utx,
+        vec![update(1, "cherry tomatoes"), update(1, "roma tomatoes")],
+    )
+    .await?;
+
+    // -- CHECK
+    match &results[0] {
+        ClientChangeResult::Applied { id, version } => {
+            assert_eq!((seed.ingredient.id, 2), (*id, *version))
+        }
+        other => panic!("Wrong result: {:?}", other),
+    }
+    match &results[1] {
+        ClientChangeResult::Conflict {
+            current_version,
+            current,
+            ..
+        } => {
+            assert_eq!(2, *current_version);
+            assert_eq!("cherry tomatoes", current["name"]);
+        }
+        other => panic!("Wrong result: {:?}", other),
+    }
+
+    Ok(())
+}
@@ -0,0 +1,116 @@
+use super::{CollectionMac, CollectionPatch};
+use crate::{
+    model::{
+        self,
+        fixture::{Seed, TestDb},
+        PatchValue, RecipeMac,
+    },
+    security::utx_from_token,
+};
+
+#[tokio::test]
+async fn model_collection_crud() -> Result<(), Box<dyn std::error::Error>> {
+    // -- FIXTURE
+    let db = TestDb::new().await?;
+    let utx = utx_from_token("123").await?;
+    let data_fx = CollectionPatch {
+        name: PatchValue::Value("Weeknight".to_string()),
+        description: PatchValue::Value("Quick dinners".to_string()),
+    };
+
+    // -- ACTION - create
+    let (collection, items) = CollectionMac::create(&db, &utx, data_fx).await?;
+
+    // -- CHECK
+    assert!(collection.id >= 1000, "ID should be >= 1000");
+    assert_eq!("Weeknight", collection.name);
+    assert_eq!(Some("Quick dinners".to_string()), collection.description);
+    assert_eq!(0, items.len());
+
+    // -- ACTION - update (clear the description)
+    let update_fx = CollectionPatch {
+        description: PatchValue::Null,
+        ..Default::default()
+    };
+    let (collection, _) = CollectionMac::update(&db, &utx, collection.id, update_fx).await?;
+
+    // -- CHECK
+    assert_eq!("Weeknight", collection.name);
+    assert_eq!(None, collection.description);
+
+    // -- ACTION - delete
+    CollectionMac::delete(&db, &utx, collection.id).await?;
+
+    // -- CHECK
+    assert_eq!(0, CollectionMac::list(&db, &utx).await?.len());
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn model_collection_add_and_reorder() -> Result<(), Box<dyn std::error::Error>> {
+    // -- FIXTURE
+    let db = TestDb::new().await?;
+    let utx = utx_from_token("123").await?;
+    let seed = Seed::create(&db, &utx).await?;
+    let (collection, _) = CollectionMac::create(&db, &utx, Default::default()).await?;
+    let (recipe, _) = RecipeMac::create(&db, &utx, Default::default()).await?;
+
+    // -- ACTION - add
+    CollectionMac::add_recipe(&db, &utx, collection.id, seed.recipe.id).await?;
+    let (_, items) = CollectionMac::add_recipe(&db, &utx, collection.id, recipe.id).await?;
+
+    // -- CHECK - in insertion order
+    let ids: Vec<i64> = items.iter().map(|i| i.recipe_id).collect();
+    assert_eq!(vec![seed.recipe.id, recipe.id], ids);
+
+    // -- ACTION - reorder
+    let (_, items) =
+        CollectionMac::reorder(&db, &utx, collection.id, vec![recipe.id, seed.recipe.id]).await?;
+
+    // -- CHECK
+    let ids: Vec<i64> = items.iter().map(|i| i.recipe_id).collect();
+    assert_eq!(vec![recipe.id, seed.recipe.id], ids);
+
+    // -- ACTION - reorder with a missing recipe
+    let result = CollectionMac::reorder(&db, &utx, collection.id, vec![seed.recipe.id]).await;
+
+    // -- CHECK
+    match result {
+        Ok(_) => panic!("Should not succeed"),
+        Err(model::Error::ValidationFailed(fields)) => assert_eq!("recipe_ids", fields[0].field),
+        other_error => panic!("Wrong Error: {:?}", other_error),
+    }
+
+    // -- ACTION - remove
+    let (_, items) = CollectionMac::remove_recipe(&db, &utx, collection.id, seed.recipe.id).await?;
+
+    // -- CHECK
+    assert_eq!(1, items.len());
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn model_collection_other_user() -> Result<(), Box<dyn std::error::Error>> {
+    // -- FIXTURE
+    let db = TestDb::new().await?;
+    let utx_123 = utx_from_token("123").await?;
+    let utx_456 = utx_from_token("456").await?;
+    let (collection, _) = CollectionMac::create(&db, &utx_123, Default::default()).await?;
+
+    // -- ACTION
+    let result = CollectionMac::get(&db, &utx_456, collection.id).await;
+
+    // -- CHECK
+    match result {
+        Ok(_) => panic!("Should not succeed"),
+        Err(model::Error::EntityNotFound(typ, id)) => {
+            assert_eq!("collections", typ);
+            assert_eq!(collection.id.to_string(), id);
+        }
+        other_error => panic!("Wrong Error: {:?}", other_error),
+    }
+
+    Ok(())
+}
@@ -1,4 +1,4 @@
-use super::init_db;
+use super::{init_db, retry_read};
 
 #[tokio::test]
 async fn model_db_init_db() -> Result<(), Box<dyn std::error::Error>> {
@@ -15,3 +15,37 @@ async fn model_db_init_db() -> Result<(), Box<dyn std::error::Error>> {
 
     Ok(())
 }
+
+#[tokio::test]
+async fn model_db_retry_read() -> Result<(), Box<dyn std::error::Error>> {
+    // -- ACTION - transient error, then success
+    let mut calls = 0;
+    let result = retry_read(|| {
+        calls += 1;
+        let attempt = calls;
+        async move {
+            match attempt {
+                1 => Err(sqlx::Error::PoolTimedOut),
+                _ => Ok(attempt),
+            }
+        }
+    })
+    .await?;
+
+    // -- CHECK
+    assert_eq!(2, result);
+
+    // -- ACTION - not transient
+    let mut calls = 0;
+    let result: Result<(), sqlx::Error> = retry_read(|| {
+        calls += 1;
+        async { Err(sqlx::Error::RowNotFound) }
+    })
+    .await;
+
+    // -- CHECK - not retried
+    assert!(matches!(result, Err(sqlx::Error::RowNotFound)));
+    assert_eq!(1, calls);
+
+    Ok(())
+}
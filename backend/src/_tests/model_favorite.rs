@@ -0,0 +1,58 @@
+use super::FavoriteMac;
+use crate::{
+    model::{
+        self,
+        fixture::{Seed, TestDb},
+        RecipeMac,
+    },
+    security::utx_from_token,
+};
+
+#[tokio::test]
+async fn model_favorite_add_and_flag() -> Result<(), Box<dyn std::error::Error>> {
+    // -- FIXTURE
+    let db = TestDb::new().await?;
+    let utx_123 = utx_from_token("123").await?;
+    let utx_456 = utx_from_token("456").await?;
+    let recipe_id = Seed::create(&db, &utx_123).await?.recipe.id;
+
+    // -- ACTION
+    let (recipe, _) = FavoriteMac::add(&db, &utx_123, recipe_id).await?;
+    FavoriteMac::add(&db, &utx_123, recipe_id).await?;
+
+    // -- CHECK - flagged for the user only
+    assert!(recipe.is_favorite);
+    assert!(RecipeMac::list(&db, &utx_123).await?[0].0.is_favorite);
+    assert!(!RecipeMac::list(&db, &utx_456).await?[0].0.is_favorite);
+    assert_eq!(1, FavoriteMac::list(&db, &utx_123).await?.len());
+    assert_eq!(0, FavoriteMac::list(&db, &utx_456).await?.len());
+
+    // -- ACTION - remove
+    let (recipe, _) = FavoriteMac::remove(&db, &utx_123, recipe_id).await?;
+
+    // -- CHECK
+    assert!(!recipe.is_favorite);
+    assert_eq!(0, FavoriteMac::list(&db, &utx_123).await?.len());
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn model_favorite_remove_not_favorite() -> Result<(), Box<dyn std::error::Error>> {
+    // -- FIXTURE
+    let db = TestDb::new().await?;
+    let utx = utx_from_token("123").await?;
+    let recipe_id = Seed::create(&db, &utx).await?.recipe.id;
+
+    // -- ACTION
+    let result = FavoriteMac::remove(&db, &utx, recipe_id).await;
+
+    // -- CHECK
+    match result {
+        Ok(_) => panic!("Should not succeed"),
+        Err(model::Error::EntityNotFound(typ, _)) => assert_eq!("recipe_favorites", typ),
+        other_error => panic!("Wrong Error: {:?}", other_error),
+    }
+
+    Ok(())
+}
@@ -1,5 +1,9 @@
 use crate::{
-    model::{self, db::init_db},
+    model::{
+        self,
+        fixture::{IngredientBuilder, TestDb},
+        PatchValue,
+    },
     security::utx_from_token,
 };
 
@@ -8,12 +12,11 @@ use super::{IngredientMac, IngredientPatch};
 #[tokio::test]
 async fn model_ingredient_create() -> Result<(), Box<dyn std::error::Error>> {
     // -- FIXTURE
-    let db = init_db().await?;
-    let utx = utx_from_token(&db, "123").await?;
+    let db = TestDb::new().await?;
+    let utx = utx_from_token("123").await?;
     let data_fx = IngredientPatch {
-        name: Some("test - model_ingredient_create 1".to_string()),
-        quantity: Some("test - model_ingredient_quantity 1".to_string()),
-        ..Default::default()
+        name: PatchValue::Value("test - model_ingredient_create 1".to_string()),
+        quantity: PatchValue::Value("test - model_ingredient_quantity 1".to_string()),
     };
 
     // -- ACTION
@@ -29,14 +32,18 @@ async fn model_ingredient_create() -> Result<(), Box<dyn std::error::Error>> {
 #[tokio::test]
 async fn model_ingredient_get() -> Result<(), Box<dyn std::error::Error>> {
     // -- FIXTURE
-    let db = init_db().await?;
-    let utx = utx_from_token(&db, "123").await?;
+    let db = TestDb::new().await?;
+    let utx = utx_from_token("123").await?;
+    let ingredient_fx = IngredientBuilder::new()
+        .name("tomatoes")
+        .create(&db, &utx)
+        .await?;
 
     // -- ACTION
-    let ingredient = IngredientMac::get(&db, &utx, 1000).await?;
+    let ingredient = IngredientMac::get(&db, &utx, ingredient_fx.id).await?;
 
     // -- CHECK
-    assert_eq!(1000, ingredient.id);
+    assert_eq!(ingredient_fx.id, ingredient.id);
     assert_eq!("tomatoes", ingredient.name);
 
     Ok(())
@@ -45,20 +52,20 @@ async fn model_ingredient_get() -> Result<(), Box<dyn std::error::Error>> {
 #[tokio::test]
 async fn model_ingredient_get_wrong_id() -> Result<(), Box<dyn std::error::Error>> {
     // -- FIXTURE
-    let db = init_db().await?;
-    let utx = utx_from_token(&db, "123").await?;
+    let db = TestDb::new().await?;
+    let utx = utx_from_token("123").await?;
 
     // -- ACTION
     let result = IngredientMac::get(&db, &utx, 99).await;
 
     // -- CHECK
     match result {
-        Ok(_) => assert!(false, "Should not succeed"),
+        Ok(_) => panic!("Should not succeed"),
         Err(model::Error::EntityNotFound(typ, id)) => {
             assert_eq!("ingredients", typ);
             assert_eq!(99.to_string(), id);
         }
-        other_error => assert!(false, "Wrong Error: {:?}", other_error),
+        other_error => panic!("Wrong Error: {:?}", other_error),
     }
 
     Ok(())
@@ -67,18 +74,17 @@ async fn model_ingredient_get_wrong_id() -> Result<(), Box<dyn std::error::Error
 #[tokio::test]
 async fn model_ingredient_update_ok() -> Result<(), Box<dyn std::error::Error>> {
     // -- FIXTURE
-    let db = init_db().await?;
-    let utx = utx_from_token(&db, "123").await?;
+    let db = TestDb::new().await?;
+    let utx = utx_from_token("123").await?;
+    IngredientBuilder::new().create(&db, &utx).await?;
     let data_fx = IngredientPatch {
-        name: Some("test - model_ingredient_update_ok 1".to_string()),
-        quantity: Some("test - model_ingredient_update_ok 1".to_string()),
-        ..Default::default()
+        name: PatchValue::Value("test - model_ingredient_update_ok 1".to_string()),
+        quantity: PatchValue::Value("test - model_ingredient_update_ok 1".to_string()),
     };
     let ingredient_fx = IngredientMac::create(&db, &utx, data_fx.clone()).await?;
     let update_data_fx = IngredientPatch {
-        name: Some("test - model_ingredient_update_ok 2".to_string()),
-        quantity: Some("test - model_ingredient_update_ok 2".to_string()),
-        ..Default::default()
+        name: PatchValue::Value("test - model_ingredient_update_ok 2".to_string()),
+        quantity: PatchValue::Value("test - model_ingredient_update_ok 2".to_string()),
     };
 
     // -- ACTION
@@ -97,17 +103,25 @@ async fn model_ingredient_update_ok() -> Result<(), Box<dyn std::error::Error>>
 #[tokio::test]
 async fn model_ingredient_list() -> Result<(), Box<dyn std::error::Error>> {
     // -- FIXTURE
-    let db = init_db().await?;
-    let utx = utx_from_token(&db, "123").await?;
+    let db = TestDb::new().await?;
+    let utx = utx_from_token("123").await?;
+    let first_fx = IngredientBuilder::new()
+        .name("tomatoes")
+        .create(&db, &utx)
+        .await?;
+    let last_fx = IngredientBuilder::new()
+        .name("basil")
+        .create(&db, &utx)
+        .await?;
 
     // -- ACTION
     let ingredients = IngredientMac::list(&db, &utx).await?;
 
-    // -- CHECK
-    assert_eq!(1, ingredients.len());
-    // ingredient 1000
-    assert_eq!(1000, ingredients[0].id);
-    assert_eq!("tomatoes", ingredients[0].name);
+    // -- CHECK - newest first
+    assert_eq!(2, ingredients.len());
+    assert_eq!(last_fx.id, ingredients[0].id);
+    assert_eq!("basil", ingredients[0].name);
+    assert_eq!(first_fx.id, ingredients[1].id);
 
     Ok(())
 }
@@ -115,14 +129,19 @@ async fn model_ingredient_list() -> Result<(), Box<dyn std::error::Error>> {
 #[tokio::test]
 async fn model_ingredient_delete_simple() -> Result<(), Box<dyn std::error::Error>> {
     // -- FIXTURE
-    let db = init_db().await?;
-    let utx = utx_from_token(&db, "123").await?;
+    let db = TestDb::new().await?;
+    let utx = utx_from_token("123").await?;
+    let ingredient_fx = IngredientBuilder::new()
+        .name("tomatoes")
+        .quantity("3 pieces")
+        .create(&db, &utx)
+        .await?;
 
     // -- ACTION
-    let ingredient = IngredientMac::delete(&db, &utx, 1000).await?;
+    let ingredient = IngredientMac::delete(&db, &utx, ingredient_fx.id).await?;
 
     // -- CHECK - deleted item
-    assert_eq!(1000, ingredient.id);
+    assert_eq!(ingredient_fx.id, ingredient.id);
     assert_eq!("tomatoes", ingredient.name);
     assert_eq!("3 pieces", ingredient.quantity);
 
@@ -132,3 +151,27 @@ async fn model_ingredient_delete_simple() -> Result<(), Box<dyn std::error::Erro
 
     Ok(())
 }
+
+#[tokio::test]
+async fn model_ingredient_update_partial() -> Result<(), Box<dyn std::error::Error>> {
+    // -- FIXTURE
+    let db = TestDb::new().await?;
+    let utx = utx_from_token("123").await?;
+    let ingredient_fx = IngredientBuilder::new()
+        .name("tomatoes")
+        .create(&db, &utx)
+        .await?;
+    let update_data_fx = IngredientPatch {
+        quantity: PatchValue::Value("5 pieces".to_string()),
+        ..Default::default()
+    };
+
+    // -- ACTION
+    let ingredient = IngredientMac::update(&db, &utx, ingredient_fx.id, update_data_fx).await?;
+
+    // -- CHECK
+    assert_eq!("tomatoes", ingredient.name, "name should be untouched");
+    assert_eq!("5 pieces", ingredient.quantity);
+
+    Ok(())
+}
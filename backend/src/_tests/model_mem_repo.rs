@@ -0,0 +1,124 @@
+use crate::{
+    model::{
+        self, IngredientRepo, PatchValue, RecipeIngredientPatch, RecipePatch, RecipePatchInner,
+        RecipeRepo,
+    },
+    security::utx_from_token,
+};
+
+use super::{IngredientPatch, MemRepo};
+
+#[tokio::test]
+async fn model_mem_repo_ingredient_crud() -> Result<(), Box<dyn std::error::Error>> {
+    // -- FIXTURE
+    let repo = MemRepo::new();
+    let utx = utx_from_token("123").await?;
+    let data_fx = IngredientPatch {
+        name: PatchValue::Value("salt".to_string()),
+        quantity: PatchValue::Value("1 pinch".to_string()),
+    };
+
+    // -- ACTION
+    let created = IngredientRepo::create(&repo, &utx, data_fx).await?;
+    let update_fx = IngredientPatch {
+        quantity: PatchValue::Value("2 pinches".to_string()),
+        ..Default::default()
+    };
+    let updated = IngredientRepo::update(&repo, &utx, created.id, update_fx).await?;
+    let list = IngredientRepo::list(&repo, &utx).await?;
+    IngredientRepo::delete(&repo, &utx, created.id).await?;
+    let result = IngredientRepo::get(&repo, &utx, created.id).await;
+
+    // -- CHECK
+    assert_eq!(1000, created.id);
+    assert_eq!(1, created.version);
+    assert_eq!("salt", updated.name);
+    assert_eq!("2 pinches", updated.quantity);
+    assert_eq!(2, updated.version);
+    assert_eq!(1, list.len());
+    match result {
+        Err(model::Error::EntityNotFound("ingredients", id)) => assert_eq!("1000", id),
+        other => panic!("Wrong result: {:?}", other),
+    }
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn model_mem_repo_ingredient_update_if_match_stale() -> Result<(), Box<dyn std::error::Error>>
+{
+    // -- FIXTURE
+    let repo = MemRepo::new();
+    let utx = utx_from_token("123").await?;
+    let created = IngredientRepo::create(&repo, &utx, IngredientPatch::default()).await?;
+
+    // -- ACTION
+    let result = IngredientRepo::update_if_match(
+        &repo,
+        &utx,
+        created.id,
+        created.version + 1,
+        IngredientPatch::default(),
+    )
+    .await;
+
+    // -- CHECK
+    match result {
+        Err(model::Error::VersionMismatch("ingredients", _)) => (),
+        other => panic!("Wrong result: {:?}", other),
+    }
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn model_mem_repo_recipe_links() -> Result<(), Box<dyn std::error::Error>> {
+    // -- FIXTURE
+    let repo = MemRepo::new();
+    let utx = utx_from_token("123").await?;
+    let ingredient = IngredientRepo::create(&repo, &utx, IngredientPatch::default()).await?;
+    let link_fx = |ingredient_id| RecipeIngredientPatch {
+        ingredient_id,
+        ingredient_name: "flour".to_string(),
+        quantity: "200g".to_string(),
+    };
+
+    // -- ACTION
+    let (recipe, ingredients) = RecipeRepo::create(
+        &repo,
+        &utx,
+        RecipePatch {
+            recipe_patch: RecipePatchInner {
+                title: PatchValue::Value("bread".to_string()),
+                ..Default::default()
+            },
+            ingredients: PatchValue::Value(vec![link_fx(ingredient.id)]),
+        },
+    )
+    .await?;
+    let invalid = RecipeRepo::create(
+        &repo,
+        &utx,
+        RecipePatch {
+            ingredients: PatchValue::Value(vec![link_fx(99)]),
+            ..Default::default()
+        },
+    )
+    .await;
+    IngredientRepo::delete(&repo, &utx, ingredient.id).await?;
+    let (_, ingredients_after) = RecipeRepo::get(&repo, &utx, recipe.id).await?;
+
+    // -- CHECK
+    assert_eq!("bread", recipe.title);
+    assert_eq!(1, ingredients.len());
+    assert_eq!(123, ingredients[0].cid);
+    match invalid {
+        Err(model::Error::ValidationFailed(fields)) => {
+            assert_eq!("ingredients[0].ingredient_id", fields[0].field)
+        }
+        other => panic!("Wrong result: {:?}", other),
+    }
+    assert!(ingredients_after.is_empty(), "links go with the ingredient");
+
+    Ok(())
+}
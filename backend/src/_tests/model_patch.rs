@@ -0,0 +1,36 @@
+use super::PatchValue;
+use crate::model::IngredientPatch;
+use serde_json::json;
+
+#[test]
+fn model_patch_absent_null_value() -> Result<(), Box<dyn std::error::Error>> {
+    // -- ACTION
+    let absent: IngredientPatch = serde_json::from_value(json!({}))?;
+    let null: IngredientPatch = serde_json::from_value(json!({"name": null}))?;
+    let value: IngredientPatch = serde_json::from_value(json!({"name": "basil"}))?;
+
+    // -- CHECK
+    assert_eq!(PatchValue::Absent, absent.name);
+    assert_eq!(PatchValue::Null, null.name);
+    assert_eq!(PatchValue::Absent, null.quantity);
+    assert_eq!(PatchValue::Value("basil".to_string()), value.name);
+
+    Ok(())
+}
+
+#[test]
+fn model_patch_serialize_skips_absent() -> Result<(), Box<dyn std::error::Error>> {
+    // -- FIXTURE
+    let patch = IngredientPatch {
+        name: PatchValue::Null,
+        ..Default::default()
+    };
+
+    // -- ACTION
+    let patch = serde_json::to_value(&patch)?;
+
+    // -- CHECK
+    assert_eq!(json!({"name": null}), patch);
+
+    Ok(())
+}
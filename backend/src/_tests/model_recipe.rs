@@ -1,22 +1,29 @@
 use super::{RecipeIngredientPatch, RecipeMac, RecipePatch, RecipePatchInner};
 use crate::{
-    model::{self, db::init_db},
+    model::{
+        self,
+        fixture::{IngredientBuilder, RecipeBuilder, Seed, TestDb},
+        PatchValue,
+    },
     security::utx_from_token,
 };
 
 #[tokio::test]
 async fn model_recipe_create() -> Result<(), Box<dyn std::error::Error>> {
     // -- FIXTURE
-    let db = init_db().await?;
-    let utx = utx_from_token(&db, "123").await?;
+    let db = TestDb::new().await?;
+    let utx = utx_from_token("123").await?;
+    let ingredient_fx = IngredientBuilder::new()
+        .name("tomatoes")
+        .create(&db, &utx)
+        .await?;
     let data_fx = RecipePatch {
         recipe_patch: RecipePatchInner {
-            title: Some("test - model_recipe_create 1".to_string()),
-            cid: Some(123),
-            ..Default::default()
+            title: PatchValue::Value("test - model_recipe_create 1".to_string()),
+            cid: PatchValue::Value(123),
         },
-        ingredients: Some(vec![RecipeIngredientPatch {
-            ingredient_id: 1000,
+        ingredients: PatchValue::Value(vec![RecipeIngredientPatch {
+            ingredient_id: ingredient_fx.id,
             ingredient_name: "tomatoes".to_string(),
             quantity: "2 cups".to_string(),
         }]),
@@ -35,32 +42,26 @@ async fn model_recipe_create() -> Result<(), Box<dyn std::error::Error>> {
 #[tokio::test]
 async fn model_recipe_get() -> Result<(), Box<dyn std::error::Error>> {
     // -- FIXTURE
-    let db = init_db().await?;
-    let utx = utx_from_token(&db, "123").await?;
-
-    let data_fx = RecipePatch {
-        recipe_patch: RecipePatchInner {
-            title: Some("tomato soup".to_string()),
-            cid: Some(123),
-            ..Default::default()
-        },
-        ingredients: Some(vec![RecipeIngredientPatch {
-            ingredient_id: 1000,
-            ingredient_name: "tomatoes".to_string(),
-            quantity: "2 cups".to_string(),
-        }]),
-    };
-
-    let recipe_created = RecipeMac::create(&db, &utx, data_fx.clone()).await?;
+    let db = TestDb::new().await?;
+    let utx = utx_from_token("123").await?;
+    let ingredient_fx = IngredientBuilder::new()
+        .name("tomatoes")
+        .create(&db, &utx)
+        .await?;
+    let (recipe_fx, _) = RecipeBuilder::new()
+        .title("tomato soup")
+        .ingredient(&ingredient_fx, "2 cups")
+        .create(&db, &utx)
+        .await?;
 
     // -- ACTION
-    let (recipe, ingredients) = RecipeMac::get(&db, &utx, 1001).await?;
+    let (recipe, ingredients) = RecipeMac::get(&db, &utx, recipe_fx.id).await?;
 
     // -- CHECK
-    assert_eq!(1001, recipe.id);
+    assert_eq!(recipe_fx.id, recipe.id);
     assert_eq!("tomato soup", recipe.title);
     assert_eq!(1, ingredients.len());
-    assert_eq!(1000, ingredients[0].ingredient_id);
+    assert_eq!(ingredient_fx.id, ingredients[0].ingredient_id);
     assert_eq!("2 cups", ingredients[0].quantity);
 
     Ok(())
@@ -69,20 +70,20 @@ async fn model_recipe_get() -> Result<(), Box<dyn std::error::Error>> {
 #[tokio::test]
 async fn model_recipe_get_wrong_id() -> Result<(), Box<dyn std::error::Error>> {
     // -- FIXTURE
-    let db = init_db().await?;
-    let utx = utx_from_token(&db, "123").await?;
+    let db = TestDb::new().await?;
+    let utx = utx_from_token("123").await?;
 
     // -- ACTION
     let result = RecipeMac::get(&db, &utx, 99).await;
 
     // -- CHECK
     match result {
-        Ok(_) => assert!(false, "Should not succeed"),
+        Ok(_) => panic!("Should not succeed"),
         Err(model::Error::EntityNotFound(typ, id)) => {
             assert_eq!("recipes", typ);
             assert_eq!(99.to_string(), id);
         }
-        other_error => assert!(false, "Wrong Error: {:?}", other_error),
+        other_error => panic!("Wrong Error: {:?}", other_error),
     }
 
     Ok(())
@@ -91,16 +92,16 @@ async fn model_recipe_get_wrong_id() -> Result<(), Box<dyn std::error::Error>> {
 #[tokio::test]
 async fn model_recipe_update_ok() -> Result<(), Box<dyn std::error::Error>> {
     // -- FIXTURE
-    let db = init_db().await?;
-    let utx = utx_from_token(&db, "123").await?;
+    let db = TestDb::new().await?;
+    let utx = utx_from_token("123").await?;
+    let seed = Seed::create(&db, &utx).await?;
     let data_fx = RecipePatch {
         recipe_patch: RecipePatchInner {
-            title: Some("test - model_recipe_update_ok 1".to_string()),
-            cid: Some(123),
-            ..Default::default()
+            title: PatchValue::Value("test - model_recipe_update_ok 1".to_string()),
+            cid: PatchValue::Value(123),
         },
-        ingredients: Some(vec![RecipeIngredientPatch {
-            ingredient_id: 1000,
+        ingredients: PatchValue::Value(vec![RecipeIngredientPatch {
+            ingredient_id: seed.ingredient.id,
             ingredient_name: "tomatoes".to_string(),
             quantity: "2 cups".to_string(),
         }]),
@@ -108,11 +109,11 @@ async fn model_recipe_update_ok() -> Result<(), Box<dyn std::error::Error>> {
     let recipe_fx = RecipeMac::create(&db, &utx, data_fx.clone()).await?;
     let update_data_fx = RecipePatch {
         recipe_patch: RecipePatchInner {
-            title: Some("test - model_recipe_update_ok 2".to_string()),
+            title: PatchValue::Value("test - model_recipe_update_ok 2".to_string()),
             ..Default::default()
         },
-        ingredients: Some(vec![RecipeIngredientPatch {
-            ingredient_id: 1000,
+        ingredients: PatchValue::Value(vec![RecipeIngredientPatch {
+            ingredient_id: seed.ingredient.id,
             ingredient_name: "tomatoes".to_string(),
             quantity: "3 tbsp".to_string(),
         }]),
@@ -137,15 +138,16 @@ async fn model_recipe_update_ok() -> Result<(), Box<dyn std::error::Error>> {
 #[tokio::test]
 async fn model_recipe_list() -> Result<(), Box<dyn std::error::Error>> {
     // -- FIXTURE
-    let db = init_db().await?;
-    let utx = utx_from_token(&db, "123").await?;
+    let db = TestDb::new().await?;
+    let utx = utx_from_token("123").await?;
+    let seed = Seed::create(&db, &utx).await?;
 
     // -- ACTION
     let recipes = RecipeMac::list(&db, &utx).await?;
 
     // -- CHECK
     assert_eq!(1, recipes.len());
-    assert_eq!(1000, recipes[0].0.id);
+    assert_eq!(seed.recipe.id, recipes[0].0.id);
     assert_eq!("spaghetti", recipes[0].0.title);
 
     Ok(())
@@ -154,14 +156,15 @@ async fn model_recipe_list() -> Result<(), Box<dyn std::error::Error>> {
 #[tokio::test]
 async fn model_recipe_delete_simple() -> Result<(), Box<dyn std::error::Error>> {
     // -- FIXTURE
-    let db = init_db().await?;
-    let utx = utx_from_token(&db, "123").await?;
+    let db = TestDb::new().await?;
+    let utx = utx_from_token("123").await?;
+    let seed = Seed::create(&db, &utx).await?;
 
     // -- ACTION
-    let recipe = RecipeMac::delete(&db, &utx, 1000).await?;
+    let recipe = RecipeMac::delete(&db, &utx, seed.recipe.id).await?;
 
     // -- CHECK - deleted item
-    assert_eq!(1000, recipe.0.id);
+    assert_eq!(seed.recipe.id, recipe.0.id);
     assert_eq!("spaghetti", recipe.0.title);
 
     // -- CHECK - list
@@ -170,3 +173,97 @@ async fn model_recipe_delete_simple() -> Result<(), Box<dyn std::error::Error>>
 
     Ok(())
 }
+
+#[tokio::test]
+async fn model_recipe_update_if_match_stale() -> Result<(), Box<dyn std::error::Error>> {
+    // -- FIXTURE
+    let db = TestDb::new().await?;
+    let utx = utx_from_token("123").await?;
+    let seed = Seed::create(&db, &utx).await?;
+    let update_data_fx = RecipePatch {
+        recipe_patch: RecipePatchInner {
+            title: PatchValue::Value("spaghetti carbonara".to_string()),
+            cid: PatchValue::Value(123),
+        },
+        ingredients: PatchValue::Absent,
+    };
+    let (recipe, _) =
+        RecipeMac::update_if_match(&db, &utx, seed.recipe.id, 1, update_data_fx.clone()).await?;
+    assert_eq!(2, recipe.version);
+
+    // -- ACTION
+    let result = RecipeMac::update_if_match(&db, &utx, seed.recipe.id, 1, update_data_fx).await;
+
+    // -- CHECK
+    match result {
+        Ok(_) => panic!("Should not succeed"),
+        Err(model::Error::VersionMismatch(typ, id)) => {
+            assert_eq!("recipes", typ);
+            assert_eq!(seed.recipe.id.to_string(), id);
+        }
+        other_error => panic!("Wrong Error: {:?}", other_error),
+    }
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn model_recipe_update_partial() -> Result<(), Box<dyn std::error::Error>> {
+    // -- FIXTURE
+    let db = TestDb::new().await?;
+    let utx = utx_from_token("123").await?;
+    let seed = Seed::create(&db, &utx).await?;
+    let update_data_fx = RecipePatch {
+        recipe_patch: RecipePatchInner {
+            cid: PatchValue::Null,
+            ..Default::default()
+        },
+        ingredients: PatchValue::Absent,
+    };
+
+    // -- ACTION
+    let (recipe, ingredients) =
+        RecipeMac::update(&db, &utx, seed.recipe.id, update_data_fx).await?;
+
+    // -- CHECK
+    assert_eq!("spaghetti", recipe.title, "title should be untouched");
+    assert_eq!(None, recipe.cid, "cid should be cleared");
+    assert_eq!(1, ingredients.len(), "ingredients should be untouched");
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn model_recipe_create_unknown_ingredient() -> Result<(), Box<dyn std::error::Error>> {
+    // -- FIXTURE
+    let db = TestDb::new().await?;
+    let utx = utx_from_token("123").await?;
+    Seed::create(&db, &utx).await?;
+    let data_fx = RecipePatch {
+        recipe_patch: RecipePatchInner {
+            title: PatchValue::Value("test - model_recipe_create_unknown_ingredient".to_string()),
+            cid: PatchValue::Absent,
+        },
+        ingredients: PatchValue::Value(vec![RecipeIngredientPatch {
+            ingredient_id: 999,
+            ingredient_name: "ghost pepper".to_string(),
+            quantity: "1 piece".to_string(),
+        }]),
+    };
+
+    // -- ACTION
+    let result = RecipeMac::create(&db, &utx, data_fx).await;
+
+    // -- CHECK
+    match result {
+        Ok(_) => panic!("Should not succeed"),
+        Err(model::Error::ValidationFailed(fields)) => {
+            assert_eq!(1, fields.len());
+            assert_eq!("ingredients[0].ingredient_id", fields[0].field);
+        }
+        other_error => panic!("Wrong Error: {:?}", other_error),
+    }
+    assert_eq!(1, RecipeMac::list(&db, &utx).await?.len());
+
+    Ok(())
+}
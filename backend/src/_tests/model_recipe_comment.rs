@@ -0,0 +1,89 @@
+use super::{render_markdown, CommentOrder, RecipeCommentMac, RecipeCommentPatch};
+use crate::{
+    model::{
+        self,
+        fixture::{Seed, TestDb},
+    },
+    security::utx_from_token,
+};
+
+#[test]
+fn model_recipe_comment_render_markdown_safe() {
+    // -- ACTION
+    let html = render_markdown(
+        "**great** <script>alert(1)</script> [link](javascript:alert(1)) <img src=x onerror=alert(1)>",
+    );
+
+    // -- CHECK
+    assert!(html.contains("<strong>great</strong>"), "{}", html);
+    assert!(!html.contains("<script"), "{}", html);
+    assert!(!html.contains("javascript:"), "{}", html);
+    assert!(!html.contains("onerror"), "{}", html);
+}
+
+#[tokio::test]
+async fn model_recipe_comment_threads() -> Result<(), Box<dyn std::error::Error>> {
+    // -- FIXTURE
+    let db = TestDb::new().await?;
+    let utx = utx_from_token("456").await?;
+    let recipe_id = Seed::create(&db, &utx).await?.recipe.id;
+    let first = RecipeCommentMac::create(&db, &utx, recipe_id, comment("first", None)).await?;
+    let reply =
+        RecipeCommentMac::create(&db, &utx, recipe_id, comment("reply", Some(first.id))).await?;
+    RecipeCommentMac::create(&db, &utx, recipe_id, comment("nested", Some(reply.id))).await?;
+    let second = RecipeCommentMac::create(&db, &utx, recipe_id, comment("second", None)).await?;
+
+    // -- ACTION
+    let newest = RecipeCommentMac::list(&db, &utx, recipe_id, CommentOrder::Newest, 1, 0).await?;
+    let oldest = RecipeCommentMac::list(&db, &utx, recipe_id, CommentOrder::Oldest, 10, 0).await?;
+
+    // -- CHECK
+    assert_eq!(1, newest.len());
+    assert_eq!(second.id, newest[0].comment.id);
+    assert_eq!(2, oldest.len());
+    assert_eq!(first.id, oldest[0].comment.id);
+    assert_eq!(2, oldest[0].replies.len());
+    assert_eq!(Some(reply.id), oldest[0].replies[1].parent_id);
+    assert_eq!(Some(first.id), oldest[0].replies[1].root_id);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn model_recipe_comment_moderation() -> Result<(), Box<dyn std::error::Error>> {
+    // -- FIXTURE - the seed recipe is owned by 123
+    let db = TestDb::new().await?;
+    let utx_owner = utx_from_token("123").await?;
+    let utx_author = utx_from_token("456").await?;
+    let recipe_id = Seed::create(&db, &utx_owner).await?.recipe.id;
+    let created =
+        RecipeCommentMac::create(&db, &utx_author, recipe_id, comment("spam", None)).await?;
+
+    // -- ACTION - the owner cannot edit it
+    let result =
+        RecipeCommentMac::update(&db, &utx_owner, recipe_id, created.id, comment("ham", None))
+            .await;
+
+    // -- CHECK
+    match result {
+        Ok(_) => panic!("Should not succeed"),
+        Err(model::Error::AccessDenied(typ, _)) => assert_eq!("recipe_comments", typ),
+        other_error => panic!("Wrong Error: {:?}", other_error),
+    }
+
+    // -- ACTION - but can delete it
+    let deleted = RecipeCommentMac::delete(&db, &utx_owner, recipe_id, created.id).await?;
+
+    // -- CHECK
+    assert!(deleted.deleted_at.is_some());
+    assert_eq!("", deleted.body);
+
+    Ok(())
+}
+
+fn comment(body: &str, parent_id: Option<i64>) -> RecipeCommentPatch {
+    RecipeCommentPatch {
+        body: body.to_string(),
+        parent_id,
+    }
+}
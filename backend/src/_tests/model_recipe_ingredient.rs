@@ -14,7 +14,7 @@ use crate::{
 async fn model_recipe_ingredient_create() -> Result<(), Box<dyn std::error::Error>> {
     // -- FIXTURE
     let db = init_db().await?;
-    let utx = utx_from_token(&db, "123").await?;
+    let utx = utx_from_token("123").await?;
 
     // Fetch the existing "tomato soup" recipe and "tomatoes" ingredient
     let recipe = RecipeMac::get(&db, &utx, 1000).await?; // Assuming "tomato soup" has id 1000
@@ -52,7 +52,7 @@ async fn model_recipe_ingredient_create() -> Result<(), Box<dyn std::error::Erro
 async fn model_recipe_ingredient_get() -> Result<(), Box<dyn std::error::Error>> {
     // -- FIXTURE
     let db = init_db().await?;
-    let utx = utx_from_token(&db, "123").await?;
+    let utx = utx_from_token("123").await?;
 
     // Fetch the existing "tomato soup" recipe and "tomatoes" ingredient
     let recipe = RecipeMac::get(&db, &utx, 1000).await?;
@@ -97,7 +97,7 @@ async fn model_recipe_ingredient_get() -> Result<(), Box<dyn std::error::Error>>
 async fn model_recipe_ingredient_get_wrong_id() -> Result<(), Box<dyn std::error::Error>> {
     // -- FIXTURE
     let db = init_db().await?;
-    let utx = utx_from_token(&db, "123").await?;
+    let utx = utx_from_token("123").await?;
 
     // -- ACTION
     let result = RecipeIngredientMac::get(&db, &utx, 9999, 9999).await;
@@ -112,7 +112,7 @@ async fn model_recipe_ingredient_get_wrong_id() -> Result<(), Box<dyn std::error
 async fn model_recipe_ingredient_update_ok() -> Result<(), Box<dyn std::error::Error>> {
     // -- FIXTURE
     let db = init_db().await?;
-    let utx = utx_from_token(&db, "123").await?;
+    let utx = utx_from_token("123").await?;
 
     // Fetch the existing "tomato soup" recipe and "tomatoes" ingredient
     let recipe = RecipeMac::get(&db, &utx, 1000).await?;
@@ -164,7 +164,7 @@ async fn model_recipe_ingredient_update_ok() -> Result<(), Box<dyn std::error::E
 async fn model_recipe_ingredient_list() -> Result<(), Box<dyn std::error::Error>> {
     // -- FIXTURE
     let db = init_db().await?;
-    let utx = utx_from_token(&db, "123").await?;
+    let utx = utx_from_token("123").await?;
 
     // Fetch the existing "tomato soup" recipe and "tomatoes" ingredient
     let recipe = RecipeMac::get(&db, &utx, 1000).await?;
@@ -194,7 +194,7 @@ async fn model_recipe_ingredient_list() -> Result<(), Box<dyn std::error::Error>
 async fn model_recipe_ingredient_delete_simple() -> Result<(), Box<dyn std::error::Error>> {
     // -- FIXTURE
     let db = init_db().await?;
-    let utx = utx_from_token(&db, "123").await?;
+    let utx = utx_from_token("123").await?;
 
     // Fetch the existing "tomato soup" recipe and "tomatoes" ingredient
     let recipe = RecipeMac::get(&db, &utx, 1000).await?;
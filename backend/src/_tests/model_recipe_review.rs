@@ -0,0 +1,138 @@
+use super::{RecipeReviewMac, RecipeReviewPatch};
+use crate::{
+    model::{
+        self,
+        fixture::{Seed, TestDb},
+        RecipeMac, RecipeSort,
+    },
+    security::utx_from_token,
+};
+
+#[tokio::test]
+async fn model_recipe_review_upsert() -> Result<(), Box<dyn std::error::Error>> {
+    // -- FIXTURE
+    let db = TestDb::new().await?;
+    let utx = utx_from_token("123").await?;
+    let seed = Seed::create(&db, &utx).await?;
+    let data_fx = RecipeReviewPatch {
+        rating: 4,
+        body: " Nice and easy ".to_string(),
+    };
+
+    // -- ACTION - create, then edit
+    let created = RecipeReviewMac::upsert(&db, &utx, seed.recipe.id, data_fx).await?;
+    let edited = RecipeReviewMac::upsert(
+        &db,
+        &utx,
+        seed.recipe.id,
+        RecipeReviewPatch {
+            rating: 2,
+            body: "Too salty".to_string(),
+        },
+    )
+    .await?;
+
+    // -- CHECK - one review per user, edited in place
+    assert_eq!("Nice and easy", created.body);
+    assert_eq!(created.id, edited.id);
+    assert_eq!(2, edited.rating);
+    assert_eq!(
+        1,
+        RecipeReviewMac::list(&db, &utx, seed.recipe.id)
+            .await?
+            .len()
+    );
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn model_recipe_review_rating_aggregate() -> Result<(), Box<dyn std::error::Error>> {
+    // -- FIXTURE
+    let db = TestDb::new().await?;
+    let utx_123 = utx_from_token("123").await?;
+    let seed = Seed::create(&db, &utx_123).await?;
+    let utx_456 = utx_from_token("456").await?;
+    let (unrated, _) = RecipeMac::create(&db, &utx_123, Default::default()).await?;
+
+    // -- ACTION
+    RecipeReviewMac::upsert(&db, &utx_123, seed.recipe.id, review(5)).await?;
+    RecipeReviewMac::upsert(&db, &utx_456, seed.recipe.id, review(2)).await?;
+
+    // -- CHECK - get
+    let (recipe, _) = RecipeMac::get(&db, &utx_123, seed.recipe.id).await?;
+    assert_eq!(Some(3.5), recipe.rating_avg);
+    assert_eq!(2, recipe.rating_count);
+    assert_eq!(
+        1, recipe.version,
+        "a review should not bump the recipe version"
+    );
+
+    // -- CHECK - list by rating, unrated last
+    let recipes = RecipeMac::list_sorted(&db, &utx_123, RecipeSort::Rating).await?;
+    assert_eq!(seed.recipe.id, recipes[0].0.id);
+    assert_eq!(unrated.id, recipes[1].0.id);
+    assert_eq!(None, recipes[1].0.rating_avg);
+
+    // -- ACTION - delete
+    RecipeReviewMac::delete(&db, &utx_456, seed.recipe.id).await?;
+
+    // -- CHECK
+    let (recipe, _) = RecipeMac::get(&db, &utx_123, seed.recipe.id).await?;
+    assert_eq!(Some(5.0), recipe.rating_avg);
+    assert_eq!(1, recipe.rating_count);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn model_recipe_review_invalid_rating() -> Result<(), Box<dyn std::error::Error>> {
+    // -- FIXTURE
+    let db = TestDb::new().await?;
+    let utx = utx_from_token("123").await?;
+    let seed = Seed::create(&db, &utx).await?;
+
+    // -- ACTION
+    let result = RecipeReviewMac::upsert(&db, &utx, seed.recipe.id, review(6)).await;
+
+    // -- CHECK
+    match result {
+        Ok(_) => panic!("Should not succeed"),
+        Err(model::Error::ValidationFailed(fields)) => {
+            assert_eq!("rating", fields[0].field);
+            assert_eq!("must be between 1 and 5", fields[0].reason);
+        }
+        other_error => panic!("Wrong Error: {:?}", other_error),
+    }
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn model_recipe_review_wrong_recipe() -> Result<(), Box<dyn std::error::Error>> {
+    // -- FIXTURE
+    let db = TestDb::new().await?;
+    let utx = utx_from_token("123").await?;
+
+    // -- ACTION
+    let result = RecipeReviewMac::upsert(&db, &utx, 999, review(3)).await;
+
+    // -- CHECK
+    match result {
+        Ok(_) => panic!("Should not succeed"),
+        Err(model::Error::EntityNotFound(typ, id)) => {
+            assert_eq!("recipes", typ);
+            assert_eq!(999.to_string(), id);
+        }
+        other_error => panic!("Wrong Error: {:?}", other_error),
+    }
+
+    Ok(())
+}
+
+fn review(rating: i8) -> RecipeReviewPatch {
+    RecipeReviewPatch {
+        rating,
+        body: String::new(),
+    }
+}
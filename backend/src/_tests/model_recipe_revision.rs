@@ -0,0 +1,173 @@
+use super::{RecipeIngredientPatch, RecipeRevisionMac};
+use crate::{
+    model::{
+        self, db,
+        fixture::{Seed, TestDb},
+        PatchValue, RecipeMac, RecipePatch, RecipePatchInner,
+    },
+    security::utx_from_token,
+};
+
+#[tokio::test]
+async fn model_recipe_revision_create_and_update() -> Result<(), Box<dyn std::error::Error>> {
+    // -- FIXTURE
+    let db = TestDb::new().await?;
+    let utx = utx_from_token("123").await?;
+    let seed = Seed::create(&db, &utx).await?;
+    let data_fx = RecipePatch {
+        recipe_patch: RecipePatchInner {
+            title: PatchValue::Value("test - model_recipe_revision 1".to_string()),
+            cid: PatchValue::Value(123),
+        },
+        ingredients: PatchValue::Value(vec![RecipeIngredientPatch {
+            ingredient_id: seed.ingredient.id,
+            ingredient_name: "tomatoes".to_string(),
+            quantity: "2 cups".to_string(),
+        }]),
+    };
+    let (recipe_fx, _) = RecipeMac::create(&db, &utx, data_fx).await?;
+
+    // -- ACTION
+    let utx_456 = utx_from_token("456").await?;
+    let update_data_fx = RecipePatch {
+        recipe_patch: RecipePatchInner {
+            title: PatchValue::Value("test - model_recipe_revision 2".to_string()),
+            cid: PatchValue::Value(123),
+        },
+        ingredients: PatchValue::Absent,
+    };
+    RecipeMac::update(&db, &utx_456, recipe_fx.id, update_data_fx).await?;
+
+    // -- CHECK
+    let revisions = RecipeRevisionMac::list(&db, &utx, recipe_fx.id).await?;
+    assert_eq!(2, revisions.len());
+    assert_eq!(2, revisions[0].rev);
+    assert_eq!("test - model_recipe_revision 2", revisions[0].title);
+    assert_eq!(456, revisions[0].cid);
+    assert_eq!(1, revisions[1].rev);
+    assert_eq!("test - model_recipe_revision 1", revisions[1].title);
+    assert_eq!(123, revisions[1].cid);
+    assert_eq!(1, revisions[1].ingredients.len());
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn model_recipe_revision_update_seed_keeps_baseline() -> Result<(), Box<dyn std::error::Error>>
+{
+    // -- FIXTURE - inserted like the dev seed, so without any revision
+    let db = TestDb::new().await?;
+    let utx = utx_from_token("123").await?;
+    let result = sqlx::query("INSERT INTO recipes (title, cid) VALUES ('spaghetti', 123)")
+        .execute(&*db)
+        .await?;
+    let recipe_id = db::last_insert_id(&result);
+    let update_data_fx = RecipePatch {
+        recipe_patch: RecipePatchInner {
+            title: PatchValue::Value("spaghetti bolognese".to_string()),
+            cid: PatchValue::Value(123),
+        },
+        ingredients: PatchValue::Absent,
+    };
+
+    // -- ACTION
+    RecipeMac::update(&db, &utx, recipe_id, update_data_fx).await?;
+
+    // -- CHECK
+    let revisions = RecipeRevisionMac::list(&db, &utx, recipe_id).await?;
+    assert_eq!(2, revisions.len());
+    assert_eq!("spaghetti", revisions[1].title);
+    assert_eq!("spaghetti bolognese", revisions[0].title);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn model_recipe_revision_diff() -> Result<(), Box<dyn std::error::Error>> {
+    // -- FIXTURE
+    let db = TestDb::new().await?;
+    let utx = utx_from_token("123").await?;
+    let seed = Seed::create(&db, &utx).await?;
+    let update_data_fx = RecipePatch {
+        recipe_patch: RecipePatchInner {
+            title: PatchValue::Value("spaghetti bolognese".to_string()),
+            cid: PatchValue::Value(123),
+        },
+        ingredients: PatchValue::Value(vec![RecipeIngredientPatch {
+            ingredient_id: seed.ingredient.id,
+            ingredient_name: "tomatoes".to_string(),
+            quantity: "400 g".to_string(),
+        }]),
+    };
+    RecipeMac::update(&db, &utx, seed.recipe.id, update_data_fx).await?;
+
+    // -- ACTION
+    let diff = RecipeRevisionMac::diff(&db, &utx, seed.recipe.id, 1, 2).await?;
+
+    // -- CHECK
+    let fields: Vec<&str> = diff.changes.iter().map(|c| c.field.as_str()).collect();
+    assert_eq!(
+        vec![
+            "title",
+            format!("ingredients[{}].quantity", seed.ingredient.id).as_str()
+        ],
+        fields
+    );
+    assert_eq!("spaghetti", diff.changes[0].from);
+    assert_eq!("spaghetti bolognese", diff.changes[0].to);
+    assert_eq!("200 g", diff.changes[1].from);
+    assert_eq!("400 g", diff.changes[1].to);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn model_recipe_revision_restore() -> Result<(), Box<dyn std::error::Error>> {
+    // -- FIXTURE
+    let db = TestDb::new().await?;
+    let utx = utx_from_token("123").await?;
+    let seed = Seed::create(&db, &utx).await?;
+    let update_data_fx = RecipePatch {
+        recipe_patch: RecipePatchInner {
+            title: PatchValue::Value("spaghetti bolognese".to_string()),
+            cid: PatchValue::Value(123),
+        },
+        ingredients: PatchValue::Value(vec![]),
+    };
+    RecipeMac::update(&db, &utx, seed.recipe.id, update_data_fx).await?;
+
+    // -- ACTION
+    let (recipe, ingredients) = RecipeRevisionMac::restore(&db, &utx, seed.recipe.id, 1).await?;
+
+    // -- CHECK
+    assert_eq!("spaghetti", recipe.title);
+    assert_eq!(1, ingredients.len());
+    assert_eq!("200 g", ingredients[0].quantity);
+    let revisions = RecipeRevisionMac::list(&db, &utx, seed.recipe.id).await?;
+    assert_eq!(3, revisions[0].rev);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn model_recipe_revision_get_wrong_rev() -> Result<(), Box<dyn std::error::Error>> {
+    // -- FIXTURE
+    let db = TestDb::new().await?;
+    let utx = utx_from_token("123").await?;
+    let seed = Seed::create(&db, &utx).await?;
+
+    // -- ACTION
+    let result = RecipeRevisionMac::get(&db, &utx, seed.recipe.id, 99).await;
+
+    // -- CHECK
+    match result {
+        Ok(_) => panic!("Should not succeed"),
+        Err(model::Error::EntityNotFound(typ, id)) => {
+            assert_eq!("recipe_revisions", typ);
+            assert_eq!(format!("{}@99", seed.recipe.id), id);
+        }
+        other_error => panic!("Wrong Error: {:?}", other_error),
+    }
+
+    Ok(())
+}
@@ -0,0 +1,103 @@
+use super::{ShareLinkMac, ShareLinkPatch, ShareTyp, SharedView};
+use crate::{
+    model::{
+        self,
+        fixture::{Seed, TestDb},
+        CollectionMac,
+    },
+    security::utx_from_token,
+};
+use chrono::{Duration, Utc};
+
+#[tokio::test]
+async fn model_share_link_view_recipe() -> Result<(), Box<dyn std::error::Error>> {
+    // -- FIXTURE
+    let db = TestDb::new().await?;
+    let utx = utx_from_token("123").await?;
+    let seed = Seed::create(&db, &utx).await?;
+    let link = ShareLinkMac::create(&db, &utx, recipe_link(seed.recipe.id, None)).await?;
+
+    // -- ACTION
+    let shared = ShareLinkMac::view(&db, &link.token).await?;
+    ShareLinkMac::view(&db, &link.token).await?;
+
+    // -- CHECK
+    assert_eq!(43, link.token.len());
+    match shared {
+        SharedView::Recipe(recipe) => {
+            assert_eq!("spaghetti", recipe.title);
+            assert_eq!("200 g", recipe.ingredients[0].quantity);
+        }
+        other => panic!("Wrong view: {:?}", other),
+    }
+    assert_eq!(2, ShareLinkMac::list(&db, &utx).await?[0].view_count);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn model_share_link_view_collection() -> Result<(), Box<dyn std::error::Error>> {
+    // -- FIXTURE
+    let db = TestDb::new().await?;
+    let utx = utx_from_token("123").await?;
+    let seed = Seed::create(&db, &utx).await?;
+    let (collection, _) = CollectionMac::create(&db, &utx, Default::default()).await?;
+    CollectionMac::add_recipe(&db, &utx, collection.id, seed.recipe.id).await?;
+    let data_fx = ShareLinkPatch {
+        typ: ShareTyp::Collection,
+        target_id: collection.id,
+        expires_at: None,
+    };
+    let link = ShareLinkMac::create(&db, &utx, data_fx).await?;
+
+    // -- ACTION
+    let shared = ShareLinkMac::view(&db, &link.token).await?;
+
+    // -- CHECK
+    match shared {
+        SharedView::Collection(collection) => {
+            assert_eq!(1, collection.recipes.len());
+            assert_eq!("spaghetti", collection.recipes[0].title);
+        }
+        other => panic!("Wrong view: {:?}", other),
+    }
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn model_share_link_revoked_and_expired() -> Result<(), Box<dyn std::error::Error>> {
+    // -- FIXTURE
+    let db = TestDb::new().await?;
+    let utx = utx_from_token("123").await?;
+    let seed = Seed::create(&db, &utx).await?;
+    let revoked = ShareLinkMac::create(&db, &utx, recipe_link(seed.recipe.id, None)).await?;
+    let revoked = ShareLinkMac::revoke(&db, &utx, revoked.id).await?;
+    let expired = ShareLinkMac::create(
+        &db,
+        &utx,
+        recipe_link(seed.recipe.id, Some(Utc::now() + Duration::seconds(1))),
+    )
+    .await?;
+    tokio::time::sleep(std::time::Duration::from_secs(2)).await;
+
+    // -- ACTION & CHECK
+    assert!(revoked.revoked_at.is_some());
+    for token in [revoked.token, expired.token] {
+        match ShareLinkMac::view(&db, &token).await {
+            Ok(_) => panic!("Should not succeed"),
+            Err(model::Error::EntityNotFound(typ, _)) => assert_eq!("share_links", typ),
+            Err(other_error) => panic!("Wrong Error: {:?}", other_error),
+        }
+    }
+
+    Ok(())
+}
+
+fn recipe_link(target_id: i64, expires_at: Option<chrono::DateTime<Utc>>) -> ShareLinkPatch {
+    ShareLinkPatch {
+        typ: ShareTyp::Recipe,
+        target_id,
+        expires_at,
+    }
+}
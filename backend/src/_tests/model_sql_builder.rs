@@ -0,0 +1,38 @@
+use super::UpdateBuilder;
+use crate::model::{self, PatchValue};
+
+#[test]
+fn model_sql_builder_update_sql() -> Result<(), Box<dyn std::error::Error>> {
+    // -- ACTION
+    let sb = UpdateBuilder::new("ingredients")
+        .patch("name", PatchValue::Value("basil".to_string()))?
+        .patch("quantity", PatchValue::<String>::Absent)?
+        .patch_nullable("cid", PatchValue::<i64>::Null)
+        .set_raw("version", "version + 1")
+        .and_where_eq("id", 1000)
+        .and_where_raw("deleted_at IS NULL");
+
+    // -- CHECK
+    assert_eq!(
+        "UPDATE ingredients SET name = ?, cid = ?, version = version + 1 WHERE id = ? AND deleted_at IS NULL",
+        sb.sql()
+    );
+
+    Ok(())
+}
+
+#[test]
+fn model_sql_builder_patch_null_not_allowed() {
+    // -- ACTION
+    let result = UpdateBuilder::new("ingredients").patch("name", PatchValue::<String>::Null);
+
+    // -- CHECK
+    match result {
+        Ok(_) => panic!("Should not succeed"),
+        Err(model::Error::PatchNullNotAllowed(typ, field)) => {
+            assert_eq!("ingredients", typ);
+            assert_eq!("name", field);
+        }
+        Err(other_error) => panic!("Wrong Error: {:?}", other_error),
+    }
+}
@@ -0,0 +1,122 @@
+use super::TrashMac;
+use crate::{
+    model::{
+        self,
+        fixture::{Seed, TestDb},
+        IngredientMac, RecipeMac,
+    },
+    security::utx_from_token,
+};
+use chrono::{Duration, Utc};
+
+#[tokio::test]
+async fn model_trash_delete_ingredient_hides_links() -> Result<(), Box<dyn std::error::Error>> {
+    // -- FIXTURE
+    let db = TestDb::new().await?;
+    let utx = utx_from_token("123").await?;
+    let seed = Seed::create(&db, &utx).await?;
+
+    // -- ACTION
+    IngredientMac::delete(&db, &utx, seed.ingredient.id).await?;
+
+    // -- CHECK
+    let (recipe, ingredients) = RecipeMac::get(&db, &utx, seed.recipe.id).await?;
+    assert_eq!("spaghetti", recipe.title);
+    assert_eq!(0, ingredients.len(), "trashed ingredient should be hidden");
+    let items = TrashMac::list(&db, &utx).await?;
+    assert_eq!(1, items.len());
+    assert_eq!("ingredient", items[0].typ);
+    assert_eq!(seed.ingredient.id, items[0].id);
+    assert_eq!("tomatoes", items[0].label);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn model_trash_restore_ingredient_with_links() -> Result<(), Box<dyn std::error::Error>> {
+    // -- FIXTURE
+    let db = TestDb::new().await?;
+    let utx = utx_from_token("123").await?;
+    let seed = Seed::create(&db, &utx).await?;
+    IngredientMac::delete(&db, &utx, seed.ingredient.id).await?;
+
+    // -- ACTION
+    let ingredient = IngredientMac::restore(&db, &utx, seed.ingredient.id).await?;
+
+    // -- CHECK
+    assert_eq!("tomatoes", ingredient.name);
+    let (_, ingredients) = RecipeMac::get(&db, &utx, seed.recipe.id).await?;
+    assert_eq!(1, ingredients.len());
+    assert_eq!("200 g", ingredients[0].quantity);
+    assert_eq!(0, TrashMac::list(&db, &utx).await?.len());
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn model_trash_restore_recipe() -> Result<(), Box<dyn std::error::Error>> {
+    // -- FIXTURE
+    let db = TestDb::new().await?;
+    let utx = utx_from_token("123").await?;
+    let seed = Seed::create(&db, &utx).await?;
+    RecipeMac::delete(&db, &utx, seed.recipe.id).await?;
+    assert_eq!(0, RecipeMac::list(&db, &utx).await?.len());
+
+    // -- ACTION
+    let (recipe, ingredients) = RecipeMac::restore(&db, &utx, seed.recipe.id).await?;
+
+    // -- CHECK
+    assert_eq!("spaghetti", recipe.title);
+    assert_eq!(1, ingredients.len());
+    assert_eq!(1, RecipeMac::list(&db, &utx).await?.len());
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn model_trash_restore_not_in_trash() -> Result<(), Box<dyn std::error::Error>> {
+    // -- FIXTURE
+    let db = TestDb::new().await?;
+    let utx = utx_from_token("123").await?;
+    let seed = Seed::create(&db, &utx).await?;
+
+    // -- ACTION
+    let result = RecipeMac::restore(&db, &utx, seed.recipe.id).await;
+
+    // -- CHECK
+    match result {
+        Ok(_) => panic!("Should not succeed"),
+        Err(model::Error::EntityNotFound(typ, id)) => {
+            assert_eq!("recipes", typ);
+            assert_eq!(seed.recipe.id.to_string(), id);
+        }
+        other_error => panic!("Wrong Error: {:?}", other_error),
+    }
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn model_trash_purge() -> Result<(), Box<dyn std::error::Error>> {
+    // -- FIXTURE
+    let db = TestDb::new().await?;
+    let utx = utx_from_token("123").await?;
+    let seed = Seed::create(&db, &utx).await?;
+    RecipeMac::delete(&db, &utx, seed.recipe.id).await?;
+    IngredientMac::delete(&db, &utx, seed.ingredient.id).await?;
+
+    // -- ACTION - nothing is old enough yet
+    let count = TrashMac::purge(&db, Utc::now() - Duration::days(30)).await?;
+    assert_eq!(0, count);
+
+    // -- ACTION - everything is past retention
+    let count = TrashMac::purge(&db, Utc::now() + Duration::seconds(5)).await?;
+
+    // -- CHECK
+    assert_eq!(2, count);
+    assert_eq!(0, TrashMac::list(&db, &utx).await?.len());
+    let result = RecipeMac::restore(&db, &utx, seed.recipe.id).await;
+    assert!(matches!(result, Err(model::Error::EntityNotFound(_, _))));
+
+    Ok(())
+}
@@ -0,0 +1,104 @@
+use crate::{
+    model::{self, fixture::TestDb},
+    security::{utx_from_token, utx_system},
+};
+
+use super::{UserMac, UserPatch};
+
+fn user_fx(username: &str, password: &str) -> UserPatch {
+    UserPatch {
+        username: username.to_string(),
+        password: password.to_string(),
+    }
+}
+
+#[tokio::test]
+async fn model_user_create_and_list() -> Result<(), Box<dyn std::error::Error>> {
+    // -- FIXTURE
+    let db = TestDb::new().await?;
+    let utx = utx_system();
+
+    // -- ACTION
+    let user = UserMac::create(&db, &utx, user_fx(" alice ", "correct horse")).await?;
+    let users = UserMac::list(&db, &utx).await?;
+
+    // -- CHECK - the password is only kept as a hash
+    assert!(user.id >= 1000, "ID should be >= 1000");
+    assert_eq!("alice", user.username);
+    assert_eq!(1, users.len());
+    assert!(UserMac::check_password(&db, "alice", "correct horse").await?);
+    assert!(!UserMac::check_password(&db, "alice", "wrong horse").await?);
+    let (hash,) = sqlx::query_as::<_, (String,)>("SELECT password_hash FROM users")
+        .fetch_one(&*db)
+        .await?;
+    assert!(hash.starts_with("$argon2"), "{}", hash);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn model_user_create_invalid() -> Result<(), Box<dyn std::error::Error>> {
+    // -- FIXTURE
+    let db = TestDb::new().await?;
+    let utx = utx_system();
+    UserMac::create(&db, &utx, user_fx("alice", "correct horse")).await?;
+
+    // -- ACTION
+    let short = UserMac::create(&db, &utx, user_fx("bob", "short")).await;
+    let taken = UserMac::create(&db, &utx, user_fx("alice", "another horse")).await;
+
+    // -- CHECK
+    match short {
+        Err(model::Error::ValidationFailed(fields)) => assert_eq!("password", fields[0].field),
+        other => panic!("expected ValidationFailed, got {:?}", other),
+    }
+    match taken {
+        Err(model::Error::ValidationFailed(fields)) => {
+            assert_eq!("username", fields[0].field);
+            assert_eq!("is already taken", fields[0].reason);
+        }
+        other => panic!("expected ValidationFailed, got {:?}", other),
+    }
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn model_user_reset_password() -> Result<(), Box<dyn std::error::Error>> {
+    // -- FIXTURE
+    let db = TestDb::new().await?;
+    let utx = utx_system();
+    UserMac::create(&db, &utx, user_fx("alice", "correct horse")).await?;
+
+    // -- ACTION
+    UserMac::reset_password(&db, &utx, user_fx("alice", "battery staple")).await?;
+    let unknown = UserMac::reset_password(&db, &utx, user_fx("bob", "battery staple")).await;
+
+    // -- CHECK
+    assert!(UserMac::check_password(&db, "alice", "battery staple").await?);
+    assert!(!UserMac::check_password(&db, "alice", "correct horse").await?);
+    assert!(matches!(
+        unknown,
+        Err(model::Error::EntityNotFound("users", _))
+    ));
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn model_user_not_admin() -> Result<(), Box<dyn std::error::Error>> {
+    // -- FIXTURE
+    let db = TestDb::new().await?;
+    let utx = utx_from_token("123").await?;
+
+    // -- ACTION
+    let result = UserMac::list(&db, &utx).await;
+
+    // -- CHECK
+    assert!(matches!(
+        result,
+        Err(model::Error::AccessDenied("users", _))
+    ));
+
+    Ok(())
+}
@@ -0,0 +1,121 @@
+use super::{FieldError, Validate};
+use crate::model::{
+    self, IngredientPatch, PatchValue, RecipeIngredientPatch, RecipePatch, RecipePatchInner,
+};
+
+#[test]
+fn model_validate_normalizes_text() -> Result<(), Box<dyn std::error::Error>> {
+    // -- FIXTURE - "café" with a combining acute accent, surrounded by blanks
+    let mut data = IngredientPatch {
+        name: PatchValue::Value("  cafe\u{301} \n".to_string()),
+        quantity: PatchValue::Absent,
+    };
+
+    // -- ACTION
+    data.validate()?;
+
+    // -- CHECK
+    assert_eq!(PatchValue::Value("caf\u{e9}".to_string()), data.name);
+    assert_eq!(PatchValue::Absent, data.quantity);
+
+    Ok(())
+}
+
+#[test]
+fn model_validate_ingredient_fields() -> Result<(), Box<dyn std::error::Error>> {
+    // -- FIXTURE
+    let mut data = IngredientPatch {
+        name: PatchValue::Value("   ".to_string()),
+        quantity: PatchValue::Value("x".repeat(51)),
+    };
+
+    // -- ACTION
+    let result = data.validate();
+
+    // -- CHECK
+    match result {
+        Ok(_) => panic!("Should not succeed"),
+        Err(model::Error::ValidationFailed(fields)) => assert_eq!(
+            vec![
+                field_error("name", "must not be empty"),
+                field_error("quantity", "must be at most 50 characters"),
+            ],
+            fields
+        ),
+        other_error => panic!("Wrong Error: {:?}", other_error),
+    }
+
+    Ok(())
+}
+
+#[test]
+fn model_validate_recipe_nested_fields() -> Result<(), Box<dyn std::error::Error>> {
+    // -- FIXTURE
+    let ingredient = RecipeIngredientPatch {
+        ingredient_id: 1000,
+        ingredient_name: "tomatoes".to_string(),
+        quantity: "2 cups".to_string(),
+    };
+    let mut data = RecipePatch {
+        recipe_patch: RecipePatchInner {
+            title: PatchValue::Null,
+            cid: PatchValue::Null,
+        },
+        ingredients: PatchValue::Value(vec![
+            ingredient.clone(),
+            RecipeIngredientPatch {
+                quantity: "".to_string(),
+                ..ingredient
+            },
+            RecipeIngredientPatch {
+                ingredient_id: 0,
+                ingredient_name: "basil".to_string(),
+                quantity: "1 leaf".to_string(),
+            },
+        ]),
+    };
+
+    // -- ACTION
+    let result = data.validate();
+
+    // -- CHECK
+    match result {
+        Ok(_) => panic!("Should not succeed"),
+        Err(model::Error::ValidationFailed(fields)) => assert_eq!(
+            vec![
+                field_error("recipe_patch.title", "must not be null"),
+                field_error("ingredients[1].quantity", "must not be empty"),
+                field_error("ingredients[1].ingredient_id", "is listed more than once"),
+                field_error("ingredients[2].ingredient_id", "must be a positive id"),
+            ],
+            fields
+        ),
+        other_error => panic!("Wrong Error: {:?}", other_error),
+    }
+
+    Ok(())
+}
+
+#[test]
+fn model_validate_length_in_chars() -> Result<(), Box<dyn std::error::Error>> {
+    // -- FIXTURE - 255 two-byte characters
+    let mut data = RecipePatchInner {
+        title: PatchValue::Value("é".repeat(255)),
+        cid: PatchValue::Absent,
+    };
+
+    // -- ACTION
+    data.validate()?;
+
+    // -- CHECK
+    assert_eq!(255, data.title.unwrap().chars().count());
+
+    Ok(())
+}
+
+fn field_error(field: &str, reason: &str) -> FieldError {
+    FieldError {
+        field: field.to_string(),
+        reason: reason.to_string(),
+    }
+}
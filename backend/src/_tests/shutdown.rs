@@ -0,0 +1,22 @@
+use super::Shutdown;
+use std::time::Duration;
+
+#[tokio::test]
+async fn shutdown_trigger_wakes_clones() -> Result<(), Box<dyn std::error::Error>> {
+    // -- FIXTURE
+    let shutdown = Shutdown::new();
+    let waiter = shutdown.clone();
+    let waiting = tokio::spawn(async move { waiter.wait().await });
+    assert!(!shutdown.is_triggered());
+
+    // -- ACTION
+    shutdown.trigger();
+
+    // -- CHECK
+    tokio::time::timeout(Duration::from_secs(1), waiting).await??;
+    assert!(shutdown.is_triggered());
+    // already triggered, resolves right away
+    tokio::time::timeout(Duration::from_secs(1), shutdown.wait()).await?;
+
+    Ok(())
+}
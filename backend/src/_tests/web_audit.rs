@@ -0,0 +1,26 @@
+use crate::model::fixture::TestDb;
+use crate::web::audit::audit_rest_filters;
+use crate::web::handle_rejection;
+use anyhow::Result;
+use warp::Filter;
+
+#[tokio::test]
+async fn web_audit_list_forbidden() -> Result<()> {
+    // -- FIXTURE
+    let test_db = TestDb::new().await?;
+    let db = test_db.arc();
+    let apis = audit_rest_filters("api", db.clone()).recover(handle_rejection);
+
+    // -- ACTION
+    let response = warp::test::request()
+        .method("GET")
+        .path("/api/admin/audit?typ=recipe")
+        .header("X-Auth-Token", "123")
+        .reply(&apis)
+        .await;
+
+    // -- CHECK
+    assert_eq!(response.status(), 403);
+
+    Ok(())
+}
@@ -0,0 +1,85 @@
+use super::bind;
+use crate::shutdown::Shutdown;
+use anyhow::Result;
+use std::time::{Duration, Instant};
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::TcpStream;
+use warp::Filter;
+
+/// A route taking `delay` to answer, to have a request in flight when shutting down.
+fn slow_routes(
+    delay: Duration,
+) -> impl Filter<Extract = impl warp::Reply, Error = warp::Rejection> + Clone {
+    warp::path("slow").and_then(move || async move {
+        tokio::time::sleep(delay).await;
+        Ok::<_, warp::Rejection>("done")
+    })
+}
+
+async fn send_get(stream: &mut TcpStream, path: &str) -> Result<()> {
+    let req = format!(
+        "GET {} HTTP/1.1\r\nHost: localhost\r\nConnection: close\r\n\r\n",
+        path
+    );
+    stream.write_all(req.as_bytes()).await?;
+    Ok(())
+}
+
+#[tokio::test]
+async fn web_bind_shutdown_drains() -> Result<()> {
+    // -- FIXTURE
+    let shutdown = Shutdown::new();
+    let svc = warp::service(slow_routes(Duration::from_millis(300)));
+    let (addr, server) = bind(
+        svc,
+        ([127, 0, 0, 1], 0).into(),
+        shutdown.clone(),
+        Duration::from_secs(5),
+    )?;
+    let server = tokio::spawn(server);
+    let mut stream = TcpStream::connect(addr).await?;
+    send_get(&mut stream, "/slow").await?;
+    tokio::time::sleep(Duration::from_millis(50)).await;
+
+    // -- ACTION
+    shutdown.trigger();
+
+    // -- CHECK - the in-flight request completes
+    let mut response = String::new();
+    stream.read_to_string(&mut response).await?;
+    assert!(response.starts_with("HTTP/1.1 200"), "{}", response);
+    assert!(response.ends_with("done"));
+
+    // -- CHECK - then the server ends and stops accepting
+    tokio::time::timeout(Duration::from_secs(2), server).await???;
+    assert!(TcpStream::connect(addr).await.is_err());
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn web_bind_shutdown_drain_timeout() -> Result<()> {
+    // -- FIXTURE
+    let shutdown = Shutdown::new();
+    let svc = warp::service(slow_routes(Duration::from_secs(30)));
+    let (addr, server) = bind(
+        svc,
+        ([127, 0, 0, 1], 0).into(),
+        shutdown.clone(),
+        Duration::from_millis(100),
+    )?;
+    let server = tokio::spawn(server);
+    let mut stream = TcpStream::connect(addr).await?;
+    send_get(&mut stream, "/slow").await?;
+    tokio::time::sleep(Duration::from_millis(50)).await;
+
+    // -- ACTION
+    let start = Instant::now();
+    shutdown.trigger();
+
+    // -- CHECK - the server gives up on the stuck request
+    tokio::time::timeout(Duration::from_secs(2), server).await???;
+    assert!(start.elapsed() < Duration::from_secs(2));
+
+    Ok(())
+}
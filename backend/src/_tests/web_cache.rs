@@ -0,0 +1,62 @@
+use super::{is_fingerprinted, with_static_cache};
+use anyhow::Result;
+use warp::Filter;
+
+const YEAR: u64 = 365 * 24 * 3600;
+
+#[tokio::test]
+async fn web_cache_static() -> Result<()> {
+    // -- FIXTURE - a site with its files, and a not found
+    let site = warp::path::tail().and_then(|tail: warp::path::Tail| async move {
+        match tail.as_str() {
+            "missing.3f2a9c1b.js" => Err(warp::reject::not_found()),
+            _ => Ok("content"),
+        }
+    });
+    let routes = with_static_cache(YEAR, site);
+    let cache_control = |path: &'static str| {
+        let routes = routes.clone();
+        async move {
+            let res = warp::test::request().path(path).reply(&routes).await;
+            res.headers()
+                .get("Cache-Control")
+                .map(|value| value.to_str().unwrap().to_string())
+        }
+    };
+
+    // -- ACTION & CHECK
+    let immutable = Some(format!("public, max-age={}, immutable", YEAR));
+    assert_eq!(immutable, cache_control("/js/app-bundle.3f2a9c1b.js").await);
+    assert_eq!(immutable, cache_control("/css/main-BqXz12aF.css").await);
+    assert_eq!(Some("no-cache".to_string()), cache_control("/").await);
+    assert_eq!(
+        Some("no-cache".to_string()),
+        cache_control("/index.html").await
+    );
+    assert_eq!(
+        Some("no-cache".to_string()),
+        cache_control("/js/app-bundle.js").await
+    );
+
+    // -- CHECK - a max age of 0 revalidates all
+    let routes = with_static_cache(0, site);
+    let res = warp::test::request()
+        .path("/js/app-bundle.3f2a9c1b.js")
+        .reply(&routes)
+        .await;
+    assert_eq!("no-cache", res.headers()["Cache-Control"]);
+
+    Ok(())
+}
+
+#[test]
+fn web_cache_fingerprint() {
+    assert!(is_fingerprinted("/js/app.3f2a9c1b.js"));
+    assert!(is_fingerprinted("/js/index-BqXz12aF.js"));
+    assert!(is_fingerprinted("/css/main.3f2a9c1b.min.css"));
+    assert!(!is_fingerprinted("/js/app-bundle.js"));
+    assert!(!is_fingerprinted("/3f2a9c1b.js"));
+    assert!(!is_fingerprinted("/img/logo.png"));
+    assert!(!is_fingerprinted("/js/app.3f2a9c.js"));
+    assert!(!is_fingerprinted("/"));
+}
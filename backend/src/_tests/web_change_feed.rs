@@ -0,0 +1,47 @@
+use crate::model::fixture::{Seed, TestDb};
+use crate::security::utx_from_token;
+use crate::web::change_feed::change_feed_rest_filters;
+use crate::web::handle_rejection;
+use anyhow::Result;
+use serde_json::json;
+use warp::Filter;
+
+#[tokio::test]
+async fn web_change_feed_push_and_pull() -> Result<()> {
+    // -- FIXTURE
+    let test_db = TestDb::new().await?;
+    let db = test_db.arc();
+    let seed = Seed::create(&db, &utx_from_token("123").await?).await?;
+    let apis = change_feed_rest_filters("api", db.clone()).recover(handle_rejection);
+
+    // -- ACTION - push an update without base version
+    let response = warp::test::request()
+        .method("POST")
+        .path("/api/changes")
+        .header("X-Auth-Token", "123")
+        .json(&json!([{"typ": "recipe", "id": seed.recipe.id, "action": "updated", "data": {}}]))
+        .reply(&apis)
+        .await;
+
+    // -- CHECK
+    assert_eq!(response.status(), 200);
+    let body: serde_json::Value = serde_json::from_slice(response.body())?;
+    assert_eq!(body["data"][0]["status"], "rejected");
+
+    // -- ACTION - pull
+    let response = warp::test::request()
+        .method("GET")
+        .path("/api/changes?since=0")
+        .header("X-Auth-Token", "123")
+        .reply(&apis)
+        .await;
+
+    // -- CHECK
+    assert_eq!(response.status(), 200);
+    let body: serde_json::Value = serde_json::from_slice(response.body())?;
+    assert_eq!(body["data"]["cursor"], 3);
+    assert_eq!(body["data"]["changes"][1]["typ"], "recipe");
+    assert_eq!(body["data"]["changes"][1]["data"]["title"], "spaghetti");
+
+    Ok(())
+}
@@ -0,0 +1,48 @@
+use crate::model::fixture::{Seed, TestDb};
+use crate::security::utx_from_token;
+use crate::web::collection::collection_rest_filters;
+use crate::web::handle_rejection;
+use anyhow::Result;
+use serde_json::json;
+use warp::Filter;
+
+#[tokio::test]
+async fn web_collection_create_and_add() -> Result<()> {
+    // -- FIXTURE
+    let test_db = TestDb::new().await?;
+    let db = test_db.arc();
+    let seed = Seed::create(&db, &utx_from_token("123").await?).await?;
+    let apis = collection_rest_filters("api", db.clone()).recover(handle_rejection);
+
+    // -- ACTION - create
+    let response = warp::test::request()
+        .method("POST")
+        .path("/api/collections")
+        .header("X-Auth-Token", "123")
+        .json(&json!({"name": "Holiday baking"}))
+        .reply(&apis)
+        .await;
+
+    // -- CHECK
+    assert_eq!(response.status(), 200);
+    let body: serde_json::Value = serde_json::from_slice(response.body())?;
+    assert_eq!(body["data"][0]["name"], "Holiday baking");
+    let id = body["data"][0]["id"].as_i64().unwrap();
+
+    // -- ACTION - add recipe
+    let response = warp::test::request()
+        .method("POST")
+        .path(&format!("/api/collections/{}/recipes", id))
+        .header("X-Auth-Token", "123")
+        .json(&json!({"recipe_id": seed.recipe.id}))
+        .reply(&apis)
+        .await;
+
+    // -- CHECK
+    assert_eq!(response.status(), 200);
+    let body: serde_json::Value = serde_json::from_slice(response.body())?;
+    assert_eq!(body["data"][1][0]["title"], "spaghetti");
+    assert_eq!(body["data"][1][0]["position"], 0);
+
+    Ok(())
+}
@@ -0,0 +1,123 @@
+use super::{negotiate, with_compression, Encoding};
+use anyhow::Result;
+use serde_json::{json, Value};
+use std::io::Read;
+use warp::{
+    http::{header, HeaderMap, HeaderValue},
+    reply::{Reply, Response},
+    Filter,
+};
+
+/// A JSON reply of about 2 KB, and a small one, compressed from 1 KB.
+fn json_routes(
+    enabled: bool,
+) -> impl Filter<Extract = (Response,), Error = std::convert::Infallible> + Clone {
+    let big = warp::path("big").map(|| {
+        let items: Vec<Value> = (0..100)
+            .map(|i| json!({ "name": "tomatoes", "id": i }))
+            .collect();
+        warp::reply::with_header(warp::reply::json(&items), "ETag", "\"3\"").into_response()
+    });
+    let small = warp::path("small").map(|| warp::reply::json(&json!({"a": 1})).into_response());
+    let image = warp::path("image").map(|| {
+        warp::reply::with_header(vec![0u8; 2048], "Content-Type", "image/png").into_response()
+    });
+    let routes = big
+        .or(small)
+        .unify()
+        .or(image)
+        .unify()
+        .recover(|_| async {
+            Ok::<Response, std::convert::Infallible>(
+                warp::http::StatusCode::NOT_FOUND.into_response(),
+            )
+        })
+        .unify();
+    with_compression(enabled, 1024, routes)
+}
+
+#[tokio::test]
+async fn web_compress_negotiated() -> Result<()> {
+    // -- FIXTURE
+    let routes = json_routes(true);
+    let request = |path: &str, accept_encoding: &str| {
+        warp::test::request()
+            .path(path)
+            .header("Accept-Encoding", accept_encoding)
+    };
+
+    // -- ACTION
+    let brotli = request("/big", "gzip, deflate, br").reply(&routes).await;
+    let gzip = request("/big", "gzip").reply(&routes).await;
+    let identity = request("/big", "identity").reply(&routes).await;
+    let small = request("/small", "gzip, br").reply(&routes).await;
+    let image = request("/image", "gzip, br").reply(&routes).await;
+
+    // -- CHECK - brotli preferred
+    assert_eq!("br", brotli.headers()["Content-Encoding"]);
+    assert_eq!("Accept-Encoding", brotli.headers()["Vary"]);
+    assert_eq!("W/\"3\"", brotli.headers()["ETag"]);
+    let mut decoded = Vec::new();
+    brotli::Decompressor::new(brotli.body().as_ref(), 4096).read_to_end(&mut decoded)?;
+    let items: Vec<Value> = serde_json::from_slice(&decoded)?;
+    assert_eq!(100, items.len());
+    assert!(brotli.body().len() < decoded.len());
+
+    // -- CHECK - gzip
+    assert_eq!("gzip", gzip.headers()["Content-Encoding"]);
+    assert_eq!(
+        gzip.body().len().to_string(),
+        gzip.headers()["Content-Length"]
+    );
+    let mut decoded = Vec::new();
+    flate2::read::GzDecoder::new(gzip.body().as_ref()).read_to_end(&mut decoded)?;
+    assert_eq!(100, serde_json::from_slice::<Vec<Value>>(&decoded)?.len());
+
+    // -- CHECK - not compressed
+    assert!(identity.headers().get("Content-Encoding").is_none());
+    assert_eq!("Accept-Encoding", identity.headers()["Vary"]);
+    assert_eq!("\"3\"", identity.headers()["ETag"]);
+    assert!(small.headers().get("Content-Encoding").is_none());
+    assert!(image.headers().get("Content-Encoding").is_none());
+    assert_eq!(2048, image.body().len());
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn web_compress_disabled() -> Result<()> {
+    // -- FIXTURE
+    let routes = json_routes(false);
+
+    // -- ACTION
+    let res = warp::test::request()
+        .path("/big")
+        .header("Accept-Encoding", "gzip, br")
+        .reply(&routes)
+        .await;
+
+    // -- CHECK
+    assert_eq!(200, res.status());
+    assert!(res.headers().get("Content-Encoding").is_none());
+
+    Ok(())
+}
+
+#[test]
+fn web_compress_negotiate() {
+    let negotiated = |accept_encoding: &str| {
+        let mut headers = HeaderMap::new();
+        headers.insert(
+            header::ACCEPT_ENCODING,
+            HeaderValue::from_str(accept_encoding).unwrap(),
+        );
+        negotiate(&headers)
+    };
+
+    assert_eq!(Some(Encoding::Brotli), negotiated("br, gzip"));
+    assert_eq!(Some(Encoding::Gzip), negotiated("br;q=0.5, gzip"));
+    assert_eq!(Some(Encoding::Gzip), negotiated("br;q=0, *"));
+    assert_eq!(Some(Encoding::Brotli), negotiated("*"));
+    assert_eq!(None, negotiated("gzip;q=0, deflate"));
+    assert_eq!(None, negotiate(&HeaderMap::new()));
+}
@@ -0,0 +1,194 @@
+use super::graphql_filters;
+use crate::model::fixture::{RecipeBuilder, Seed, TestDb};
+use crate::security::utx_from_token;
+use crate::web::handle_rejection;
+use anyhow::Result;
+use serde_json::{json, Value};
+use warp::Filter;
+
+#[tokio::test]
+async fn web_graphql_query_fields() -> Result<()> {
+    // -- FIXTURE
+    let test_db = TestDb::new().await?;
+    let db = test_db.arc();
+    Seed::create(&db, &utx_from_token("123").await?).await?;
+    let apis = graphql_filters("api", db.clone(), 8, 500).recover(handle_rejection);
+
+    // -- ACTION - only the titles
+    let response = warp::test::request()
+        .method("POST")
+        .path("/api/graphql")
+        .header("X-Auth-Token", "123")
+        .json(&json!({"query": "{ recipes { title } }"}))
+        .reply(&apis)
+        .await;
+
+    // -- CHECK
+    assert_eq!(200, response.status());
+    let body: Value = serde_json::from_slice(response.body())?;
+    assert_eq!(json!({"data": {"recipes": [{"title": "spaghetti"}]}}), body);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn web_graphql_query_nested() -> Result<()> {
+    // -- FIXTURE - a second recipe using the seed ingredient
+    let test_db = TestDb::new().await?;
+    let db = test_db.arc();
+    let utx = utx_from_token("123").await?;
+    let seed = Seed::create(&db, &utx).await?;
+    RecipeBuilder::new()
+        .title("tomato soup")
+        .ingredient(&seed.ingredient, "400 g")
+        .create(&db, &utx)
+        .await?;
+    let apis = graphql_filters("api", db.clone(), 8, 500).recover(handle_rejection);
+
+    // -- ACTION - the recipes using the ingredients of a recipe
+    let query = format!(
+        "{{ recipe(id: {}) {{ title ingredients {{ quantity ingredient {{ name recipes {{ title }} }} }} }} }}",
+        seed.recipe.id
+    );
+    let response = warp::test::request()
+        .method("POST")
+        .path("/api/graphql")
+        .header("X-Auth-Token", "123")
+        .json(&json!({ "query": query }))
+        .reply(&apis)
+        .await;
+
+    // -- CHECK - newest first
+    assert_eq!(200, response.status());
+    let body: Value = serde_json::from_slice(response.body())?;
+    let link = &body["data"]["recipe"]["ingredients"][0];
+    assert_eq!("200 g", link["quantity"]);
+    assert_eq!("tomatoes", link["ingredient"]["name"]);
+    assert_eq!(
+        json!([{"title": "tomato soup"}, {"title": "spaghetti"}]),
+        link["ingredient"]["recipes"]
+    );
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn web_graphql_mutation_version() -> Result<()> {
+    // -- FIXTURE
+    let test_db = TestDb::new().await?;
+    let db = test_db.arc();
+    let seed = Seed::create(&db, &utx_from_token("123").await?).await?;
+    let apis = graphql_filters("api", db.clone(), 8, 500).recover(handle_rejection);
+    let mutation = |version: i64| {
+        format!(
+            "mutation {{ updateIngredient(id: {}, version: {}, data: {{ quantity: \"5 pieces\" }}) {{ quantity version }} }}",
+            seed.ingredient.id, version
+        )
+    };
+
+    // -- ACTION - on the current version
+    let response = warp::test::request()
+        .method("POST")
+        .path("/api/graphql")
+        .header("X-Auth-Token", "123")
+        .json(&json!({ "query": mutation(seed.ingredient.version) }))
+        .reply(&apis)
+        .await;
+
+    // -- CHECK
+    let body: Value = serde_json::from_slice(response.body())?;
+    let ingredient = &body["data"]["updateIngredient"];
+    assert_eq!("5 pieces", ingredient["quantity"]);
+    assert_eq!(seed.ingredient.version + 1, ingredient["version"]);
+
+    // -- ACTION - on the now stale version
+    let response = warp::test::request()
+        .method("POST")
+        .path("/api/graphql")
+        .header("X-Auth-Token", "123")
+        .json(&json!({ "query": mutation(seed.ingredient.version) }))
+        .reply(&apis)
+        .await;
+
+    // -- CHECK
+    let body: Value = serde_json::from_slice(response.body())?;
+    assert_eq!("VERSION_MISMATCH", body["errors"][0]["extensions"]["code"]);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn web_graphql_limits() -> Result<()> {
+    // -- FIXTURE
+    let test_db = TestDb::new().await?;
+    let db = test_db.arc();
+    let apis = graphql_filters("api", db.clone(), 3, 500).recover(handle_rejection);
+
+    // -- ACTION - 4 levels deep
+    let response = warp::test::request()
+        .method("POST")
+        .path("/api/graphql")
+        .header("X-Auth-Token", "123")
+        .json(&json!({"query": "{ recipes { ingredients { ingredient { name } } } }"}))
+        .reply(&apis)
+        .await;
+
+    // -- CHECK - rejected before running
+    let body: Value = serde_json::from_slice(response.body())?;
+    assert!(body["data"].is_null());
+    assert_eq!("Query is nested too deep.", body["errors"][0]["message"]);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn web_graphql_batch() -> Result<()> {
+    // -- FIXTURE
+    let test_db = TestDb::new().await?;
+    let db = test_db.arc();
+    Seed::create(&db, &utx_from_token("123").await?).await?;
+    let apis = graphql_filters("api", db.clone(), 8, 500).recover(handle_rejection);
+
+    // -- ACTION
+    let response = warp::test::request()
+        .method("POST")
+        .path("/api/graphql")
+        .header("X-Auth-Token", "123")
+        .json(&json!([
+            {"query": "{ recipes { title } }"},
+            {"query": "{ ingredients { name } }"},
+        ]))
+        .reply(&apis)
+        .await;
+
+    // -- CHECK - one response per query
+    assert_eq!(200, response.status());
+    let body: Value = serde_json::from_slice(response.body())?;
+    assert_eq!("spaghetti", body[0]["data"]["recipes"][0]["title"]);
+    assert_eq!("tomatoes", body[1]["data"]["ingredients"][0]["name"]);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn web_graphql_no_auth() -> Result<()> {
+    // -- FIXTURE
+    let test_db = TestDb::new().await?;
+    let db = test_db.arc();
+    let apis = graphql_filters("api", db.clone(), 8, 500).recover(handle_rejection);
+
+    // -- ACTION
+    let response = warp::test::request()
+        .method("POST")
+        .path("/api/graphql")
+        .json(&json!({"query": "{ recipes { title } }"}))
+        .reply(&apis)
+        .await;
+
+    // -- CHECK - same as the REST APIs
+    assert_eq!(400, response.status());
+    let body: Value = serde_json::from_slice(response.body())?;
+    assert_eq!("web::Error", body["errorMessage"]);
+
+    Ok(())
+}
@@ -0,0 +1,36 @@
+use super::health_filters;
+use crate::model::fixture::TestDb;
+use anyhow::Result;
+
+#[tokio::test]
+async fn web_health_ready() -> Result<()> {
+    // -- FIXTURE
+    let test_db = TestDb::new().await?;
+    let db = test_db.arc();
+    let apis = health_filters(db.clone());
+
+    // -- ACTION
+    let response = warp::test::request()
+        .method("GET")
+        .path("/health/ready")
+        .reply(&apis)
+        .await;
+
+    // -- CHECK
+    assert_eq!(response.status(), 200);
+    let body: serde_json::Value = serde_json::from_slice(response.body())?;
+    assert_eq!(body["status"], "ready");
+    assert_eq!(body["migrations"]["pending"], serde_json::json!([]));
+
+    // -- ACTION - live
+    let response = warp::test::request()
+        .method("GET")
+        .path("/health/live")
+        .reply(&apis)
+        .await;
+
+    // -- CHECK
+    assert_eq!(response.status(), 200);
+
+    Ok(())
+}
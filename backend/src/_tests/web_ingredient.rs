@@ -8,7 +8,10 @@ use warp::hyper::{body::Bytes, Response};
 use warp::reply::Json;
 use warp::Filter;
 
-use crate::model::{init_db, Ingredient, IngredientMac};
+use crate::model::{
+    fixture::{Seed, TestDb},
+    DbRepo, Ingredient, IngredientMac, IngredientRepo, MemRepo,
+};
 use crate::security::utx_from_token;
 use crate::web::handle_rejection;
 use crate::web::ingredient::ingredient_rest_filters;
@@ -16,9 +19,11 @@ use crate::web::ingredient::ingredient_rest_filters;
 #[tokio::test]
 async fn web_ingredient_list() -> Result<()> {
     // -- FIXTURE
-    let db = init_db().await?;
-    let db = Arc::new(db);
-    let ingredient_apis = ingredient_rest_filters("api", db.clone()).recover(handle_rejection);
+    let test_db = TestDb::new().await?;
+    let db = test_db.arc();
+    let seed = Seed::create(&db, &utx_from_token("123").await?).await?;
+    let ingredient_apis =
+        ingredient_rest_filters("api", Arc::new(DbRepo::new(db.clone()))).recover(handle_rejection);
 
     // -- ACTION
     let resp = warp::test::request()
@@ -36,7 +41,7 @@ async fn web_ingredient_list() -> Result<()> {
 
     // -- CHECK
     assert_eq!(1, ingredients.len(), "number of ingredients");
-    assert_eq!(1000, ingredients[0].id);
+    assert_eq!(seed.ingredient.id, ingredients[0].id);
     assert_eq!("tomatoes", ingredients[0].name);
 
     Ok(())
@@ -45,15 +50,17 @@ async fn web_ingredient_list() -> Result<()> {
 #[tokio::test]
 async fn web_ingredient_get_ok() -> Result<()> {
     // -- FIXTURE
-    let db = init_db().await?;
-    let db = Arc::new(db);
-    let ingredient_apis = ingredient_rest_filters("api", db.clone()).recover(handle_rejection);
+    let test_db = TestDb::new().await?;
+    let db = test_db.arc();
+    let seed = Seed::create(&db, &utx_from_token("123").await?).await?;
+    let ingredient_apis =
+        ingredient_rest_filters("api", Arc::new(DbRepo::new(db.clone()))).recover(handle_rejection);
 
     // -- ACTION
     let resp = warp::test::request()
         .method("GET")
         .header("X-Auth-Token", "123")
-        .path("/api/ingredients/1000")
+        .path(&format!("/api/ingredients/{}", seed.ingredient.id))
         .reply(&ingredient_apis)
         .await;
 
@@ -64,7 +71,7 @@ async fn web_ingredient_get_ok() -> Result<()> {
     let ingredient: Ingredient = extract_body_data(resp)?;
 
     // -- CHECK - .data (ingredient)
-    assert_eq!(1000, ingredient.id);
+    assert_eq!(seed.ingredient.id, ingredient.id);
     assert_eq!("tomatoes", ingredient.name);
 
     Ok(())
@@ -73,9 +80,10 @@ async fn web_ingredient_get_ok() -> Result<()> {
 #[tokio::test]
 async fn web_ingredient_create_ok() -> Result<()> {
     // -- FIXTURE
-    let db = init_db().await?;
-    let db = Arc::new(db);
-    let ingredient_apis = ingredient_rest_filters("api", db.clone()).recover(handle_rejection);
+    let test_db = TestDb::new().await?;
+    let db = test_db.arc();
+    let ingredient_apis =
+        ingredient_rest_filters("api", Arc::new(DbRepo::new(db.clone()))).recover(handle_rejection);
 
     // new ingredient fixture
     const NAME: &str = "test - web_ingredient_create_ok";
@@ -108,9 +116,11 @@ async fn web_ingredient_create_ok() -> Result<()> {
 #[tokio::test]
 async fn web_ingredient_update_ok() -> Result<()> {
     // -- FIXTURE
-    let db = init_db().await?;
-    let db = Arc::new(db);
-    let ingredient_apis = ingredient_rest_filters("api", db.clone()).recover(handle_rejection);
+    let test_db = TestDb::new().await?;
+    let db = test_db.arc();
+    let seed = Seed::create(&db, &utx_from_token("123").await?).await?;
+    let ingredient_apis =
+        ingredient_rest_filters("api", Arc::new(DbRepo::new(db.clone()))).recover(handle_rejection);
 
     // updated ingredient
     const NAME: &str = "test - ingredient 1000 updated";
@@ -122,7 +132,7 @@ async fn web_ingredient_update_ok() -> Result<()> {
     let resp = warp::test::request()
         .method("PATCH")
         .header("X-Auth-Token", "123")
-        .path("/api/ingredients/1000")
+        .path(&format!("/api/ingredients/{}", seed.ingredient.id))
         .json(&body)
         .reply(&ingredient_apis)
         .await;
@@ -134,7 +144,7 @@ async fn web_ingredient_update_ok() -> Result<()> {
     let ingredient: Ingredient = extract_body_data(resp)?;
 
     // -- CHECK - .data (ingredient)
-    assert_eq!(1000, ingredient.id, "ingredient.id");
+    assert_eq!(seed.ingredient.id, ingredient.id, "ingredient.id");
     assert_eq!(NAME, ingredient.name);
 
     Ok(())
@@ -143,15 +153,17 @@ async fn web_ingredient_update_ok() -> Result<()> {
 #[tokio::test]
 async fn web_ingredient_delete_ok() -> Result<()> {
     // -- FIXTURE
-    let db = init_db().await?;
-    let db = Arc::new(db);
-    let ingredient_apis = ingredient_rest_filters("api", db.clone()).recover(handle_rejection);
+    let test_db = TestDb::new().await?;
+    let db = test_db.arc();
+    let seed = Seed::create(&db, &utx_from_token("123").await?).await?;
+    let ingredient_apis =
+        ingredient_rest_filters("api", Arc::new(DbRepo::new(db.clone()))).recover(handle_rejection);
 
     // -- ACTION
     let resp = warp::test::request()
         .method("DELETE")
         .header("X-Auth-Token", "123")
-        .path("/api/ingredients/1000")
+        .path(&format!("/api/ingredients/{}", seed.ingredient.id))
         .reply(&ingredient_apis)
         .await;
 
@@ -162,17 +174,164 @@ async fn web_ingredient_delete_ok() -> Result<()> {
     let ingredient: Ingredient = extract_body_data(resp)?;
 
     // -- CHECK - .data (ingredients)
-    assert_eq!(1000, ingredient.id);
+    assert_eq!(seed.ingredient.id, ingredient.id);
     assert_eq!("tomatoes", ingredient.name);
 
     // -- CHECK - list .len() should be 0
-    let utx = utx_from_token(&db, "123").await?;
+    let utx = utx_from_token("123").await?;
     let ingredients = IngredientMac::list(&db, &utx).await?;
     assert_eq!(0, ingredients.len());
 
     Ok(())
 }
 
+#[tokio::test]
+async fn web_ingredient_update_if_match() -> Result<()> {
+    // -- FIXTURE
+    let test_db = TestDb::new().await?;
+    let db = test_db.arc();
+    let seed = Seed::create(&db, &utx_from_token("123").await?).await?;
+    let ingredient_apis =
+        ingredient_rest_filters("api", Arc::new(DbRepo::new(db.clone()))).recover(handle_rejection);
+    let resp = warp::test::request()
+        .method("GET")
+        .header("X-Auth-Token", "123")
+        .path(&format!("/api/ingredients/{}", seed.ingredient.id))
+        .reply(&ingredient_apis)
+        .await;
+    let etag = resp.headers()["ETag"].to_str()?.to_string();
+    assert_eq!("\"1\"", etag);
+
+    // -- ACTION - matching ETag
+    let resp = warp::test::request()
+        .method("PATCH")
+        .header("X-Auth-Token", "123")
+        .header("If-Match", &etag)
+        .path(&format!("/api/ingredients/{}", seed.ingredient.id))
+        .json(&json!({"name": "cherry tomatoes", "quantity": "1 cup"}))
+        .reply(&ingredient_apis)
+        .await;
+
+    // -- CHECK - updated, with the new ETag
+    assert_eq!(200, resp.status(), "http status");
+    assert_eq!("\"2\"", resp.headers()["ETag"]);
+
+    // -- ACTION - stale ETag
+    let resp = warp::test::request()
+        .method("PATCH")
+        .header("X-Auth-Token", "123")
+        .header("If-Match", &etag)
+        .path(&format!("/api/ingredients/{}", seed.ingredient.id))
+        .json(&json!({"name": "roma tomatoes", "quantity": "1 cup"}))
+        .reply(&ingredient_apis)
+        .await;
+
+    // -- CHECK - rejected, first update kept
+    assert_eq!(412, resp.status(), "http status");
+    let utx = utx_from_token("123").await?;
+    let ingredient = IngredientMac::get(&db, &utx, seed.ingredient.id).await?;
+    assert_eq!("cherry tomatoes", ingredient.name);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn web_ingredient_delete_if_match_stale() -> Result<()> {
+    // -- FIXTURE
+    let test_db = TestDb::new().await?;
+    let db = test_db.arc();
+    let seed = Seed::create(&db, &utx_from_token("123").await?).await?;
+    let ingredient_apis =
+        ingredient_rest_filters("api", Arc::new(DbRepo::new(db.clone()))).recover(handle_rejection);
+
+    // -- ACTION
+    let resp = warp::test::request()
+        .method("DELETE")
+        .header("X-Auth-Token", "123")
+        .header("If-Match", "\"7\"")
+        .path(&format!("/api/ingredients/{}", seed.ingredient.id))
+        .reply(&ingredient_apis)
+        .await;
+
+    // -- CHECK
+    assert_eq!(412, resp.status(), "http status");
+    let utx = utx_from_token("123").await?;
+    assert_eq!(1, IngredientMac::list(&db, &utx).await?.len());
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn web_ingredient_create_invalid() -> Result<()> {
+    // -- FIXTURE
+    let test_db = TestDb::new().await?;
+    let db = test_db.arc();
+    Seed::create(&db, &utx_from_token("123").await?).await?;
+    let ingredient_apis =
+        ingredient_rest_filters("api", Arc::new(DbRepo::new(db.clone()))).recover(handle_rejection);
+
+    // -- ACTION
+    let resp = warp::test::request()
+        .method("POST")
+        .header("X-Auth-Token", "123")
+        .path("/api/ingredients")
+        .json(&json!({"name": " ", "quantity": null}))
+        .reply(&ingredient_apis)
+        .await;
+
+    // -- CHECK
+    assert_eq!(422, resp.status(), "http status");
+    let body: Value = from_str(from_utf8(resp.body())?)?;
+    assert_eq!(
+        json!([
+            {"field": "name", "reason": "must not be empty"},
+            {"field": "quantity", "reason": "must not be null"},
+        ]),
+        body["errorFields"]
+    );
+    let utx = utx_from_token("123").await?;
+    assert_eq!(1, IngredientMac::list(&db, &utx).await?.len());
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn web_ingredient_mem_create_and_delete() -> Result<()> {
+    // -- FIXTURE - no db, the store is in memory
+    let repo = Arc::new(MemRepo::new());
+    let ingredient_apis = ingredient_rest_filters("api", repo.clone()).recover(handle_rejection);
+
+    // -- ACTION - create
+    let resp = warp::test::request()
+        .method("POST")
+        .header("X-Auth-Token", "123")
+        .path("/api/ingredients")
+        .json(&json!({"name": "basil", "quantity": "1 bunch"}))
+        .reply(&ingredient_apis)
+        .await;
+
+    // -- CHECK - create
+    assert_eq!(200, resp.status(), "http status");
+    let ingredient: Ingredient = extract_body_data(resp)?;
+    assert_eq!(1000, ingredient.id);
+    assert_eq!("basil", ingredient.name);
+
+    // -- ACTION - delete
+    let resp = warp::test::request()
+        .method("DELETE")
+        .header("X-Auth-Token", "123")
+        .path("/api/ingredients/1000")
+        .reply(&ingredient_apis)
+        .await;
+
+    // -- CHECK - delete
+    assert_eq!(200, resp.status(), "http status");
+    let utx = utx_from_token("123").await?;
+    assert!(repo.list(&utx).await?.is_empty());
+
+    Ok(())
+}
+
 // region Web Test Utils
 fn extract_body_data<D>(resp: Response<Bytes>) -> Result<D>
 where
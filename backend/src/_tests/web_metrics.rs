@@ -0,0 +1,61 @@
+use super::metrics_filters;
+use crate::model::fixture::{Seed, TestDb};
+use crate::security::utx_from_token;
+use crate::web::handle_rejection;
+use anyhow::Result;
+use warp::Filter;
+
+#[tokio::test]
+async fn web_metrics_scrape() -> Result<()> {
+    // -- FIXTURE
+    let test_db = TestDb::new().await?;
+    let db = test_db.arc();
+    Seed::create(&db, &utx_from_token("123").await?).await?;
+    let apis =
+        metrics_filters(db.clone(), true, Some("secret".to_string())).recover(handle_rejection);
+
+    // -- ACTION - without the token
+    let response = warp::test::request()
+        .method("GET")
+        .path("/metrics")
+        .reply(&apis)
+        .await;
+
+    // -- CHECK
+    assert_eq!(response.status(), 401);
+
+    // -- ACTION - with the token
+    let response = warp::test::request()
+        .method("GET")
+        .path("/metrics")
+        .header("Authorization", "Bearer secret")
+        .reply(&apis)
+        .await;
+
+    // -- CHECK
+    assert_eq!(response.status(), 200);
+    let body = String::from_utf8(response.body().to_vec())?;
+    assert!(body.contains("cookbook_entities{typ=\"recipe\"} 1"));
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn web_metrics_disabled() -> Result<()> {
+    // -- FIXTURE
+    let test_db = TestDb::new().await?;
+    let db = test_db.arc();
+    let apis = metrics_filters(db.clone(), false, None);
+
+    // -- ACTION
+    let matches = warp::test::request()
+        .method("GET")
+        .path("/metrics")
+        .matches(&apis)
+        .await;
+
+    // -- CHECK - same as an unknown route
+    assert!(!matches);
+
+    Ok(())
+}
@@ -0,0 +1,198 @@
+use super::{openapi_filters, openapi_spec};
+use crate::model::fixture::{IngredientBuilder, RecipeBuilder};
+use crate::model::{IngredientRepo, MemRepo, RecipeRepo};
+use crate::security::utx_from_token;
+use crate::web::ingredient::ingredient_rest_filters;
+use crate::web::recipe::recipe_rest_filters;
+use anyhow::{bail, Context, Result};
+use serde_json::{json, Value};
+use std::sync::Arc;
+use warp::{Filter, Reply};
+
+const METHODS: [&str; 5] = ["get", "post", "put", "patch", "delete"];
+
+#[tokio::test]
+async fn web_openapi_routes_match_spec() -> Result<()> {
+    // -- FIXTURE
+    let spec = openapi_spec("api");
+    let paths = spec["paths"].as_object().context("paths")?;
+
+    for (path, item) in paths {
+        for method in METHODS {
+            // -- FIXTURE - fresh store with an ingredient and a recipe using it
+            let repo = Arc::new(MemRepo::new());
+            let utx = utx_from_token("123").await?;
+            let ingredient_fx = IngredientBuilder::new().name("tomatoes");
+            let ingredient =
+                IngredientRepo::create(repo.as_ref(), &utx, ingredient_fx.patch()).await?;
+            let recipe_fx = RecipeBuilder::new().ingredient(&ingredient, "200 g");
+            let (recipe, _) =
+                RecipeRepo::create(repo.as_ref(), &utx, recipe_fx.patch(utx.user_id)).await?;
+            let apis = ingredient_rest_filters("api", repo.clone())
+                .or(recipe_rest_filters("api", repo.clone()));
+            let id = match path.contains("/recipes") {
+                true => recipe.id,
+                false => ingredient.id,
+            };
+
+            // -- ACTION
+            let op = &item[method];
+            let mut request = warp::test::request()
+                .method(&method.to_uppercase())
+                .path(&path.replace("{id}", &id.to_string()))
+                .header("X-Auth-Token", "123");
+            let body_spec = &op["requestBody"]["content"]["application/json"];
+            if !body_spec.is_null() {
+                check_schema(&spec, &body_spec["schema"], &body_spec["example"])
+                    .with_context(|| format!("{} {} example", method, path))?;
+                request = request.json(&body_spec["example"]);
+            }
+            let result = request.filter(&apis).await;
+
+            // -- CHECK - served when in the spec, with the documented body
+            match (op.is_null(), result) {
+                (true, Err(_)) => (),
+                (true, Ok(_)) => bail!("{} {} is served but missing from the spec", method, path),
+                (false, Err(rejection)) => {
+                    bail!(
+                        "{} {} is in the spec but rejected: {:?}",
+                        method,
+                        path,
+                        rejection
+                    )
+                }
+                (false, Ok(reply)) => {
+                    let response = reply.into_response();
+                    let status = response.status().as_u16().to_string();
+                    let response_spec = &op["responses"][&status];
+                    assert!(!response_spec.is_null(), "{} {} {}", method, path, status);
+                    let bytes = warp::hyper::body::to_bytes(response.into_body()).await?;
+                    let body: Value = serde_json::from_slice(&bytes)?;
+                    check_schema(
+                        &spec,
+                        &response_spec["content"]["application/json"]["schema"],
+                        &body,
+                    )
+                    .with_context(|| format!("{} {} response", method, path))?;
+                }
+            }
+        }
+    }
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn web_openapi_serve() -> Result<()> {
+    // -- FIXTURE
+    let apis = openapi_filters("api");
+
+    // -- ACTION
+    let spec = warp::test::request()
+        .method("GET")
+        .path("/api/openapi.json")
+        .reply(&apis)
+        .await;
+    let docs = warp::test::request()
+        .method("GET")
+        .path("/api/docs")
+        .reply(&apis)
+        .await;
+
+    // -- CHECK - no auth needed
+    assert_eq!(200, spec.status());
+    let body: Value = serde_json::from_slice(spec.body())?;
+    assert_eq!("3.1.0", body["openapi"]);
+    assert!(body["components"]["schemas"]["RecipePatch"].is_object());
+    assert_eq!(200, docs.status());
+    assert!(std::str::from_utf8(docs.body())?.contains("openapi.json"));
+
+    Ok(())
+}
+
+// region: Test Utils
+/// Minimal JSON Schema check ($ref, type, properties, required, items, prefixItems, enum).
+/// Objects must not have properties the schema does not list, so a new struct field fails too.
+fn check_schema(spec: &Value, schema: &Value, value: &Value) -> Result<()> {
+    if let Some(reference) = schema["$ref"].as_str() {
+        let name = reference.trim_start_matches("#/components/schemas/");
+        let schema = &spec["components"]["schemas"][name];
+        if schema.is_null() {
+            bail!("unknown schema {}", reference);
+        }
+        return check_schema(spec, schema, value).with_context(|| name.to_string());
+    }
+
+    let types: Vec<&str> = match &schema["type"] {
+        Value::String(typ) => vec![typ.as_str()],
+        Value::Array(types) => types.iter().filter_map(Value::as_str).collect(),
+        _ => vec![],
+    };
+    let typ = match value {
+        Value::Null => "null",
+        Value::Bool(_) => "boolean",
+        Value::Number(n) if n.is_i64() || n.is_u64() => "integer",
+        Value::Number(_) => "number",
+        Value::String(_) => "string",
+        Value::Array(_) => "array",
+        Value::Object(_) => "object",
+    };
+    let type_ok =
+        types.is_empty() || types.contains(&typ) || (typ == "integer" && types.contains(&"number"));
+    if !type_ok {
+        bail!("expected {:?}, got {}", types, value);
+    }
+    if let Some(values) = schema["enum"].as_array() {
+        if !values.contains(value) {
+            bail!("{} not in {:?}", value, values);
+        }
+    }
+
+    match value {
+        Value::Object(object) => {
+            let properties = schema["properties"].as_object();
+            for required in schema["required"].as_array().into_iter().flatten() {
+                let required = required.as_str().unwrap_or_default();
+                if !object.contains_key(required) {
+                    bail!("missing required property {}", required);
+                }
+            }
+            for (key, property) in object {
+                let property_schema = properties
+                    .and_then(|p| p.get(key))
+                    .with_context(|| format!("property {} is not in the schema", key))?;
+                check_schema(spec, property_schema, property).with_context(|| key.clone())?;
+            }
+        }
+        Value::Array(items) => {
+            let prefix = schema["prefixItems"].as_array();
+            for (i, item) in items.iter().enumerate() {
+                let item_schema = match prefix.and_then(|p| p.get(i)) {
+                    Some(item_schema) => item_schema,
+                    None => &schema["items"],
+                };
+                check_schema(spec, item_schema, item).with_context(|| format!("[{}]", i))?;
+            }
+        }
+        _ => (),
+    }
+
+    Ok(())
+}
+
+#[test]
+fn web_openapi_check_schema_extra_property() {
+    // -- FIXTURE
+    let spec = openapi_spec("api");
+
+    // -- ACTION
+    let result = check_schema(
+        &spec,
+        &json!({"$ref": "#/components/schemas/Ingredient"}),
+        &json!({"id": 1, "name": "salt", "quantity": "1 pinch", "version": 1, "color": "white"}),
+    );
+
+    // -- CHECK
+    assert!(result.is_err());
+}
+// endregion: Test Utils
@@ -0,0 +1,179 @@
+use super::{body_limit, with_policy, Policy};
+use crate::shutdown::Shutdown;
+use crate::web::{bind, handle_rejection};
+use anyhow::Result;
+use serde_json::{json, Value};
+use std::{convert::Infallible, sync::Arc, time::Duration};
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::TcpStream;
+use warp::{http::Method, reply::Response, Filter};
+
+const CSP: &str = "default-src 'self'";
+
+/// An echo of the JSON body, at most 32 bytes, behind the policy.
+fn echo_routes(
+    cors_allowed_origins: &[&str],
+) -> impl Filter<Extract = (Response,), Error = Infallible> + Clone {
+    let policy = Arc::new(Policy {
+        cors_allowed_origins: cors_allowed_origins.iter().map(|o| o.to_string()).collect(),
+        cors_allowed_methods: vec![Method::GET, Method::POST],
+        content_security_policy: CSP.to_string(),
+    });
+    let echo = warp::path("echo")
+        .and(warp::body::json())
+        .map(|body: Value| warp::reply::json(&body));
+    let routes = body_limit(32).and(echo).recover(handle_rejection);
+    with_policy(policy, routes)
+}
+
+#[tokio::test]
+async fn web_policy_security_headers() -> Result<()> {
+    // -- FIXTURE
+    let routes = echo_routes(&[]);
+
+    // -- ACTION
+    let ok = warp::test::request()
+        .method("POST")
+        .path("/echo")
+        .json(&json!({"a": 1}))
+        .reply(&routes)
+        .await;
+    let not_found = warp::test::request().path("/nope").reply(&routes).await;
+
+    // -- CHECK - on the replies and on the errors
+    for res in [&ok, &not_found] {
+        assert_eq!(CSP, res.headers()["Content-Security-Policy"]);
+        assert_eq!("nosniff", res.headers()["X-Content-Type-Options"]);
+        assert_eq!(
+            "strict-origin-when-cross-origin",
+            res.headers()["Referrer-Policy"]
+        );
+        assert_eq!("DENY", res.headers()["X-Frame-Options"]);
+        assert!(res.headers().get("Access-Control-Allow-Origin").is_none());
+    }
+    assert_eq!(200, ok.status());
+    assert_eq!(400, not_found.status());
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn web_policy_cors() -> Result<()> {
+    // -- FIXTURE
+    let routes = echo_routes(&["https://cookbook.example"]);
+    let request = |method: &str, origin: &str| {
+        warp::test::request()
+            .method(method)
+            .path("/echo")
+            .header("Origin", origin)
+    };
+
+    // -- ACTION
+    let allowed = request("POST", "https://cookbook.example")
+        .json(&json!({"a": 1}))
+        .reply(&routes)
+        .await;
+    let other = request("POST", "https://evil.example")
+        .json(&json!({"a": 1}))
+        .reply(&routes)
+        .await;
+    let preflight = request("OPTIONS", "https://cookbook.example")
+        .header("Access-Control-Request-Method", "POST")
+        .header("Access-Control-Request-Headers", "x-auth-token")
+        .reply(&routes)
+        .await;
+    let other_preflight = request("OPTIONS", "https://evil.example")
+        .header("Access-Control-Request-Method", "POST")
+        .reply(&routes)
+        .await;
+
+    // -- CHECK - the allowed origin
+    assert_eq!(200, allowed.status());
+    assert_eq!(
+        "https://cookbook.example",
+        allowed.headers()["Access-Control-Allow-Origin"]
+    );
+    assert!(allowed.headers()["Access-Control-Expose-Headers"]
+        .to_str()?
+        .contains("ETag"));
+    assert_eq!("Origin", allowed.headers()["Vary"]);
+
+    // -- CHECK - another origin, served but not allowed to read it (the browser blocks it)
+    assert_eq!(200, other.status());
+    assert!(other.headers().get("Access-Control-Allow-Origin").is_none());
+
+    // -- CHECK - the preflights
+    assert_eq!(204, preflight.status());
+    assert_eq!(
+        "GET, POST",
+        preflight.headers()["Access-Control-Allow-Methods"]
+    );
+    assert!(preflight.headers()["Access-Control-Allow-Headers"]
+        .to_str()?
+        .contains("X-Auth-Token"));
+    assert_eq!(204, other_preflight.status());
+    assert!(other_preflight
+        .headers()
+        .get("Access-Control-Allow-Methods")
+        .is_none());
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn web_policy_body_limit() -> Result<()> {
+    // -- FIXTURE
+    let routes = echo_routes(&[]);
+
+    // -- ACTION
+    let at_limit = warp::test::request()
+        .method("POST")
+        .path("/echo")
+        .json(&json!({ "text": "x".repeat(21) }))
+        .reply(&routes)
+        .await;
+    let over_limit = warp::test::request()
+        .method("POST")
+        .path("/echo")
+        .json(&json!({ "text": "x".repeat(22) }))
+        .reply(&routes)
+        .await;
+
+    // -- CHECK
+    assert_eq!(200, at_limit.status());
+    assert_eq!(413, over_limit.status());
+    let body: Value = serde_json::from_slice(over_limit.body())?;
+    assert_eq!("web::Error", body["errorMessage"]);
+    assert_eq!(CSP, over_limit.headers()["Content-Security-Policy"]);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn web_policy_body_length_required() -> Result<()> {
+    // -- FIXTURE - a server, the test requests always come with a Content-Length
+    let shutdown = Shutdown::new();
+    let (addr, server) = bind(
+        warp::service(echo_routes(&[])),
+        ([127, 0, 0, 1], 0).into(),
+        shutdown.clone(),
+        Duration::from_secs(1),
+    )?;
+    let server = tokio::spawn(server);
+
+    // -- ACTION
+    let mut stream = TcpStream::connect(addr).await?;
+    let req = "POST /echo HTTP/1.1\r\nHost: localhost\r\nConnection: close\r\n\
+        Content-Type: application/json\r\nTransfer-Encoding: chunked\r\n\r\n2\r\n{}\r\n0\r\n\r\n";
+    stream.write_all(req.as_bytes()).await?;
+    let mut response = String::new();
+    stream.read_to_string(&mut response).await?;
+
+    // -- CHECK
+    assert!(response.starts_with("HTTP/1.1 411"), "{}", response);
+
+    shutdown.trigger();
+    server.await??;
+
+    Ok(())
+}
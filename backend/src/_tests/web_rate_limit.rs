@@ -0,0 +1,149 @@
+use super::{rate_limit, rate_limit_api, RateClass, RateKey, RateLimiter};
+use crate::web::filter_auth::do_auth;
+use crate::web::handle_rejection;
+use anyhow::Result;
+use serde_json::Value;
+use std::net::SocketAddr;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use warp::Filter;
+
+const CLIENT: &str = "10.0.0.1:5000";
+const OTHER_CLIENT: &str = "10.0.0.2:5000";
+
+#[tokio::test]
+async fn web_rate_limit_write_budget() -> Result<()> {
+    // -- FIXTURE - 2 writes a minute
+    let limiter = Arc::new(RateLimiter::new(100, 2, 100));
+    let apis = rate_limit(limiter, do_auth())
+        .map(|_| "ok")
+        .recover(handle_rejection);
+    let request = |method: &str, token: &str, client: &str| {
+        warp::test::request()
+            .method(method)
+            .path("/api/ingredients")
+            .header("X-Auth-Token", token)
+            .remote_addr(client.parse::<SocketAddr>().unwrap())
+    };
+
+    // -- ACTION
+    let first = request("POST", "123", CLIENT).reply(&apis).await;
+    let second = request("POST", "123", CLIENT).reply(&apis).await;
+    let third = request("POST", "123", CLIENT).reply(&apis).await;
+
+    // -- CHECK
+    assert_eq!(200, first.status());
+    assert_eq!(200, second.status());
+    assert_eq!(429, third.status());
+    assert_eq!("30", third.headers()["Retry-After"]);
+    assert_eq!("2", third.headers()["RateLimit-Limit"]);
+    assert_eq!("0", third.headers()["RateLimit-Remaining"]);
+    assert_eq!("30", third.headers()["RateLimit-Reset"]);
+    let body: Value = serde_json::from_slice(third.body())?;
+    assert_eq!("web::RateLimited", body["errorMessage"]);
+
+    // -- ACTION - reads have their own budget, other users and IPs theirs
+    let read = request("GET", "123", CLIENT).reply(&apis).await;
+    let other_user = request("POST", "124", OTHER_CLIENT).reply(&apis).await;
+    let same_user_other_ip = request("POST", "123", OTHER_CLIENT).reply(&apis).await;
+
+    // -- CHECK
+    assert_eq!(200, read.status());
+    assert_eq!(200, other_user.status());
+    assert_eq!(429, same_user_other_ip.status());
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn web_rate_limit_ip_budget() -> Result<()> {
+    // -- FIXTURE
+    let limiter = Arc::new(RateLimiter::new(100, 2, 100));
+    let apis = rate_limit(limiter, do_auth())
+        .map(|_| "ok")
+        .recover(handle_rejection);
+    let request = |token: &str| {
+        warp::test::request()
+            .method("DELETE")
+            .path("/api/ingredients/1000")
+            .header("X-Auth-Token", token)
+            .remote_addr(CLIENT.parse::<SocketAddr>().unwrap())
+    };
+
+    // -- ACTION - users behind the same IP
+    let statuses = vec![
+        request("123").reply(&apis).await.status(),
+        request("124").reply(&apis).await.status(),
+        request("125").reply(&apis).await.status(),
+    ];
+
+    // -- CHECK
+    assert_eq!(vec![200, 200, 429], statuses);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn web_rate_limit_auth_budget() -> Result<()> {
+    // -- FIXTURE - 2 invalid tokens a minute, in front of the routes
+    let limiter = Arc::new(RateLimiter::new(100, 100, 2));
+    let apis = rate_limit_api("api", limiter, do_auth())
+        .and(warp::path!("api" / "recipes"))
+        .and(do_auth())
+        .map(|_| "ok")
+        .recover(handle_rejection);
+    let request = |token: &str| {
+        warp::test::request()
+            .method("GET")
+            .path("/api/recipes")
+            .header("X-Auth-Token", token)
+            .remote_addr(CLIENT.parse::<SocketAddr>().unwrap())
+    };
+
+    // -- ACTION
+    let statuses = vec![
+        request("guess-1").reply(&apis).await.status(),
+        request("guess-2").reply(&apis).await.status(),
+        request("guess-3").reply(&apis).await.status(),
+        request("123").reply(&apis).await.status(),
+    ];
+
+    // -- CHECK - rejected by the route, then throttled, a valid token still goes through
+    assert_eq!(vec![400, 400, 429, 200], statuses);
+
+    // -- ACTION - outside of the API
+    let outside = warp::test::request()
+        .method("GET")
+        .path("/index.html")
+        .header("X-Auth-Token", "guess-4")
+        .remote_addr(CLIENT.parse::<SocketAddr>().unwrap())
+        .reply(&apis)
+        .await;
+
+    // -- CHECK - not throttled (not found)
+    assert_eq!(400, outside.status());
+    assert!(outside.headers().get("Retry-After").is_none());
+
+    Ok(())
+}
+
+#[test]
+fn web_rate_limit_refill() {
+    // -- FIXTURE - 60 a minute, one a second
+    let limiter = RateLimiter::new(60, 60, 60);
+    let key = RateKey::User(123);
+    let start = Instant::now();
+    for _ in 0..60 {
+        assert!(limiter.acquire_at(RateClass::Read, key, start).is_ok());
+    }
+
+    // -- ACTION
+    let empty = limiter.acquire_at(RateClass::Read, key, start);
+    let refilled = limiter.acquire_at(RateClass::Read, key, start + Duration::from_secs(1));
+    let unlimited = RateLimiter::new(0, 0, 0).acquire_at(RateClass::Read, key, start);
+
+    // -- CHECK
+    assert_eq!(1, empty.err().map(|l| l.retry_after).unwrap_or_default());
+    assert!(refilled.is_ok());
+    assert!(unlimited.is_ok());
+}
@@ -1,5 +1,9 @@
-use crate::model::{init_db, Db, RecipeIngredientPatch, RecipeMac, RecipePatch, RecipePatchInner};
-use crate::security::UserCtx;
+use crate::model::{
+    fixture::{Seed, TestDb},
+    DbRepo, IngredientPatch, IngredientRepo, MemRepo, PatchValue, RecipeIngredientPatch,
+    RecipePatch, RecipePatchInner, RecipeRepo,
+};
+use crate::security::utx_from_token;
 use crate::web::handle_rejection;
 use crate::web::recipe::recipe_rest_filters;
 use anyhow::Result;
@@ -10,18 +14,19 @@ use warp::Filter;
 #[tokio::test]
 async fn web_recipe_create() -> Result<()> {
     // -- FIXTURE
-    let db = init_db().await?;
-    let db = Arc::new(db);
-    let recipe_apis = recipe_rest_filters("api", db.clone()).recover(handle_rejection);
+    let test_db = TestDb::new().await?;
+    let db = test_db.arc();
+    let seed = Seed::create(&db, &utx_from_token("123").await?).await?;
+    let recipe_apis =
+        recipe_rest_filters("api", Arc::new(DbRepo::new(db.clone()))).recover(handle_rejection);
 
     let recipe_patch = RecipePatch {
         recipe_patch: RecipePatchInner {
-            title: Some("New Recipe".to_string()),
-            cid: Some(123),
-            ..Default::default()
+            title: PatchValue::Value("New Recipe".to_string()),
+            cid: PatchValue::Value(123),
         },
-        ingredients: Some(vec![RecipeIngredientPatch {
-            ingredient_id: 1000,
+        ingredients: PatchValue::Value(vec![RecipeIngredientPatch {
+            ingredient_id: seed.ingredient.id,
             ingredient_name: "tomatoes".to_string(),
             quantity: "1 cup".to_string(),
         }]),
@@ -53,13 +58,15 @@ async fn web_recipe_create() -> Result<()> {
 #[tokio::test]
 async fn web_recipe_get() -> Result<()> {
     // -- FIXTURE
-    let db = init_db().await?;
-    let db = Arc::new(db);
-    let recipe_apis = recipe_rest_filters("api", db.clone()).recover(handle_rejection);
+    let test_db = TestDb::new().await?;
+    let db = test_db.arc();
+    let seed = Seed::create(&db, &utx_from_token("123").await?).await?;
+    let recipe_apis =
+        recipe_rest_filters("api", Arc::new(DbRepo::new(db.clone()))).recover(handle_rejection);
 
     let response = warp::test::request()
         .method("GET")
-        .path("/api/recipes/1000")
+        .path(&format!("/api/recipes/{}", seed.recipe.id))
         .header("X-Auth-Token", "123")
         .reply(&recipe_apis)
         .await;
@@ -68,24 +75,26 @@ async fn web_recipe_get() -> Result<()> {
     let body: serde_json::Value = serde_json::from_slice(response.body()).unwrap();
     //println!("{:#?}", body);
     let recipe = &body["data"][0];
-    assert_eq!(recipe["id"], 1000);
+    assert_eq!(recipe["id"], seed.recipe.id);
     Ok(())
 }
 
 #[tokio::test]
 async fn web_recipe_update() -> Result<()> {
     // -- FIXTURE
-    let db = init_db().await?;
-    let db = Arc::new(db);
-    let recipe_apis = recipe_rest_filters("api", db.clone()).recover(handle_rejection);
+    let test_db = TestDb::new().await?;
+    let db = test_db.arc();
+    let seed = Seed::create(&db, &utx_from_token("123").await?).await?;
+    let recipe_apis =
+        recipe_rest_filters("api", Arc::new(DbRepo::new(db.clone()))).recover(handle_rejection);
 
     let recipe_patch = RecipePatch {
         recipe_patch: RecipePatchInner {
-            title: Some("Updated Recipe".to_string()),
+            title: PatchValue::Value("Updated Recipe".to_string()),
             ..Default::default()
         },
-        ingredients: Some(vec![RecipeIngredientPatch {
-            ingredient_id: 1000,
+        ingredients: PatchValue::Value(vec![RecipeIngredientPatch {
+            ingredient_id: seed.ingredient.id,
             ingredient_name: "tomatoes".to_string(),
             quantity: "2 cups".to_string(),
         }]),
@@ -93,7 +102,7 @@ async fn web_recipe_update() -> Result<()> {
 
     let response = warp::test::request()
         .method("PATCH")
-        .path("/api/recipes/1000")
+        .path(&format!("/api/recipes/{}", seed.recipe.id))
         .header("X-Auth-Token", "123")
         .json(&recipe_patch)
         .reply(&recipe_apis)
@@ -112,9 +121,10 @@ async fn web_recipe_update() -> Result<()> {
 #[tokio::test]
 async fn web_recipe_list() -> Result<()> {
     // -- FIXTURE
-    let db = init_db().await?;
-    let db = Arc::new(db);
-    let recipe_apis = recipe_rest_filters("api", db.clone()).recover(handle_rejection);
+    let test_db = TestDb::new().await?;
+    let db = test_db.arc();
+    let recipe_apis =
+        recipe_rest_filters("api", Arc::new(DbRepo::new(db.clone()))).recover(handle_rejection);
 
     let response = warp::test::request()
         .method("GET")
@@ -138,16 +148,75 @@ async fn web_recipe_list() -> Result<()> {
     Ok(())
 }
 
+#[tokio::test]
+async fn web_recipe_list_conditional() -> Result<()> {
+    // -- FIXTURE
+    let test_db = TestDb::new().await?;
+    let db = test_db.arc();
+    let utx = utx_from_token("123").await?;
+    let seed = Seed::create(&db, &utx).await?;
+    let repo = Arc::new(DbRepo::new(db.clone()));
+    let recipe_apis = recipe_rest_filters("api", repo.clone()).recover(handle_rejection);
+    let request = || {
+        warp::test::request()
+            .method("GET")
+            .path("/api/recipes")
+            .header("X-Auth-Token", "123")
+    };
+    let first = request().reply(&recipe_apis).await;
+    let etag = first.headers()["ETag"].to_str()?.to_string();
+    let last_modified = first.headers()["Last-Modified"].to_str()?.to_string();
+
+    // -- ACTION
+    let same_etag = request()
+        .header("If-None-Match", &etag)
+        .reply(&recipe_apis)
+        .await;
+    let same_date = request()
+        .header("If-Modified-Since", &last_modified)
+        .reply(&recipe_apis)
+        .await;
+    let patch = RecipePatch {
+        recipe_patch: RecipePatchInner {
+            title: PatchValue::Value("spaghetti bolognese".to_string()),
+            ..Default::default()
+        },
+        ..Default::default()
+    };
+    RecipeRepo::update(repo.as_ref(), &utx, seed.recipe.id, patch).await?;
+    let changed = request()
+        .header("If-None-Match", &etag)
+        .reply(&recipe_apis)
+        .await;
+
+    // -- CHECK
+    assert_eq!(200, first.status());
+    assert!(etag.starts_with("W/\""), "{}", etag);
+    assert!(last_modified.ends_with(" GMT"), "{}", last_modified);
+    assert_eq!(304, same_etag.status());
+    assert!(same_etag.body().is_empty());
+    assert_eq!(etag, same_etag.headers()["ETag"]);
+    assert_eq!(304, same_date.status());
+    assert_eq!(200, changed.status());
+    assert_ne!(etag, changed.headers()["ETag"]);
+    let body: serde_json::Value = serde_json::from_slice(changed.body())?;
+    assert_eq!("spaghetti bolognese", body["data"][0][0]["title"]);
+
+    Ok(())
+}
+
 #[tokio::test]
 async fn web_recipe_delete() -> Result<()> {
     // -- FIXTURE
-    let db = init_db().await?;
-    let db = Arc::new(db);
-    let recipe_apis = recipe_rest_filters("api", db.clone()).recover(handle_rejection);
+    let test_db = TestDb::new().await?;
+    let db = test_db.arc();
+    let seed = Seed::create(&db, &utx_from_token("123").await?).await?;
+    let recipe_apis =
+        recipe_rest_filters("api", Arc::new(DbRepo::new(db.clone()))).recover(handle_rejection);
 
     let response = warp::test::request()
         .method("DELETE")
-        .path("/api/recipes/1000")
+        .path(&format!("/api/recipes/{}", seed.recipe.id))
         .header("X-Auth-Token", "123")
         .reply(&recipe_apis)
         .await;
@@ -158,7 +227,63 @@ async fn web_recipe_delete() -> Result<()> {
     // Access the recipe
     let recipe = &body["data"][0];
 
-    assert_eq!(recipe["id"], 1000);
+    assert_eq!(recipe["id"], seed.recipe.id);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn web_recipe_mem_create_and_update() -> Result<()> {
+    // -- FIXTURE - no db, the store is in memory
+    let repo = Arc::new(MemRepo::new());
+    let utx = utx_from_token("123").await?;
+    let ingredient =
+        IngredientRepo::create(repo.as_ref(), &utx, IngredientPatch::default()).await?;
+    let recipe_apis = recipe_rest_filters("api", repo.clone()).recover(handle_rejection);
+
+    let recipe_patch = RecipePatch {
+        recipe_patch: RecipePatchInner {
+            title: PatchValue::Value("New Recipe".to_string()),
+            ..Default::default()
+        },
+        ingredients: PatchValue::Value(vec![RecipeIngredientPatch {
+            ingredient_id: ingredient.id,
+            ingredient_name: "tomatoes".to_string(),
+            quantity: "1 cup".to_string(),
+        }]),
+    };
+
+    // -- ACTION - create
+    let response = warp::test::request()
+        .method("POST")
+        .path("/api/recipes")
+        .header("X-Auth-Token", "123")
+        .json(&recipe_patch)
+        .reply(&recipe_apis)
+        .await;
+
+    // -- CHECK - create
+    assert_eq!(response.status(), 200);
+    assert_eq!(response.headers()["ETag"], "\"1\"");
+    let body: serde_json::Value = serde_json::from_slice(response.body())?;
+    assert_eq!(body["data"][0]["id"], 1000);
+    assert_eq!(body["data"][1][0]["ingredient_name"], "tomatoes");
+
+    // -- ACTION - update with a stale If-Match
+    let response = warp::test::request()
+        .method("PATCH")
+        .path("/api/recipes/1000")
+        .header("X-Auth-Token", "123")
+        .header("If-Match", "\"2\"")
+        .json(&json!({"recipe_patch": {"title": "Updated Recipe"}}))
+        .reply(&recipe_apis)
+        .await;
+
+    // -- CHECK - update
+    assert_eq!(response.status(), 412);
+    let (recipe, ingredients) = RecipeRepo::get(repo.as_ref(), &utx, 1000).await?;
+    assert_eq!("New Recipe", recipe.title);
+    assert_eq!(1, ingredients.len());
 
     Ok(())
 }
@@ -0,0 +1,93 @@
+use crate::model::{
+    fixture::{Seed, TestDb},
+    DbRepo,
+};
+use crate::security::utx_from_token;
+use crate::web::handle_rejection;
+use crate::web::recipe::recipe_rest_filters;
+use crate::web::recipe_review::recipe_review_rest_filters;
+use anyhow::Result;
+use serde_json::json;
+use std::sync::Arc;
+use warp::Filter;
+
+#[tokio::test]
+async fn web_recipe_review_upsert_and_list() -> Result<()> {
+    // -- FIXTURE
+    let test_db = TestDb::new().await?;
+    let db = test_db.arc();
+    let seed = Seed::create(&db, &utx_from_token("123").await?).await?;
+    let apis = recipe_review_rest_filters("api", db.clone())
+        .or(recipe_rest_filters(
+            "api",
+            Arc::new(DbRepo::new(db.clone())),
+        ))
+        .recover(handle_rejection);
+
+    // -- ACTION - create own review
+    let response = warp::test::request()
+        .method("PUT")
+        .path(&format!("/api/recipes/{}/reviews/mine", seed.recipe.id))
+        .header("X-Auth-Token", "123")
+        .json(&json!({"rating": 4, "body": "Great"}))
+        .reply(&apis)
+        .await;
+
+    // -- CHECK
+    assert_eq!(response.status(), 200);
+    let body: serde_json::Value = serde_json::from_slice(response.body())?;
+    assert_eq!(body["data"]["rating"], 4);
+    assert_eq!(body["data"]["cid"], 123);
+
+    // -- ACTION - list
+    let response = warp::test::request()
+        .method("GET")
+        .path(&format!("/api/recipes/{}/reviews", seed.recipe.id))
+        .header("X-Auth-Token", "123")
+        .reply(&apis)
+        .await;
+
+    // -- CHECK
+    assert_eq!(response.status(), 200);
+    let body: serde_json::Value = serde_json::from_slice(response.body())?;
+    assert_eq!(body["data"][0]["body"], "Great");
+
+    // -- ACTION - recipe list sorted by rating
+    let response = warp::test::request()
+        .method("GET")
+        .path("/api/recipes?sort=rating")
+        .header("X-Auth-Token", "123")
+        .reply(&apis)
+        .await;
+
+    // -- CHECK
+    assert_eq!(response.status(), 200);
+    let body: serde_json::Value = serde_json::from_slice(response.body())?;
+    assert_eq!(body["data"][0][0]["rating_avg"], 4.0);
+    assert_eq!(body["data"][0][0]["rating_count"], 1);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn web_recipe_review_invalid() -> Result<()> {
+    // -- FIXTURE
+    let test_db = TestDb::new().await?;
+    let db = test_db.arc();
+    let seed = Seed::create(&db, &utx_from_token("123").await?).await?;
+    let apis = recipe_review_rest_filters("api", db.clone()).recover(handle_rejection);
+
+    // -- ACTION
+    let response = warp::test::request()
+        .method("PUT")
+        .path(&format!("/api/recipes/{}/reviews/mine", seed.recipe.id))
+        .header("X-Auth-Token", "123")
+        .json(&json!({"rating": 0}))
+        .reply(&apis)
+        .await;
+
+    // -- CHECK
+    assert_eq!(response.status(), 422);
+
+    Ok(())
+}
@@ -0,0 +1,86 @@
+use crate::model::{
+    fixture::{Seed, TestDb},
+    DbRepo, PatchValue, RecipePatch, RecipePatchInner,
+};
+use crate::security::utx_from_token;
+use crate::web::handle_rejection;
+use crate::web::recipe::recipe_rest_filters;
+use crate::web::recipe_revision::recipe_revision_rest_filters;
+use anyhow::Result;
+use std::sync::Arc;
+use warp::Filter;
+
+#[tokio::test]
+async fn web_recipe_revision_list_and_restore() -> Result<()> {
+    // -- FIXTURE
+    let test_db = TestDb::new().await?;
+    let db = test_db.arc();
+    let seed = Seed::create(&db, &utx_from_token("123").await?).await?;
+    let recipe_apis = recipe_rest_filters("api", Arc::new(DbRepo::new(db.clone())))
+        .or(recipe_revision_rest_filters("api", db.clone()))
+        .recover(handle_rejection);
+
+    let recipe_patch = RecipePatch {
+        recipe_patch: RecipePatchInner {
+            title: PatchValue::Value("Updated Recipe".to_string()),
+            ..Default::default()
+        },
+        ingredients: PatchValue::Absent,
+    };
+    warp::test::request()
+        .method("PATCH")
+        .path(&format!("/api/recipes/{}", seed.recipe.id))
+        .header("X-Auth-Token", "123")
+        .json(&recipe_patch)
+        .reply(&recipe_apis)
+        .await;
+
+    // -- ACTION - list
+    let response = warp::test::request()
+        .method("GET")
+        .path(&format!("/api/recipes/{}/revisions", seed.recipe.id))
+        .header("X-Auth-Token", "123")
+        .reply(&recipe_apis)
+        .await;
+
+    // -- CHECK - list
+    assert_eq!(response.status(), 200);
+    let body: serde_json::Value = serde_json::from_slice(response.body()).unwrap();
+    let revisions = body["data"].as_array().unwrap();
+    assert_eq!(2, revisions.len());
+    assert_eq!(revisions[0]["title"], "Updated Recipe");
+
+    // -- ACTION - diff
+    let response = warp::test::request()
+        .method("GET")
+        .path(&format!(
+            "/api/recipes/{}/revisions/diff?from=1&to=2",
+            seed.recipe.id
+        ))
+        .header("X-Auth-Token", "123")
+        .reply(&recipe_apis)
+        .await;
+
+    // -- CHECK - diff
+    assert_eq!(response.status(), 200);
+    let body: serde_json::Value = serde_json::from_slice(response.body()).unwrap();
+    assert_eq!(body["data"]["changes"][0]["field"], "title");
+
+    // -- ACTION - restore
+    let response = warp::test::request()
+        .method("POST")
+        .path(&format!(
+            "/api/recipes/{}/revisions/1/restore",
+            seed.recipe.id
+        ))
+        .header("X-Auth-Token", "123")
+        .reply(&recipe_apis)
+        .await;
+
+    // -- CHECK - restore
+    assert_eq!(response.status(), 200);
+    let body: serde_json::Value = serde_json::from_slice(response.body()).unwrap();
+    assert_eq!(body["data"][0]["title"], "spaghetti");
+
+    Ok(())
+}
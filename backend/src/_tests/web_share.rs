@@ -0,0 +1,42 @@
+use crate::model::fixture::{Seed, TestDb};
+use crate::security::utx_from_token;
+use crate::web::handle_rejection;
+use crate::web::share::share_rest_filters;
+use anyhow::Result;
+use serde_json::json;
+use warp::Filter;
+
+#[tokio::test]
+async fn web_share_view_without_auth() -> Result<()> {
+    // -- FIXTURE
+    let test_db = TestDb::new().await?;
+    let db = test_db.arc();
+    let seed = Seed::create(&db, &utx_from_token("123").await?).await?;
+    let apis = share_rest_filters("api", db.clone()).recover(handle_rejection);
+    let response = warp::test::request()
+        .method("POST")
+        .path("/api/shares")
+        .header("X-Auth-Token", "123")
+        .json(&json!({"typ": "recipe", "target_id": seed.recipe.id}))
+        .reply(&apis)
+        .await;
+    let body: serde_json::Value = serde_json::from_slice(response.body())?;
+    let token = body["data"]["token"].as_str().unwrap().to_string();
+
+    // -- ACTION
+    let response = warp::test::request()
+        .method("GET")
+        .path(&format!("/api/shared/{}", token))
+        .reply(&apis)
+        .await;
+
+    // -- CHECK - public, without owner data
+    assert_eq!(response.status(), 200);
+    let body: serde_json::Value = serde_json::from_slice(response.body())?;
+    assert_eq!(body["data"]["typ"], "recipe");
+    assert_eq!(body["data"]["title"], "spaghetti");
+    assert!(body["data"].get("cid").is_none());
+    assert!(body["data"]["ingredients"][0].get("cid").is_none());
+
+    Ok(())
+}
@@ -0,0 +1,43 @@
+use super::{redact_query, traced, HEADER_REQUEST_ID};
+use anyhow::Result;
+use hyper::{Body, Request};
+use warp::Filter;
+
+#[tokio::test]
+async fn web_trace_request_id() -> Result<()> {
+    // -- FIXTURE - a route echoing the request id it sees
+    let routes = warp::header::<String>(HEADER_REQUEST_ID).map(|id: String| id);
+    let svc = warp::service(routes);
+
+    // -- ACTION - id sent by the client
+    let req = Request::get("/")
+        .header(HEADER_REQUEST_ID, "abc-123")
+        .body(Body::empty())?;
+    let res = traced(svc, req).await?;
+
+    // -- CHECK - kept as is
+    assert_eq!("abc-123", res.headers()[HEADER_REQUEST_ID]);
+    let body = hyper::body::to_bytes(res.into_body()).await?;
+    assert_eq!("abc-123", body);
+
+    // -- ACTION - no id, or not a usable one
+    let req = Request::get("/")
+        .header(HEADER_REQUEST_ID, "bad id")
+        .body(Body::empty())?;
+    let res = traced(svc, req).await?;
+
+    // -- CHECK - generated, and the same for the routes and the response
+    let request_id = res.headers()[HEADER_REQUEST_ID].to_str()?.to_string();
+    assert_eq!(16, request_id.len());
+    let body = hyper::body::to_bytes(res.into_body()).await?;
+    assert_eq!(request_id, body);
+
+    Ok(())
+}
+
+#[test]
+fn web_trace_redact_query() {
+    assert_eq!("token=***&since=3", redact_query("token=123&since=3"));
+    assert_eq!("typ=recipe", redact_query("typ=recipe"));
+    assert_eq!("", redact_query(""));
+}
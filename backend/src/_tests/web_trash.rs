@@ -0,0 +1,60 @@
+use crate::model::{
+    fixture::{Seed, TestDb},
+    DbRepo,
+};
+use crate::security::utx_from_token;
+use crate::web::handle_rejection;
+use crate::web::ingredient::ingredient_rest_filters;
+use crate::web::trash::trash_rest_filters;
+use anyhow::Result;
+use std::sync::Arc;
+use warp::Filter;
+
+#[tokio::test]
+async fn web_trash_list_and_restore() -> Result<()> {
+    // -- FIXTURE
+    let test_db = TestDb::new().await?;
+    let db = test_db.arc();
+    let seed = Seed::create(&db, &utx_from_token("123").await?).await?;
+    let apis = ingredient_rest_filters("api", Arc::new(DbRepo::new(db.clone())))
+        .or(trash_rest_filters("api", db.clone()))
+        .recover(handle_rejection);
+    warp::test::request()
+        .method("DELETE")
+        .path(&format!("/api/ingredients/{}", seed.ingredient.id))
+        .header("X-Auth-Token", "123")
+        .reply(&apis)
+        .await;
+
+    // -- ACTION - list
+    let response = warp::test::request()
+        .method("GET")
+        .path("/api/trash")
+        .header("X-Auth-Token", "123")
+        .reply(&apis)
+        .await;
+
+    // -- CHECK - list
+    assert_eq!(response.status(), 200);
+    let body: serde_json::Value = serde_json::from_slice(response.body()).unwrap();
+    assert_eq!(body["data"][0]["typ"], "ingredient");
+    assert_eq!(body["data"][0]["id"], seed.ingredient.id);
+
+    // -- ACTION - restore
+    let response = warp::test::request()
+        .method("POST")
+        .path(&format!(
+            "/api/trash/ingredients/{}/restore",
+            seed.ingredient.id
+        ))
+        .header("X-Auth-Token", "123")
+        .reply(&apis)
+        .await;
+
+    // -- CHECK - restore
+    assert_eq!(response.status(), 200);
+    let body: serde_json::Value = serde_json::from_slice(response.body()).unwrap();
+    assert_eq!(body["data"]["name"], "tomatoes");
+
+    Ok(())
+}
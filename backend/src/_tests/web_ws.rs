@@ -0,0 +1,47 @@
+use crate::model::{fixture::TestDb, RecipeMac};
+use crate::security::utx_from_token;
+use crate::shutdown::Shutdown;
+use crate::web::ws::ws_filters;
+use anyhow::Result;
+use std::time::Duration;
+
+#[tokio::test]
+async fn web_ws_recipe_created() -> Result<()> {
+    // -- FIXTURE
+    let test_db = TestDb::new().await?;
+    let db = test_db.arc();
+    let mut client = warp::test::ws()
+        .path("/api/ws?token=123")
+        .handshake(ws_filters("api", Shutdown::new()))
+        .await?;
+    let utx = utx_from_token("123").await?;
+
+    // -- ACTION
+    let (recipe, _) = RecipeMac::create(&db, &utx, Default::default()).await?;
+
+    // -- CHECK - other tests share the bus, so skip their events
+    loop {
+        let msg = tokio::time::timeout(Duration::from_secs(5), client.recv()).await??;
+        let event: serde_json::Value = serde_json::from_str(msg.to_str().unwrap())?;
+        if event["typ"] == "recipe" && event["id"] == recipe.id && event["action"] == "created" {
+            assert_eq!(event["cid"], 123);
+            break;
+        }
+    }
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn web_ws_missing_token() -> Result<()> {
+    // -- ACTION
+    let result = warp::test::ws()
+        .path("/api/ws")
+        .handshake(ws_filters("api", Shutdown::new()))
+        .await;
+
+    // -- CHECK
+    assert!(result.is_err(), "handshake should be rejected");
+
+    Ok(())
+}
@@ -0,0 +1,208 @@
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+
+use super::{Dataset, Error};
+use crate::model::{
+    self, Db, Ingredient, IngredientMac, IngredientPatch, PatchValue, Recipe, RecipeIngredientMac,
+    RecipeIngredientPatch, RecipeMac, RecipePatch, RecipePatchInner,
+};
+use crate::security::{utx_system, UserCtx};
+
+/// Owner of the seeded recipes, as in the dev seed.
+const SEED_CID: i64 = 123;
+
+// region: Seed
+/// (name, quantity)
+type SeedIngredient = (&'static str, &'static str);
+/// (title, [(ingredient name, quantity)])
+type SeedRecipe = (&'static str, &'static [(&'static str, &'static str)]);
+
+const DEV_INGREDIENTS: &[SeedIngredient] = &[("tomatoes", "3 pieces")];
+const DEV_RECIPES: &[SeedRecipe] = &[("spaghetti", &[("tomatoes", "200 g")])];
+
+const DEMO_INGREDIENTS: &[SeedIngredient] = &[
+    ("tomatoes", "3 pieces"),
+    ("spaghetti", "500 g"),
+    ("garlic", "1 head"),
+    ("basil", "1 bunch"),
+    ("olive oil", "1 bottle"),
+    ("bread", "1 loaf"),
+    ("eggs", "6 pieces"),
+    ("flour", "1 kg"),
+    ("milk", "1 l"),
+];
+const DEMO_RECIPES: &[SeedRecipe] = &[
+    (
+        "spaghetti al pomodoro",
+        &[
+            ("spaghetti", "200 g"),
+            ("tomatoes", "400 g"),
+            ("garlic", "2 cloves"),
+            ("basil", "a few leaves"),
+            ("olive oil", "2 tbsp"),
+        ],
+    ),
+    (
+        "bruschetta",
+        &[
+            ("bread", "4 slices"),
+            ("tomatoes", "2 pieces"),
+            ("garlic", "1 clove"),
+            ("olive oil", "1 tbsp"),
+        ],
+    ),
+    (
+        "pancakes",
+        &[("flour", "125 g"), ("eggs", "2 pieces"), ("milk", "250 ml")],
+    ),
+];
+
+/// Add the ingredients and recipes of `dataset`, returns how many of each.
+pub async fn seed(
+    db: &Db,
+    utx: &UserCtx,
+    dataset: Dataset,
+) -> Result<(usize, usize), model::Error> {
+    let (ingredients, recipes) = match dataset {
+        Dataset::Dev => (DEV_INGREDIENTS, DEV_RECIPES),
+        Dataset::Demo => (DEMO_INGREDIENTS, DEMO_RECIPES),
+    };
+
+    let mut by_name: HashMap<&str, Ingredient> = HashMap::new();
+    for (name, quantity) in ingredients {
+        let data = IngredientPatch {
+            name: PatchValue::Value(name.to_string()),
+            quantity: PatchValue::Value(quantity.to_string()),
+        };
+        by_name.insert(name, IngredientMac::create(db, utx, data).await?);
+    }
+
+    for (title, links) in recipes {
+        let links = links
+            .iter()
+            .map(|(name, quantity)| RecipeIngredientPatch {
+                ingredient_id: by_name[name].id,
+                ingredient_name: name.to_string(),
+                quantity: quantity.to_string(),
+            })
+            .collect();
+        RecipeMac::create(db, utx, recipe_patch(title, Some(SEED_CID), links)).await?;
+    }
+
+    Ok((ingredients.len(), recipes.len()))
+}
+// endregion: Seed
+
+// region: Export
+/// The ingredients and recipes (with their ingredients) of `export`, newest first.
+/// Note: the trash, reviews, comments, favorites and collections are not part of it.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Export {
+    pub ingredients: Vec<Ingredient>,
+    pub recipes: Vec<(Recipe, Vec<RecipeIngredientMac>)>,
+}
+
+pub async fn export(db: &Db, utx: &UserCtx) -> Result<Export, model::Error> {
+    Ok(Export {
+        ingredients: IngredientMac::list(db, utx).await?,
+        recipes: RecipeMac::list(db, utx).await?,
+    })
+}
+
+/// Create the ingredients and recipes of `data` (oldest first, with new ids), returns how many of each.
+/// Note: fails before creating anything when a recipe uses an ingredient missing from `data`.
+pub async fn import(db: &Db, utx: &UserCtx, data: Export) -> Result<(usize, usize), Error> {
+    let ingredient_ids: HashSet<i64> = data.ingredients.iter().map(|i| i.id).collect();
+    for (recipe, links) in &data.recipes {
+        if let Some(link) = links
+            .iter()
+            .find(|link| !ingredient_ids.contains(&link.ingredient_id))
+        {
+            return Err(Error::ImportMissingIngredient(
+                recipe.title.clone(),
+                link.ingredient_id,
+            ));
+        }
+    }
+
+    // exported id -> new id
+    let mut new_ids: HashMap<i64, i64> = HashMap::new();
+    for ingredient in data.ingredients.iter().rev() {
+        let patch = IngredientPatch {
+            name: PatchValue::Value(ingredient.name.clone()),
+            quantity: PatchValue::Value(ingredient.quantity.clone()),
+        };
+        let created = IngredientMac::create(db, utx, patch).await?;
+        new_ids.insert(ingredient.id, created.id);
+    }
+
+    for (recipe, links) in data.recipes.iter().rev() {
+        let links = links
+            .iter()
+            .map(|link| RecipeIngredientPatch {
+                ingredient_id: new_ids[&link.ingredient_id],
+                ingredient_name: link.ingredient_name.clone(),
+                quantity: link.quantity.clone(),
+            })
+            .collect();
+        RecipeMac::create(db, utx, recipe_patch(&recipe.title, recipe.cid, links)).await?;
+    }
+
+    Ok((data.ingredients.len(), data.recipes.len()))
+}
+// endregion: Export
+
+// region: Check
+/// The issues found in the db, empty when it is fine.
+/// Fails (rather than reporting an issue) when the db cannot be reached.
+pub async fn check(db: &Db) -> Result<Vec<String>, model::Error> {
+    model::ping(db).await?;
+
+    let mut issues: Vec<String> = model::pending_migrations(db)
+        .await?
+        .into_iter()
+        .map(|file| format!("migration {} is not applied", file))
+        .collect();
+    // the schema is not all there, the next checks would only fail
+    if !issues.is_empty() {
+        return Ok(issues);
+    }
+
+    let utx = utx_system();
+    let recipes = RecipeMac::list(db, &utx).await?;
+    let ids: Vec<i64> = recipes
+        .iter()
+        .flat_map(|(_, links)| links.iter().map(|link| link.ingredient_id))
+        .collect::<HashSet<i64>>()
+        .into_iter()
+        .collect();
+    let live: HashSet<i64> = IngredientMac::get_many(db, &utx, &ids)
+        .await?
+        .into_iter()
+        .map(|ingredient| ingredient.id)
+        .collect();
+    for (recipe, links) in &recipes {
+        for link in links.iter().filter(|l| !live.contains(&l.ingredient_id)) {
+            issues.push(format!(
+                "recipe {} ({}) uses the missing or trashed ingredient {} ({})",
+                recipe.id, recipe.title, link.ingredient_id, link.ingredient_name
+            ));
+        }
+    }
+
+    Ok(issues)
+}
+// endregion: Check
+
+fn recipe_patch(title: &str, cid: Option<i64>, links: Vec<RecipeIngredientPatch>) -> RecipePatch {
+    RecipePatch {
+        recipe_patch: RecipePatchInner {
+            title: PatchValue::Value(title.to_string()),
+            cid: match cid {
+                Some(cid) => PatchValue::Value(cid),
+                None => PatchValue::Null,
+            },
+        },
+        ingredients: PatchValue::Value(links),
+    }
+}
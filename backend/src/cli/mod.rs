@@ -0,0 +1,232 @@
+use clap::{Args, Parser, Subcommand, ValueEnum};
+use std::io::{self, BufRead};
+use std::path::PathBuf;
+use thiserror::Error as ThisError;
+
+use crate::model::{self, Db, UserMac, UserPatch};
+use crate::security::utx_system;
+
+mod data;
+
+pub use data::{check, export, import, seed, Export};
+
+const DEFAULT_WEB_FOLDER: &str = "web-folder/";
+const DEFAULT_WEB_PORT: u16 = 8080;
+
+/// Cookbook server, and its admin commands. Runs the server when no command is given.
+#[derive(Parser)]
+#[command(name = "cookbook", version, args_conflicts_with_subcommands = true)]
+pub struct Cli {
+    #[command(subcommand)]
+    pub command: Option<Command>,
+
+    /// e.g. `cookbook ../frontend/web-folder`, same as `cookbook serve ../frontend/web-folder`
+    #[command(flatten)]
+    pub serve: ServeArgs,
+}
+
+impl Cli {
+    /// The command to run, `serve` when none is given.
+    pub fn command(self) -> Command {
+        self.command.unwrap_or(Command::Serve(self.serve))
+    }
+}
+
+#[derive(Subcommand)]
+pub enum Command {
+    /// Run the web server
+    Serve(ServeArgs),
+    /// Apply, revert or list the schema migrations
+    #[command(subcommand)]
+    Migrate(MigrateCommand),
+    /// Add a dataset of ingredients and recipes
+    Seed {
+        #[arg(value_enum)]
+        dataset: Dataset,
+    },
+    /// Manage the users
+    #[command(subcommand)]
+    User(UserCommand),
+    /// Write the ingredients and recipes (with their ingredients) to a JSON file
+    Export { file: PathBuf },
+    /// Add the ingredients and recipes of a file written by `export`
+    Import { file: PathBuf },
+    /// Check the db is reachable, migrated and consistent (exits with 1 otherwise)
+    Check,
+}
+
+#[derive(Args)]
+pub struct ServeArgs {
+    /// Folder of the static web content
+    #[arg(default_value = DEFAULT_WEB_FOLDER)]
+    pub web_folder: String,
+    /// Port to listen on (localhost only)
+    #[arg(long, default_value_t = DEFAULT_WEB_PORT)]
+    pub port: u16,
+    /// Do not purge the trash in the background
+    #[arg(long)]
+    pub no_trash_purge: bool,
+}
+
+#[derive(Subcommand)]
+pub enum MigrateCommand {
+    /// Apply the pending migrations
+    Up,
+    /// Revert the last applied migration
+    Down,
+    /// List the migrations, applied or pending
+    Status,
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+pub enum Dataset {
+    /// the dev seed, tomatoes and spaghetti
+    Dev,
+    /// a few more ingredients and recipes, to try the app
+    Demo,
+}
+
+#[derive(Subcommand)]
+pub enum UserCommand {
+    /// Create a user, and print its id (its X-Auth-Token)
+    Create(UserArgs),
+    /// Set a new password
+    ResetPassword(UserArgs),
+    /// List the users
+    List,
+}
+
+#[derive(Args)]
+pub struct UserArgs {
+    pub username: String,
+    /// Read from the first line of stdin when not given (keeps it out of the shell history)
+    #[arg(long)]
+    pub password: Option<String>,
+}
+
+/// Run an admin command (all but `serve`) against `db`, printing its outcome to stdout.
+pub async fn run(command: Command, db: &Db) -> Result<(), Error> {
+    let utx = utx_system();
+    match command {
+        Command::Serve(_) => return Err(Error::NotAnAdminCommand("serve")),
+
+        Command::Migrate(MigrateCommand::Up) => {
+            let applied = model::migrate_up(db).await?;
+            for file in &applied {
+                println!("applied {}", file);
+            }
+            println!("{} migration(s) applied", applied.len());
+        }
+        Command::Migrate(MigrateCommand::Down) => match model::migrate_down(db).await? {
+            Some(file) => println!("reverted {}", file),
+            None => println!("no migration to revert"),
+        },
+        Command::Migrate(MigrateCommand::Status) => {
+            for (file, applied) in model::migration_status(db).await? {
+                let status = if applied { "applied" } else { "pending" };
+                println!("{:<8} {}", status, file);
+            }
+        }
+
+        Command::Seed { dataset } => {
+            let (ingredients, recipes) = seed(db, &utx, dataset).await?;
+            println!(
+                "seeded {} ingredient(s), {} recipe(s)",
+                ingredients, recipes
+            );
+        }
+
+        Command::User(UserCommand::Create(args)) => {
+            let user = UserMac::create(db, &utx, user_patch(args)?).await?;
+            println!("created user {} ({})", user.id, user.username);
+        }
+        Command::User(UserCommand::ResetPassword(args)) => {
+            let user = UserMac::reset_password(db, &utx, user_patch(args)?).await?;
+            println!("reset the password of user {} ({})", user.id, user.username);
+        }
+        Command::User(UserCommand::List) => {
+            for user in UserMac::list(db, &utx).await? {
+                println!("{:>6} {:<24} {}", user.id, user.username, user.ctime);
+            }
+        }
+
+        Command::Export { file } => {
+            let data = export(db, &utx).await?;
+            std::fs::write(&file, serde_json::to_vec_pretty(&data)?)?;
+            println!(
+                "exported {} ingredient(s), {} recipe(s) to {}",
+                data.ingredients.len(),
+                data.recipes.len(),
+                file.display()
+            );
+        }
+        Command::Import { file } => {
+            let data: Export = serde_json::from_slice(&std::fs::read(&file)?)?;
+            let (ingredients, recipes) = import(db, &utx, data).await?;
+            println!(
+                "imported {} ingredient(s), {} recipe(s)",
+                ingredients, recipes
+            );
+        }
+
+        Command::Check => {
+            let issues = check(db).await?;
+            for issue in &issues {
+                println!("{}", issue);
+            }
+            if !issues.is_empty() {
+                return Err(Error::CheckFailed(issues.len()));
+            }
+            println!("db ok");
+        }
+    }
+
+    Ok(())
+}
+
+fn user_patch(args: UserArgs) -> Result<UserPatch, Error> {
+    let password = match args.password {
+        Some(password) => password,
+        None => {
+            let mut line = String::new();
+            io::stdin().lock().read_line(&mut line)?;
+            line.trim_end_matches(['\r', '\n']).to_string()
+        }
+    };
+
+    Ok(UserPatch {
+        username: args.username,
+        password,
+    })
+}
+
+#[allow(clippy::enum_variant_names)]
+#[derive(ThisError, Debug)]
+pub enum Error {
+    #[error("'{0}' is not an admin command.")]
+    NotAnAdminCommand(&'static str),
+
+    #[error("Check failed with {0} issue(s).")]
+    CheckFailed(usize),
+
+    #[error("Import failed, recipe '{0}' uses ingredient {1} which is not in the file.")]
+    ImportMissingIngredient(String, i64),
+
+    #[error(transparent)]
+    ModelError(#[from] model::Error),
+
+    #[error(transparent)]
+    SqlxError(#[from] sqlx::Error),
+
+    #[error(transparent)]
+    IOError(#[from] io::Error),
+
+    #[error(transparent)]
+    JsonError(#[from] serde_json::Error),
+}
+
+// region: Test
+#[cfg(test)]
+#[path = "../_tests/cli.rs"]
+mod tests;
+// endregion: Test
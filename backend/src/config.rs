@@ -0,0 +1,124 @@
+use std::{env, time::Duration};
+use warp::http::Method;
+
+const DEFAULT_TRASH_RETENTION_DAYS: i64 = 30;
+const DEFAULT_DB_CONNECT_TIMEOUT_MS: u64 = 5_000;
+const DEFAULT_DB_STARTUP_DEADLINE_SECS: u64 = 60;
+const DEFAULT_SHUTDOWN_DRAIN_TIMEOUT_SECS: u64 = 30;
+const DEFAULT_GRAPHQL_MAX_DEPTH: usize = 8;
+const DEFAULT_GRAPHQL_MAX_COMPLEXITY: usize = 500;
+const DEFAULT_RATE_LIMIT_READ: u32 = 600;
+const DEFAULT_RATE_LIMIT_WRITE: u32 = 60;
+const DEFAULT_RATE_LIMIT_AUTH: u32 = 10;
+const DEFAULT_CORS_ALLOWED_METHODS: &str = "GET,POST,PUT,PATCH,DELETE";
+const DEFAULT_CONTENT_SECURITY_POLICY: &str = "default-src 'self'; img-src 'self' data:; style-src 'self' 'unsafe-inline'; frame-ancestors 'none'";
+const DEFAULT_MAX_BODY_BYTES: u64 = 1024 * 1024;
+const DEFAULT_COMPRESSION_MIN_BYTES: usize = 1024;
+const DEFAULT_STATIC_MAX_AGE_SECS: u64 = 365 * 24 * 3600;
+
+pub struct Config {
+    /// Connect timeout of each DB connection attempt
+    pub db_connect_timeout: Duration,
+    /// How long the startup keeps retrying the DB before giving up
+    pub db_startup_deadline: Duration,
+    /// How long the in-flight requests get to complete on shutdown
+    pub shutdown_drain_timeout: Duration,
+    /// Days a deleted recipe or ingredient stays in the trash before being purged
+    pub trash_retention_days: i64,
+    /// Users allowed on the admin endpoints (e.g. the audit log), from a comma separated list
+    pub admin_user_ids: Vec<i64>,
+    /// Logs as JSON lines (LOG_FORMAT=json) instead of the pretty, human readable output
+    pub log_json: bool,
+    /// OTLP collector receiving the spans, only used with the "otel" feature
+    pub otel_endpoint: Option<String>,
+    /// Serve the Prometheus `/metrics` (METRICS_ENABLED=false to turn it off)
+    pub metrics_enabled: bool,
+    /// When set, `/metrics` requires `Authorization: Bearer <METRICS_TOKEN>`
+    pub metrics_token: Option<String>,
+    /// Deepest nesting a GraphQL query may have
+    pub graphql_max_depth: usize,
+    /// Highest complexity a GraphQL query may have (one per field, list relations count 10x)
+    pub graphql_max_complexity: usize,
+    /// API reads (GET) allowed per minute, per user and per client IP, 0 for no limit
+    pub rate_limit_read: u32,
+    /// API writes (POST, PATCH, DELETE...) allowed per minute, per user and per client IP, 0 for no limit
+    pub rate_limit_write: u32,
+    /// Requests with an invalid X-Auth-Token allowed per minute, per client IP, 0 for no limit
+    pub rate_limit_auth: u32,
+    /// Origins allowed to call the APIs from a browser (e.g. `https://cookbook.example`, `*` for any),
+    /// from a comma separated list, none (same origin only) by default
+    pub cors_allowed_origins: Vec<String>,
+    /// Methods allowed to those origins, from a comma separated list
+    pub cors_allowed_methods: Vec<Method>,
+    /// Content-Security-Policy of the replies that do not set their own
+    pub content_security_policy: String,
+    /// Largest request body accepted (413 above)
+    pub max_body_bytes: u64,
+    /// Compress the replies with brotli or gzip (COMPRESSION_ENABLED=false to turn it off)
+    pub compression_enabled: bool,
+    /// Smallest reply compressed
+    pub compression_min_bytes: usize,
+    /// How long the fingerprinted static files (e.g. `app.3f2a9c1b.js`) are cached, 0 to revalidate them all
+    pub static_max_age_secs: u64,
+}
+
+impl Config {
+    /// Read the config from the environment, falling back to the defaults
+    pub fn from_env() -> Config {
+        Config {
+            db_connect_timeout: Duration::from_millis(
+                env_parse("DB_CONNECT_TIMEOUT_MS").unwrap_or(DEFAULT_DB_CONNECT_TIMEOUT_MS),
+            ),
+            db_startup_deadline: Duration::from_secs(
+                env_parse("DB_STARTUP_DEADLINE_SECS").unwrap_or(DEFAULT_DB_STARTUP_DEADLINE_SECS),
+            ),
+            shutdown_drain_timeout: Duration::from_secs(
+                env_parse("SHUTDOWN_DRAIN_TIMEOUT_SECS")
+                    .unwrap_or(DEFAULT_SHUTDOWN_DRAIN_TIMEOUT_SECS),
+            ),
+            trash_retention_days: env_parse("TRASH_RETENTION_DAYS")
+                .unwrap_or(DEFAULT_TRASH_RETENTION_DAYS),
+            admin_user_ids: env_list("ADMIN_USER_IDS").unwrap_or_default(),
+            log_json: env::var("LOG_FORMAT").is_ok_and(|format| format == "json"),
+            otel_endpoint: env::var("OTEL_EXPORTER_OTLP_ENDPOINT").ok(),
+            metrics_enabled: env_parse("METRICS_ENABLED").unwrap_or(true),
+            metrics_token: env::var("METRICS_TOKEN").ok(),
+            graphql_max_depth: env_parse("GRAPHQL_MAX_DEPTH").unwrap_or(DEFAULT_GRAPHQL_MAX_DEPTH),
+            graphql_max_complexity: env_parse("GRAPHQL_MAX_COMPLEXITY")
+                .unwrap_or(DEFAULT_GRAPHQL_MAX_COMPLEXITY),
+            rate_limit_read: env_parse("RATE_LIMIT_READ").unwrap_or(DEFAULT_RATE_LIMIT_READ),
+            rate_limit_write: env_parse("RATE_LIMIT_WRITE").unwrap_or(DEFAULT_RATE_LIMIT_WRITE),
+            rate_limit_auth: env_parse("RATE_LIMIT_AUTH").unwrap_or(DEFAULT_RATE_LIMIT_AUTH),
+            cors_allowed_origins: env_list("CORS_ALLOWED_ORIGINS").unwrap_or_default(),
+            cors_allowed_methods: env_list("CORS_ALLOWED_METHODS").unwrap_or_else(|| {
+                DEFAULT_CORS_ALLOWED_METHODS
+                    .split(',')
+                    .filter_map(|method| method.parse().ok())
+                    .collect()
+            }),
+            content_security_policy: env::var("CONTENT_SECURITY_POLICY")
+                .unwrap_or_else(|_| DEFAULT_CONTENT_SECURITY_POLICY.to_string()),
+            max_body_bytes: env_parse("MAX_BODY_BYTES").unwrap_or(DEFAULT_MAX_BODY_BYTES),
+            compression_enabled: env_parse("COMPRESSION_ENABLED").unwrap_or(true),
+            compression_min_bytes: env_parse("COMPRESSION_MIN_BYTES")
+                .unwrap_or(DEFAULT_COMPRESSION_MIN_BYTES),
+            static_max_age_secs: env_parse("STATIC_MAX_AGE_SECS")
+                .unwrap_or(DEFAULT_STATIC_MAX_AGE_SECS),
+        }
+    }
+}
+
+fn env_parse<T: std::str::FromStr>(name: &str) -> Option<T> {
+    env::var(name).ok().and_then(|v| v.parse().ok())
+}
+
+/// A comma separated list, skipping the items that do not parse.
+fn env_list<T: std::str::FromStr>(name: &str) -> Option<Vec<T>> {
+    env::var(name).ok().map(|list| {
+        list.split(',')
+            .map(str::trim)
+            .filter(|item| !item.is_empty())
+            .filter_map(|item| item.parse().ok())
+            .collect()
+    })
+}
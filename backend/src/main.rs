@@ -1,31 +1,115 @@
 #![allow(unused)]
 
-use model::init_db;
-use std::{env, sync::Arc};
+use chrono::{Duration as ChronoDuration, Utc};
+use clap::Parser;
+use cli::{Cli, Command, ServeArgs};
+use config::Config;
+use model::{connect_db, init_db_with_retry, Db, TrashMac};
+use shutdown::Shutdown;
+use std::{sync::Arc, time::Duration};
+use tracing::{error, info, warn};
 use web::start_web;
 
+mod cli;
+mod config;
+mod metrics;
 mod model;
 mod security;
+mod shutdown;
+mod telemetry;
 mod web;
 
-const DEFAULT_WEB_FOLDER: &'static str = "web-folder/";
-const DEFAULT_WEB_PORT: u16 = 8080;
+const TRASH_PURGE_INTERVAL: Duration = Duration::from_secs(60 * 60);
 
 #[tokio::main]
 async fn main() {
-    // compute the web_folder
-    let mut args: Vec<String> = env::args().collect();
-    let web_folder = args.pop().unwrap_or_else(|| DEFAULT_WEB_FOLDER.to_string());
-    let web_port = DEFAULT_WEB_PORT;
-
-    // get the database
-    // TODO - loop until valid DB
-    let db = init_db().await.expect("Cannot init db");
+    let command = Cli::parse().command();
+    let config = Config::from_env();
+    telemetry::init_tracing(&config).expect("Cannot init tracing");
+
+    let code = match command {
+        Command::Serve(args) => serve(args, &config).await,
+        command => admin(command, &config).await,
+    };
+    telemetry::shutdown_tracing();
+    std::process::exit(code);
+}
+
+/// Run an admin command against the db as is (no recreate, no migration unless asked for).
+async fn admin(command: Command, config: &Config) -> i32 {
+    let db = match connect_db(config.db_connect_timeout).await {
+        Ok(db) => db,
+        Err(ex) => {
+            eprintln!("db not available: {}", ex);
+            return 1;
+        }
+    };
+
+    let result = cli::run(command, &db).await;
+    db.close().await;
+    match result {
+        Ok(()) => 0,
+        Err(ex) => {
+            eprintln!("{}", ex);
+            1
+        }
+    }
+}
+
+async fn serve(args: ServeArgs, config: &Config) -> i32 {
+    // get the database, waiting for it when it starts along with the server
+    let db = match init_db_with_retry(config.db_connect_timeout, config.db_startup_deadline).await {
+        Ok(db) => db,
+        Err(ex) => {
+            error!(cause = %ex, "db not available before the startup deadline");
+            return 1;
+        }
+    };
     let db = Arc::new(db);
 
+    // SIGINT/SIGTERM stop the server (draining the in-flight requests) and the background tasks
+    let shutdown = Shutdown::new();
+    tokio::spawn(shutdown::trigger_on_signal(shutdown.clone()));
+
+    // purge the trash in the background
+    if !args.no_trash_purge {
+        tokio::spawn(purge_trash_loop(
+            db.clone(),
+            config.trash_retention_days,
+            shutdown.clone(),
+        ));
+    }
+
     // start the server
-    match start_web(&web_folder, web_port, db).await {
-        Ok(_) => println!("Server ended"),
-        Err(ex) => println!("ERROR - web server failed to start. Cause: {:?}", ex),
+    let code = match start_web(&args.web_folder, args.port, db.clone(), config, shutdown).await {
+        Ok(_) => {
+            info!("server ended");
+            0
+        }
+        Err(ex) => {
+            error!(cause = %ex, "web server failed");
+            1
+        }
+    };
+
+    // the requests are drained, no more use of the pool
+    db.close().await;
+    info!("db pool closed");
+    code
+}
+
+async fn purge_trash_loop(db: Arc<Db>, retention_days: i64, shutdown: Shutdown) {
+    let mut interval = tokio::time::interval(TRASH_PURGE_INTERVAL);
+    loop {
+        tokio::select! {
+            _ = interval.tick() => (),
+            _ = shutdown.wait() => break,
+        }
+        let older_than = Utc::now() - ChronoDuration::days(retention_days);
+        match TrashMac::purge(&db, older_than).await {
+            Ok(0) => (),
+            Ok(count) => info!(count, "purged item(s) from the trash"),
+            Err(ex) => warn!(cause = %ex, "trash purge failed"),
+        }
     }
 }
@@ -0,0 +1,187 @@
+use prometheus::{
+    Encoder, Gauge, HistogramOpts, HistogramVec, IntCounterVec, IntGaugeVec, Opts, Registry,
+    TextEncoder,
+};
+use std::sync::OnceLock;
+use std::time::{Duration, Instant};
+use thiserror::Error as ThisError;
+
+use crate::model::{self, Db, IngredientMac, RecipeMac};
+
+const NAMESPACE: &str = "cookbook";
+/// Path segments kept as is in the route label, longer ones are taken for params (e.g. share tokens)
+const ROUTE_SEGMENT_MAX_LEN: usize = 20;
+
+static METRICS: OnceLock<Metrics> = OnceLock::new();
+
+// region: Metrics
+struct Metrics {
+    registry: Registry,
+    http_requests: IntCounterVec,
+    http_request_duration: HistogramVec,
+    db_pool_connections: IntGaugeVec,
+    db_pool_acquire_seconds: Gauge,
+    mac_duration: HistogramVec,
+    entities: IntGaugeVec,
+}
+
+impl Metrics {
+    fn new() -> Result<Metrics, prometheus::Error> {
+        let registry = Registry::new_custom(Some(NAMESPACE.to_string()), None)?;
+
+        let http_requests = IntCounterVec::new(
+            Opts::new("http_requests_total", "HTTP requests served"),
+            &["route", "method", "status"],
+        )?;
+        let http_request_duration = HistogramVec::new(
+            HistogramOpts::new("http_request_duration_seconds", "HTTP request latency"),
+            &["route", "method", "status"],
+        )?;
+        let db_pool_connections = IntGaugeVec::new(
+            Opts::new("db_pool_connections", "DB pool connections by state"),
+            &["state"],
+        )?;
+        let db_pool_acquire_seconds = Gauge::new(
+            "db_pool_acquire_seconds",
+            "Wait for a DB pool connection, probed at scrape",
+        )?;
+        let mac_duration = HistogramVec::new(
+            HistogramOpts::new(
+                "mac_duration_seconds",
+                "Model access calls, queries included",
+            ),
+            &["method"],
+        )?;
+        let entities = IntGaugeVec::new(
+            Opts::new("entities", "Live (not trashed) entities"),
+            &["typ"],
+        )?;
+
+        registry.register(Box::new(http_requests.clone()))?;
+        registry.register(Box::new(http_request_duration.clone()))?;
+        registry.register(Box::new(db_pool_connections.clone()))?;
+        registry.register(Box::new(db_pool_acquire_seconds.clone()))?;
+        registry.register(Box::new(mac_duration.clone()))?;
+        registry.register(Box::new(entities.clone()))?;
+
+        Ok(Metrics {
+            registry,
+            http_requests,
+            http_request_duration,
+            db_pool_connections,
+            db_pool_acquire_seconds,
+            mac_duration,
+            entities,
+        })
+    }
+}
+
+fn metrics() -> &'static Metrics {
+    METRICS.get_or_init(|| Metrics::new().expect("metrics are valid"))
+}
+// endregion: Metrics
+
+// region: Recording
+/// Count a served request, `path` is reduced to its route (e.g. "/api/recipes/:id").
+pub fn observe_request(path: &str, method: &str, status: u16, elapsed: Duration) {
+    let route = route_label(path);
+    let status = status.to_string();
+    let labels = [route.as_str(), method, status.as_str()];
+    let m = metrics();
+    m.http_requests.with_label_values(&labels).inc();
+    m.http_request_duration
+        .with_label_values(&labels)
+        .observe(elapsed.as_secs_f64());
+}
+
+/// Times a Mac method until dropped, e.g. `let _timer = metrics::mac_timer("RecipeMac::get");`
+pub fn mac_timer(method: &'static str) -> MacTimer {
+    MacTimer {
+        method,
+        start: Instant::now(),
+    }
+}
+
+pub struct MacTimer {
+    method: &'static str,
+    start: Instant,
+}
+
+impl Drop for MacTimer {
+    fn drop(&mut self) {
+        metrics()
+            .mac_duration
+            .with_label_values(&[self.method])
+            .observe(self.start.elapsed().as_secs_f64());
+    }
+}
+// endregion: Recording
+
+// region: Export
+/// Refresh the gauges read from the DB, then encode everything in the Prometheus text format.
+pub async fn render(db: &Db) -> Result<String, Error> {
+    let m = metrics();
+
+    let size = db.size() as i64;
+    let idle = db.num_idle() as i64;
+    m.db_pool_connections
+        .with_label_values(&["active"])
+        .set(size - idle);
+    m.db_pool_connections.with_label_values(&["idle"]).set(idle);
+    let start = Instant::now();
+    drop(db.acquire().await.map_err(model::Error::from)?);
+    m.db_pool_acquire_seconds.set(start.elapsed().as_secs_f64());
+
+    m.entities
+        .with_label_values(&["recipe"])
+        .set(RecipeMac::count(db).await?);
+    m.entities
+        .with_label_values(&["ingredient"])
+        .set(IngredientMac::count(db).await?);
+
+    let mut buffer = Vec::new();
+    TextEncoder::new().encode(&m.registry.gather(), &mut buffer)?;
+
+    Ok(String::from_utf8_lossy(&buffer).into_owned())
+}
+
+/// Numeric segments become ":id" and other non word ones ":param", so the label stays bounded.
+/// Anything outside of the API, the metrics and the health checks is the static site.
+pub fn route_label(path: &str) -> String {
+    let segments: Vec<&str> = path.split('/').filter(|s| !s.is_empty()).collect();
+    match segments.first() {
+        Some(&"api") | Some(&"metrics") | Some(&"health") => (),
+        _ => return "static".to_string(),
+    }
+
+    let route: Vec<&str> = segments
+        .into_iter()
+        .map(|segment| {
+            if segment.chars().all(|c| c.is_ascii_digit()) {
+                ":id"
+            } else if segment.len() <= ROUTE_SEGMENT_MAX_LEN
+                && segment.chars().all(|c| c.is_ascii_lowercase() || c == '_')
+            {
+                segment
+            } else {
+                ":param"
+            }
+        })
+        .collect();
+
+    format!("/{}", route.join("/"))
+}
+// endregion: Export
+
+#[derive(ThisError, Debug)]
+pub enum Error {
+    #[error(transparent)]
+    Model(#[from] model::Error),
+
+    #[error(transparent)]
+    Prometheus(#[from] prometheus::Error),
+}
+
+#[cfg(test)]
+#[path = "_tests/metrics.rs"]
+mod tests;
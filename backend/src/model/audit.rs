@@ -0,0 +1,148 @@
+use crate::{
+    metrics,
+    model::{self, db::Db},
+    security::UserCtx,
+};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use sqlx::types::Json;
+
+use super::sql_builder::SqlVal;
+
+const PAGE_LIMIT_MAX: i64 = 500;
+
+// region: Audit Types
+#[derive(sqlx::FromRow, Debug, Clone, Serialize, Deserialize)]
+pub struct AuditEntry {
+    pub id: i64,
+    /// "recipe", "ingredient" or "recipe_ingredients" (the full link list of recipe `entity_id`)
+    pub typ: String,
+    pub entity_id: i64,
+    /// "created", "updated", "deleted" or "restored"
+    pub action: String,
+    pub before_data: Option<Json<Value>>,
+    pub after_data: Option<Json<Value>>,
+    /// user who made the change
+    pub cid: i64,
+    pub request_id: Option<String>,
+    pub ctime: DateTime<Utc>,
+}
+
+/// Filters of the audit log query, all optional.
+#[derive(Default, Debug, Clone, Serialize, Deserialize)]
+pub struct AuditFilter {
+    pub typ: Option<String>,
+    pub entity_id: Option<i64>,
+    pub cid: Option<i64>,
+    pub request_id: Option<String>,
+    pub since: Option<DateTime<Utc>>,
+    pub until: Option<DateTime<Utc>>,
+    pub limit: Option<i64>,
+    pub offset: Option<i64>,
+}
+// endregion: Audit Types
+
+// region: AuditMac
+pub struct AuditMac;
+
+impl AuditMac {
+    /// Append an entry. There is no update nor delete, the log is append-only.
+    pub async fn record(
+        db: &Db,
+        utx: &UserCtx,
+        typ: &str,
+        entity_id: i64,
+        action: &str,
+        before: Option<Value>,
+        after: Option<Value>,
+    ) -> Result<(), model::Error> {
+        let _timer = metrics::mac_timer("AuditMac::record");
+        let sql_insert = "INSERT INTO audit_log (typ, entity_id, action, before_data, after_data, cid, request_id) VALUES (?, ?, ?, ?, ?, ?, ?)";
+        sqlx::query(sql_insert)
+            .bind(typ)
+            .bind(entity_id)
+            .bind(action)
+            .bind(before.map(Json))
+            .bind(after.map(Json))
+            .bind(utx.user_id)
+            .bind(&utx.request_id)
+            .execute(db)
+            .await?;
+
+        Ok(())
+    }
+
+    /// Newest entries first. Admins only.
+    pub async fn list(
+        db: &Db,
+        utx: &UserCtx,
+        filter: AuditFilter,
+    ) -> Result<Vec<AuditEntry>, model::Error> {
+        let _timer = metrics::mac_timer("AuditMac::list");
+        if !utx.is_admin {
+            return Err(model::Error::AccessDenied(
+                "audit_log",
+                utx.user_id.to_string(),
+            ));
+        }
+
+        let mut wheres: Vec<&str> = Vec::new();
+        let mut binds: Vec<SqlVal> = Vec::new();
+        if let Some(typ) = filter.typ {
+            wheres.push("typ = ?");
+            binds.push(typ.into());
+        }
+        if let Some(entity_id) = filter.entity_id {
+            wheres.push("entity_id = ?");
+            binds.push(entity_id.into());
+        }
+        if let Some(cid) = filter.cid {
+            wheres.push("cid = ?");
+            binds.push(cid.into());
+        }
+        if let Some(request_id) = filter.request_id {
+            wheres.push("request_id = ?");
+            binds.push(request_id.into());
+        }
+        if let Some(since) = filter.since {
+            wheres.push("ctime >= ?");
+            binds.push(since.into());
+        }
+        if let Some(until) = filter.until {
+            wheres.push("ctime < ?");
+            binds.push(until.into());
+        }
+        let sql_where = if wheres.is_empty() {
+            String::new()
+        } else {
+            format!("WHERE {}", wheres.join(" AND "))
+        };
+
+        let sql = format!(
+            "SELECT * FROM audit_log {} ORDER BY id DESC LIMIT ? OFFSET ?",
+            sql_where
+        );
+        let mut query = sqlx::query_as::<_, AuditEntry>(&sql);
+        for val in binds {
+            query = val.bind_as(query);
+        }
+        let entries = query
+            .bind(filter.limit.unwrap_or(100).clamp(1, PAGE_LIMIT_MAX))
+            .bind(filter.offset.unwrap_or(0).max(0))
+            .fetch_all(db)
+            .await?;
+
+        Ok(entries)
+    }
+}
+
+/// JSON snapshot of an entity for the before/after columns.
+pub(super) fn snapshot<T: Serialize>(data: &T) -> Option<Value> {
+    serde_json::to_value(data).ok()
+}
+// endregion: AuditMac
+
+#[cfg(test)]
+#[path = "../_tests/model_audit.rs"]
+mod tests;
@@ -0,0 +1,321 @@
+use crate::{
+    metrics,
+    model::{self, db::Db},
+    security::UserCtx,
+};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+
+use super::event::{ChangeAction, EventBus};
+use super::ingredient::{IngredientMac, IngredientPatch};
+use super::recipe::{RecipeMac, RecipePatch};
+use super::recipe_ingredient::RecipeIngredientMac;
+
+const PAGE_LIMIT_MAX: i64 = 500;
+
+// region: Change Feed Types
+#[derive(sqlx::FromRow, Debug, Clone)]
+struct ChangeRow {
+    seq: i64,
+    typ: String,
+    entity_id: i64,
+    action: String,
+}
+
+/// The latest state of a changed entity. `data` is `None` for a tombstone (action "deleted").
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Change {
+    pub seq: i64,
+    /// "recipe", "ingredient" or "recipe_ingredients" (the full link list of recipe `id`)
+    pub typ: String,
+    pub id: i64,
+    pub action: ChangeAction,
+    pub data: Option<Value>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ChangeFeed {
+    /// pass it back as `since` for the next page
+    pub cursor: i64,
+    pub changes: Vec<Change>,
+    /// more changes are waiting after `cursor`
+    pub has_more: bool,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ClientChangeTyp {
+    Recipe,
+    Ingredient,
+}
+
+/// A write made offline. Updates and deletes must carry the version they were based on.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ClientChange {
+    pub typ: ClientChangeTyp,
+    /// absent on create
+    #[serde(default)]
+    pub id: Option<i64>,
+    #[serde(default)]
+    pub base_version: Option<i64>,
+    pub action: ChangeAction,
+    /// RecipePatch or IngredientPatch, ignored on delete
+    #[serde(default)]
+    pub data: Value,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "status", rename_all = "snake_case")]
+pub enum ClientChangeResult {
+    Applied {
+        id: i64,
+        version: i64,
+    },
+    /// the entity moved on since `base_version`, nothing was written
+    Conflict {
+        id: i64,
+        base_version: i64,
+        current_version: i64,
+        current: Value,
+    },
+    Rejected {
+        error: String,
+    },
+}
+// endregion: Change Feed Types
+
+// region: ChangeFeedMac
+pub struct ChangeFeedMac;
+
+impl ChangeFeedMac {
+    /// Append a change to the feed, and tell the live listeners.
+    pub async fn record(
+        db: &Db,
+        utx: &UserCtx,
+        typ: &str,
+        id: i64,
+        action: ChangeAction,
+    ) -> Result<(), model::Error> {
+        let _timer = metrics::mac_timer("ChangeFeedMac::record");
+        let sql_insert = "INSERT INTO changes (typ, entity_id, action, cid) VALUES (?, ?, ?, ?)";
+        sqlx::query(sql_insert)
+            .bind(typ)
+            .bind(id)
+            .bind(action.as_str())
+            .bind(utx.user_id)
+            .execute(db)
+            .await?;
+
+        EventBus::publish(typ, id, action, utx.user_id);
+
+        Ok(())
+    }
+
+    /// Changes after `since` (0 for everything), one per entity with its current state.
+    pub async fn since(
+        db: &Db,
+        utx: &UserCtx,
+        since: i64,
+        limit: i64,
+    ) -> Result<ChangeFeed, model::Error> {
+        let _timer = metrics::mac_timer("ChangeFeedMac::since");
+        let limit = limit.clamp(1, PAGE_LIMIT_MAX);
+        let sql =
+            "SELECT seq, typ, entity_id, action FROM changes WHERE seq > ? ORDER BY seq LIMIT ?";
+        let rows = sqlx::query_as::<_, ChangeRow>(sql)
+            .bind(since)
+            .bind(limit + 1)
+            .fetch_all(db)
+            .await?;
+
+        let has_more = rows.len() as i64 > limit;
+        let rows: Vec<ChangeRow> = rows.into_iter().take(limit as usize).collect();
+        let cursor = rows.last().map(|row| row.seq).unwrap_or(since);
+
+        // Only the last change of each entity matters, the state is read now anyway
+        let mut latest: Vec<ChangeRow> = Vec::new();
+        for row in rows {
+            latest.retain(|r| !(r.typ == row.typ && r.entity_id == row.entity_id));
+            latest.push(row);
+        }
+
+        let mut changes = Vec::new();
+        for row in latest {
+            let data = current_state(db, utx, &row.typ, row.entity_id).await?;
+            let action = match data {
+                None => ChangeAction::Deleted,
+                Some(_) => ChangeAction::parse(&row.action),
+            };
+            changes.push(Change {
+                seq: row.seq,
+                typ: row.typ,
+                id: row.entity_id,
+                action,
+                data,
+            });
+        }
+
+        Ok(ChangeFeed {
+            cursor,
+            changes,
+            has_more,
+        })
+    }
+
+    /// Apply offline writes in order. Each one succeeds, conflicts or is rejected on its own.
+    pub async fn apply(
+        db: &Db,
+        utx: &UserCtx,
+        changes: Vec<ClientChange>,
+    ) -> Result<Vec<ClientChangeResult>, model::Error> {
+        let _timer = metrics::mac_timer("ChangeFeedMac::apply");
+        let mut results = Vec::new();
+        for change in changes {
+            let result = match apply_one(db, utx, &change).await {
+                Ok((id, version)) => ClientChangeResult::Applied { id, version },
+                Err(model::Error::VersionMismatch(_, _)) => {
+                    // apply_one only gets there with an id and a base version
+                    let id = change.id.unwrap_or_default();
+                    let current = match change.typ {
+                        ClientChangeTyp::Recipe => json!(RecipeMac::get(db, utx, id).await?),
+                        ClientChangeTyp::Ingredient => {
+                            json!(IngredientMac::get(db, utx, id).await?)
+                        }
+                    };
+                    let current_version = match change.typ {
+                        ClientChangeTyp::Recipe => current[0]["version"].as_i64(),
+                        ClientChangeTyp::Ingredient => current["version"].as_i64(),
+                    };
+                    ClientChangeResult::Conflict {
+                        id,
+                        base_version: change.base_version.unwrap_or_default(),
+                        current_version: current_version.unwrap_or_default(),
+                        current,
+                    }
+                }
+                Err(model::Error::SqlxError(ex)) => return Err(model::Error::SqlxError(ex)),
+                Err(ex) => ClientChangeResult::Rejected {
+                    error: ex.to_string(),
+                },
+            };
+            results.push(result);
+        }
+
+        Ok(results)
+    }
+}
+// endregion: ChangeFeedMac
+
+// region: Utils
+impl ChangeAction {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ChangeAction::Created => "created",
+            ChangeAction::Updated => "updated",
+            ChangeAction::Deleted => "deleted",
+        }
+    }
+
+    fn parse(action: &str) -> ChangeAction {
+        match action {
+            "created" => ChangeAction::Created,
+            "deleted" => ChangeAction::Deleted,
+            _ => ChangeAction::Updated,
+        }
+    }
+}
+
+/// `None` when the entity is gone (trashed or purged).
+async fn current_state(
+    db: &Db,
+    utx: &UserCtx,
+    typ: &str,
+    id: i64,
+) -> Result<Option<Value>, model::Error> {
+    let state = match typ {
+        "recipe" => RecipeMac::get(db, utx, id)
+            .await
+            .map(|(recipe, _)| json!(recipe)),
+        "ingredient" => IngredientMac::get(db, utx, id).await.map(|i| json!(i)),
+        _ => match RecipeMac::get(db, utx, id).await {
+            Ok(_) => Ok(json!(RecipeIngredientMac::list_by_recipe(db, id).await?)),
+            Err(ex) => Err(ex),
+        },
+    };
+
+    match state {
+        Ok(state) => Ok(Some(state)),
+        Err(model::Error::EntityNotFound(_, _)) => Ok(None),
+        Err(ex) => Err(ex),
+    }
+}
+
+/// Returns the id and new version of the written entity (the old version for a delete).
+async fn apply_one(
+    db: &Db,
+    utx: &UserCtx,
+    change: &ClientChange,
+) -> Result<(i64, i64), model::Error> {
+    let target = match change.action {
+        ChangeAction::Created => None,
+        _ => match (change.id, change.base_version) {
+            (Some(id), Some(version)) => Some((id, version)),
+            _ => {
+                return Err(model::Error::ValidationFailed(vec![model::FieldError {
+                    field: "base_version".to_string(),
+                    reason: "id and base_version are required to update or delete".to_string(),
+                }]))
+            }
+        },
+    };
+
+    match (change.typ, change.action, target) {
+        (ClientChangeTyp::Recipe, ChangeAction::Created, _) => {
+            let (recipe, _) = RecipeMac::create(db, utx, parse_data(&change.data)?).await?;
+            Ok((recipe.id, recipe.version))
+        }
+        (ClientChangeTyp::Recipe, ChangeAction::Updated, Some((id, version))) => {
+            let data: RecipePatch = parse_data(&change.data)?;
+            let (recipe, _) = RecipeMac::update_if_match(db, utx, id, version, data).await?;
+            Ok((recipe.id, recipe.version))
+        }
+        (ClientChangeTyp::Recipe, ChangeAction::Deleted, Some((id, version))) => {
+            let (recipe, _) = RecipeMac::delete_if_match(db, utx, id, version).await?;
+            Ok((recipe.id, recipe.version))
+        }
+        (ClientChangeTyp::Ingredient, ChangeAction::Created, _) => {
+            let ingredient = IngredientMac::create(db, utx, parse_data(&change.data)?).await?;
+            Ok((ingredient.id, ingredient.version))
+        }
+        (ClientChangeTyp::Ingredient, ChangeAction::Updated, Some((id, version))) => {
+            let data: IngredientPatch = parse_data(&change.data)?;
+            let ingredient = IngredientMac::update_if_match(db, utx, id, version, data).await?;
+            Ok((ingredient.id, ingredient.version))
+        }
+        (ClientChangeTyp::Ingredient, ChangeAction::Deleted, Some((id, version))) => {
+            let ingredient = IngredientMac::delete_if_match(db, utx, id, version).await?;
+            Ok((ingredient.id, ingredient.version))
+        }
+        // updates and deletes always have a target at this point
+        (_, _, None) => unreachable!(),
+    }
+}
+
+fn parse_data<T: for<'de> Deserialize<'de>>(data: &Value) -> Result<T, model::Error> {
+    let data = if data.is_null() {
+        json!({})
+    } else {
+        data.clone()
+    };
+    serde_json::from_value(data).map_err(|ex| {
+        model::Error::ValidationFailed(vec![model::FieldError {
+            field: "data".to_string(),
+            reason: ex.to_string(),
+        }])
+    })
+}
+// endregion: Utils
+
+#[cfg(test)]
+#[path = "../_tests/model_change_feed.rs"]
+mod tests;
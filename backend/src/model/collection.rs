@@ -0,0 +1,273 @@
+use crate::{
+    metrics,
+    model::{
+        self,
+        db::{self, Db},
+    },
+    security::UserCtx,
+};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+use super::patch::PatchValue;
+use super::recipe::RecipeMac;
+use super::sql_builder::UpdateBuilder;
+use super::validate::{FieldError, TextRule, Validate, Validator};
+
+// region: Collection Types
+#[derive(sqlx::FromRow, Debug, Clone, Serialize, Deserialize)]
+pub struct Collection {
+    pub id: i64,
+    pub name: String,
+    pub description: Option<String>,
+    /// owner of the collection
+    pub cid: i64,
+    pub ctime: DateTime<Utc>,
+    pub mtime: DateTime<Utc>,
+}
+
+/// A recipe of a collection, in collection order.
+#[derive(sqlx::FromRow, Debug, Clone, Serialize, Deserialize)]
+pub struct CollectionItem {
+    pub recipe_id: i64,
+    pub title: String,
+    pub position: i64,
+}
+
+#[derive(Default, Debug, Clone, Serialize, Deserialize)]
+pub struct CollectionPatch {
+    #[serde(default, skip_serializing_if = "PatchValue::is_absent")]
+    pub name: PatchValue<String>,
+    /// `null` clears it
+    #[serde(default, skip_serializing_if = "PatchValue::is_absent")]
+    pub description: PatchValue<String>,
+}
+
+impl Validate for CollectionPatch {
+    fn check(&mut self, v: &mut Validator) {
+        v.text("name", &mut self.name, TextRule::required(255))
+            .text_nullable(
+                "description",
+                &mut self.description,
+                TextRule::optional(2000),
+            );
+    }
+}
+// endregion: Collection Types
+
+// region: CollectionMac
+pub struct CollectionMac;
+
+impl CollectionMac {
+    pub async fn create(
+        db: &Db,
+        utx: &UserCtx,
+        mut data: CollectionPatch,
+    ) -> Result<(Collection, Vec<CollectionItem>), model::Error> {
+        let _timer = metrics::mac_timer("CollectionMac::create");
+        data.validate()?;
+
+        let name =
+            data.name
+                .not_null_or("collections", "name", "Untitled Collection".to_string())?;
+
+        let sql_insert = "INSERT INTO collections (name, description, cid) VALUES (?, ?, ?)";
+        let result = sqlx::query(sql_insert)
+            .bind(name)
+            .bind(data.description.value())
+            .bind(utx.user_id)
+            .execute(db)
+            .await?;
+
+        Self::get(db, utx, db::last_insert_id(&result)).await
+    }
+
+    /// Collections are personal, the ones of other users are not found.
+    pub async fn get(
+        db: &Db,
+        utx: &UserCtx,
+        id: i64,
+    ) -> Result<(Collection, Vec<CollectionItem>), model::Error> {
+        let _timer = metrics::mac_timer("CollectionMac::get");
+        let collection = get_owned(db, utx, id).await?;
+        let items = list_items(db, id).await?;
+
+        Ok((collection, items))
+    }
+
+    pub async fn list(db: &Db, utx: &UserCtx) -> Result<Vec<Collection>, model::Error> {
+        let _timer = metrics::mac_timer("CollectionMac::list");
+        let sql = "SELECT * FROM collections WHERE cid = ? ORDER BY name, id";
+        let collections = sqlx::query_as::<_, Collection>(sql)
+            .bind(utx.user_id)
+            .fetch_all(db)
+            .await?;
+
+        Ok(collections)
+    }
+
+    pub async fn update(
+        db: &Db,
+        utx: &UserCtx,
+        id: i64,
+        mut data: CollectionPatch,
+    ) -> Result<(Collection, Vec<CollectionItem>), model::Error> {
+        let _timer = metrics::mac_timer("CollectionMac::update");
+        data.validate()?;
+        get_owned(db, utx, id).await?;
+
+        // Note: mtime is set explicitly so an empty patch still makes a valid UPDATE
+        UpdateBuilder::new("collections")
+            .patch("name", data.name)?
+            .patch_nullable("description", data.description)
+            .set_raw("mtime", "CURRENT_TIMESTAMP")
+            .and_where_eq("id", id)
+            .and_where_eq("cid", utx.user_id)
+            .execute(db)
+            .await?;
+
+        Self::get(db, utx, id).await
+    }
+
+    pub async fn delete(
+        db: &Db,
+        utx: &UserCtx,
+        id: i64,
+    ) -> Result<(Collection, Vec<CollectionItem>), model::Error> {
+        let _timer = metrics::mac_timer("CollectionMac::delete");
+        let collection = Self::get(db, utx, id).await?;
+
+        let sql_delete = "DELETE FROM collections WHERE id = ? AND cid = ?";
+        sqlx::query(sql_delete)
+            .bind(id)
+            .bind(utx.user_id)
+            .execute(db)
+            .await?;
+
+        Ok(collection)
+    }
+
+    /// Append the recipe at the end of the collection (no-op when already in it).
+    pub async fn add_recipe(
+        db: &Db,
+        utx: &UserCtx,
+        id: i64,
+        recipe_id: i64,
+    ) -> Result<(Collection, Vec<CollectionItem>), model::Error> {
+        let _timer = metrics::mac_timer("CollectionMac::add_recipe");
+        get_owned(db, utx, id).await?;
+        RecipeMac::get(db, utx, recipe_id).await?;
+
+        let sql_insert = format!(
+            "{} INTO collection_recipes (collection_id, recipe_id, position)
+            SELECT ?, ?, COALESCE(MAX(position) + 1, 0) FROM collection_recipes WHERE collection_id = ?",
+            db::INSERT_IGNORE
+        );
+        sqlx::query(&sql_insert)
+            .bind(id)
+            .bind(recipe_id)
+            .bind(id)
+            .execute(db)
+            .await?;
+
+        Self::get(db, utx, id).await
+    }
+
+    pub async fn remove_recipe(
+        db: &Db,
+        utx: &UserCtx,
+        id: i64,
+        recipe_id: i64,
+    ) -> Result<(Collection, Vec<CollectionItem>), model::Error> {
+        let _timer = metrics::mac_timer("CollectionMac::remove_recipe");
+        get_owned(db, utx, id).await?;
+
+        let sql_delete = "DELETE FROM collection_recipes WHERE collection_id = ? AND recipe_id = ?";
+        let result = sqlx::query(sql_delete)
+            .bind(id)
+            .bind(recipe_id)
+            .execute(db)
+            .await?;
+        if result.rows_affected() == 0 {
+            return Err(model::Error::EntityNotFound(
+                "collection_recipes",
+                format!("{}@{}", id, recipe_id),
+            ));
+        }
+
+        Self::get(db, utx, id).await
+    }
+
+    /// Reorder the collection, `recipe_ids` must list every recipe of the collection once.
+    pub async fn reorder(
+        db: &Db,
+        utx: &UserCtx,
+        id: i64,
+        recipe_ids: Vec<i64>,
+    ) -> Result<(Collection, Vec<CollectionItem>), model::Error> {
+        let _timer = metrics::mac_timer("CollectionMac::reorder");
+        get_owned(db, utx, id).await?;
+
+        let mut current: Vec<i64> = list_items(db, id)
+            .await?
+            .into_iter()
+            .map(|item| item.recipe_id)
+            .collect();
+        current.sort_unstable();
+        let mut requested = recipe_ids.clone();
+        requested.sort_unstable();
+        if requested != current {
+            return Err(model::Error::ValidationFailed(vec![FieldError {
+                field: "recipe_ids".to_string(),
+                reason: "must list every recipe of the collection exactly once".to_string(),
+            }]));
+        }
+
+        let sql_update =
+            "UPDATE collection_recipes SET position = ? WHERE collection_id = ? AND recipe_id = ?";
+        for (position, recipe_id) in recipe_ids.into_iter().enumerate() {
+            sqlx::query(sql_update)
+                .bind(position as i64)
+                .bind(id)
+                .bind(recipe_id)
+                .execute(db)
+                .await?;
+        }
+
+        Self::get(db, utx, id).await
+    }
+}
+// endregion: CollectionMac
+
+// region: Utils
+async fn get_owned(db: &Db, utx: &UserCtx, id: i64) -> Result<Collection, model::Error> {
+    let sql = "SELECT * FROM collections WHERE id = ? AND cid = ?";
+    sqlx::query_as::<_, Collection>(sql)
+        .bind(id)
+        .bind(utx.user_id)
+        .fetch_one(db)
+        .await
+        .map_err(|sqlx_error| match sqlx_error {
+            sqlx::Error::RowNotFound => model::Error::EntityNotFound("collections", id.to_string()),
+            other => model::Error::SqlxError(other),
+        })
+}
+
+/// Recipes in the trash are hidden, they come back with the recipe.
+async fn list_items(db: &Db, id: i64) -> Result<Vec<CollectionItem>, model::Error> {
+    let sql = "SELECT cr.recipe_id, r.title, cr.position FROM collection_recipes cr
+        JOIN recipes r ON r.id = cr.recipe_id
+        WHERE cr.collection_id = ? AND r.deleted_at IS NULL
+        ORDER BY cr.position, cr.recipe_id";
+    let items = sqlx::query_as::<_, CollectionItem>(sql)
+        .bind(id)
+        .fetch_all(db)
+        .await?;
+
+    Ok(items)
+}
+// endregion: Utils
+
+#[cfg(test)]
+#[path = "../_tests/model_collection.rs"]
+mod tests;
@@ -1,55 +1,481 @@
-use sqlx::{mysql::MySqlPoolOptions, MySql, Pool};
-use std::{fs, path::PathBuf, time::Duration};
+#[cfg(not(feature = "sqlite"))]
+use sqlx::mysql::{MySqlConnectOptions, MySqlDatabaseError, MySqlPoolOptions};
+#[cfg(feature = "sqlite")]
+use sqlx::sqlite::{SqliteConnectOptions, SqlitePoolOptions};
+use sqlx::{database::HasArguments, ConnectOptions, Database, Pool};
+use std::{
+    fs,
+    future::Future,
+    path::{Path, PathBuf},
+    str::FromStr,
+    time::Duration,
+};
+use tokio::time::{sleep, Instant};
+use tracing::{error, info, warn};
 
+#[cfg(not(any(feature = "mysql", feature = "sqlite")))]
+compile_error!("enable one of the storage features: mysql (default) or sqlite");
+
+#[cfg(not(feature = "sqlite"))]
 const HOST: &str = "localhost:3306";
+#[cfg(not(feature = "sqlite"))]
 const ROOT_DB: &str = "cookbook";
+#[cfg(not(feature = "sqlite"))]
 const ROOT_USER: &str = "root";
+#[cfg(not(feature = "sqlite"))]
 const ROOT_PWD: &str = "macmacmac";
 
 // app db
+#[cfg(not(feature = "sqlite"))]
 const APP_DB: &str = "cookbook";
+#[cfg(not(feature = "sqlite"))]
 const APP_USER: &str = "bogdan";
+#[cfg(not(feature = "sqlite"))]
 const APP_PWD: &str = "macmacmac";
 const APP_MAX_CON: u32 = 5;
+// statements slower than this are logged as warnings
+const SLOW_STATEMENT: Duration = Duration::from_secs(1);
+// used by init_db, the server takes it from the config
+const DEFAULT_CONNECT_TIMEOUT: Duration = Duration::from_secs(2);
+
+// startup retries (exponential backoff)
+const RETRY_BACKOFF_START: Duration = Duration::from_millis(250);
+const RETRY_BACKOFF_MAX: Duration = Duration::from_secs(10);
+
+// transient errors retries of the idempotent reads
+const READ_RETRIES: u32 = 3;
+const READ_RETRY_BACKOFF: Duration = Duration::from_millis(50);
+
+// sql files (one folder per dialect)
+#[cfg(not(feature = "sqlite"))]
+const SQL_DIR: &str = "sql/mysql/";
+#[cfg(not(feature = "sqlite"))]
+const SQL_RECREATE: &str = "sql/mysql/00-recreate-db.sql";
+#[cfg(feature = "sqlite")]
+const SQL_DIR: &str = "sql/sqlite/";
+// dev data, applied by init_db but not by init_test_db
+const SQL_DEV_SEED_SUFFIX: &str = "-dev-seed.sql";
+// reverts the migration of the same name, for `migrate down`
+const SQL_DOWN_SUFFIX: &str = ".down.sql";
+
+// test dbs (see init_test_db), created from the server db of root
+#[cfg(all(test, not(feature = "sqlite")))]
+const TEST_DB_PREFIX: &str = "cookbook_test_";
+#[cfg(all(test, not(feature = "sqlite")))]
+const TEST_ROOT_DB: &str = "mysql";
+
+// sqlite db file, in-memory (and new on each init_db) when not set
+#[cfg(feature = "sqlite")]
+const SQLITE_FILE_ENV: &str = "SQLITE_FILE";
+
+// region: Driver
+#[cfg(not(feature = "sqlite"))]
+pub type DbDriver = sqlx::MySql;
+#[cfg(feature = "sqlite")]
+pub type DbDriver = sqlx::Sqlite;
 
-// sql files
-const SQL_DIR: &str = "sql/";
-const SQL_RECREATE: &str = "sql/00-recreate-db.sql";
+pub type Db = Pool<DbDriver>;
+pub type DbRow = <DbDriver as Database>::Row;
+pub type DbQueryResult = <DbDriver as Database>::QueryResult;
+pub type DbArguments<'q> = <DbDriver as HasArguments<'q>>::Arguments;
+
+/// The id generated by an INSERT.
+pub fn last_insert_id(result: &DbQueryResult) -> i64 {
+    #[cfg(not(feature = "sqlite"))]
+    return result.last_insert_id() as i64;
+    #[cfg(feature = "sqlite")]
+    return result.last_insert_rowid();
+}
+// endregion: Driver
+
+// region: Dialect
+/// `a <=> b`, equal or both NULL.
+#[cfg(not(feature = "sqlite"))]
+pub const NULL_SAFE_EQ: &str = "<=>";
+#[cfg(feature = "sqlite")]
+pub const NULL_SAFE_EQ: &str = "IS";
+
+/// INSERT skipping the rows already there (duplicate key).
+#[cfg(not(feature = "sqlite"))]
+pub const INSERT_IGNORE: &str = "INSERT IGNORE";
+#[cfg(feature = "sqlite")]
+pub const INSERT_IGNORE: &str = "INSERT OR IGNORE";
+
+/// Suffix of an INSERT updating `columns` when the row of the `key` columns is already there.
+/// e.g. `on_conflict_update("recipe_id, cid", &["rating"])`
+pub fn on_conflict_update(key: &str, columns: &[&str]) -> String {
+    #[cfg(not(feature = "sqlite"))]
+    {
+        let _ = key;
+        let sets: Vec<String> = columns
+            .iter()
+            .map(|c| format!("{c} = VALUES({c})"))
+            .collect();
+        format!("ON DUPLICATE KEY UPDATE {}", sets.join(", "))
+    }
+    #[cfg(feature = "sqlite")]
+    {
+        let sets: Vec<String> = columns
+            .iter()
+            .map(|c| format!("{c} = excluded.{c}"))
+            .collect();
+        format!("ON CONFLICT ({}) DO UPDATE SET {}", key, sets.join(", "))
+    }
+}
+// endregion: Dialect
 
-pub type Db = Pool<MySql>;
+/// Placeholders of an `IN` list of `len` values, e.g. `(?, ?, ?)`.
+/// Note: an empty list gives `(NULL)`, matching nothing.
+pub fn in_list(len: usize) -> String {
+    match len {
+        0 => "(NULL)".to_string(),
+        len => format!("({})", vec!["?"; len].join(", ")),
+    }
+}
 
 pub async fn init_db() -> Result<Db, sqlx::Error> {
+    init_db_with(DEFAULT_CONNECT_TIMEOUT).await
+}
+
+/// Same as `init_db`, retried with an exponential backoff until `deadline`, for the DB starting along with the server.
+pub async fn init_db_with_retry(
+    connect_timeout: Duration,
+    deadline: Duration,
+) -> Result<Db, sqlx::Error> {
+    let give_up_at = Instant::now() + deadline;
+    let mut backoff = RETRY_BACKOFF_START;
+    let mut attempt = 1;
+    loop {
+        match init_db_with(connect_timeout).await {
+            Ok(db) => return Ok(db),
+            Err(ex) if Instant::now() + backoff < give_up_at => {
+                warn!(attempt, cause = %ex, retry_in_ms = backoff.as_millis() as u64, "db not available yet");
+                sleep(backoff).await;
+                backoff = (backoff * 2).min(RETRY_BACKOFF_MAX);
+                attempt += 1;
+            }
+            Err(ex) => return Err(ex),
+        }
+    }
+}
+
+#[cfg(not(feature = "sqlite"))]
+async fn init_db_with(connect_timeout: Duration) -> Result<Db, sqlx::Error> {
     // -- Create the db with ROOT (dev only)
     {
-        let root_db = new_db_pool(HOST, ROOT_DB, ROOT_USER, ROOT_PWD, 1).await?;
+        let root_db = new_db_pool(HOST, ROOT_DB, ROOT_USER, ROOT_PWD, 1, connect_timeout).await?;
         pexec(&root_db, SQL_RECREATE).await?;
     }
 
     // -- Run the app sql files
-    let app_db = new_db_pool(HOST, APP_DB, APP_USER, APP_PWD, APP_MAX_CON).await?;
-    let mut paths: Vec<PathBuf> = fs::read_dir(SQL_DIR)?
+    let app_db = new_db_pool(
+        HOST,
+        APP_DB,
+        APP_USER,
+        APP_PWD,
+        APP_MAX_CON,
+        connect_timeout,
+    )
+    .await?;
+    // execute each file, and keep track of it for the readiness check
+    apply_sql_files(&app_db, app_sql_files()?).await?;
+    info!("db initialized");
+
+    //returning the app db
+    new_db_pool(
+        HOST,
+        APP_DB,
+        APP_USER,
+        APP_PWD,
+        APP_MAX_CON,
+        connect_timeout,
+    )
+    .await
+}
+
+#[cfg(feature = "sqlite")]
+async fn init_db_with(connect_timeout: Duration) -> Result<Db, sqlx::Error> {
+    let file = std::env::var(SQLITE_FILE_ENV).ok();
+    let db = new_sqlite_pool(file.as_deref(), connect_timeout).await?;
+
+    // no recreate, a db file keeps its data and only gets the sql files not applied yet
+    apply_sql_files(&db, unapplied_sql_files(&db).await?).await?;
+    info!(
+        file = file.as_deref().unwrap_or(":memory:"),
+        "db initialized"
+    );
+
+    Ok(db)
+}
+
+/// Connect to the app db as is, no recreate and no sql file applied (e.g. for the CLI).
+pub async fn connect_db(connect_timeout: Duration) -> Result<Db, sqlx::Error> {
+    #[cfg(not(feature = "sqlite"))]
+    return new_db_pool(
+        HOST,
+        APP_DB,
+        APP_USER,
+        APP_PWD,
+        APP_MAX_CON,
+        connect_timeout,
+    )
+    .await;
+    #[cfg(feature = "sqlite")]
+    return new_sqlite_pool(
+        std::env::var(SQLITE_FILE_ENV).ok().as_deref(),
+        connect_timeout,
+    )
+    .await;
+}
+
+/// A new, private and empty (schema only, no dev seed) db, so the tests can run in parallel.
+/// Returns the db along with its name, for `drop_test_db`.
+/// Note: mysql creates a `cookbook_test_<random>` database (as root, no app user to race with init_db),
+///       sqlite a private in-memory db.
+#[cfg(test)]
+pub async fn init_test_db() -> Result<(Db, String), sqlx::Error> {
+    #[cfg(not(feature = "sqlite"))]
+    let (db, name) = {
+        let name = format!("{}{:08x}", TEST_DB_PREFIX, rand::random::<u32>());
+        let root_db = new_db_pool(
+            HOST,
+            TEST_ROOT_DB,
+            ROOT_USER,
+            ROOT_PWD,
+            1,
+            DEFAULT_CONNECT_TIMEOUT,
+        )
+        .await?;
+        let sql_create = format!(
+            "CREATE DATABASE {} CHARACTER SET utf8mb4 COLLATE utf8mb4_unicode_ci",
+            name
+        );
+        sqlx::query(&sql_create).execute(&root_db).await?;
+        root_db.close().await;
+
+        let db = new_db_pool(
+            HOST,
+            &name,
+            ROOT_USER,
+            ROOT_PWD,
+            APP_MAX_CON,
+            DEFAULT_CONNECT_TIMEOUT,
+        )
+        .await?;
+        (db, name)
+    };
+    #[cfg(feature = "sqlite")]
+    let (db, name) = (
+        new_sqlite_pool(None, DEFAULT_CONNECT_TIMEOUT).await?,
+        ":memory:".to_string(),
+    );
+
+    let schema_files = app_sql_files()?
+        .into_iter()
+        .filter(|path| !is_dev_seed(path))
+        .collect();
+    apply_sql_files(&db, schema_files).await?;
+
+    Ok((db, name))
+}
+
+/// Drop a db made by `init_test_db` (nothing to do for an in-memory one).
+#[cfg(test)]
+pub async fn drop_test_db(name: &str) -> Result<(), sqlx::Error> {
+    #[cfg(not(feature = "sqlite"))]
+    {
+        // never anything but a test db
+        if !name.starts_with(TEST_DB_PREFIX) {
+            return Ok(());
+        }
+        let root_db = new_db_pool(
+            HOST,
+            TEST_ROOT_DB,
+            ROOT_USER,
+            ROOT_PWD,
+            1,
+            DEFAULT_CONNECT_TIMEOUT,
+        )
+        .await?;
+        sqlx::query(&format!("DROP DATABASE IF EXISTS {}", name))
+            .execute(&root_db)
+            .await?;
+        root_db.close().await;
+    }
+    #[cfg(feature = "sqlite")]
+    let _ = name;
+
+    Ok(())
+}
+
+/// The app sql files not applied to the db yet (empty when up to date).
+/// Note: the dev seeds are data, not schema, a db without them is still up to date.
+pub async fn pending_migrations(db: &Db) -> Result<Vec<String>, sqlx::Error> {
+    Ok(unapplied_sql_files(db)
+        .await?
         .into_iter()
+        .filter(|path| !is_dev_seed(path))
+        .collect())
+}
+
+/// Apply the pending migrations, in order, and return them.
+pub async fn migrate_up(db: &Db) -> Result<Vec<String>, sqlx::Error> {
+    let pending = pending_migrations(db).await?;
+    apply_sql_files(db, pending.clone()).await?;
+
+    Ok(pending)
+}
+
+/// Revert the last applied migration with its `.down.sql` file, and return it (None when there is none to revert).
+/// Note: fails when that migration has no down file, the earlier ones are left as is.
+pub async fn migrate_down(db: &Db) -> Result<Option<String>, sqlx::Error> {
+    let last = migration_status(db)
+        .await?
+        .into_iter()
+        .filter(|(_, applied)| *applied)
+        .map(|(file, _)| file)
+        .next_back();
+    let Some(file) = last else {
+        return Ok(None);
+    };
+
+    let down_file = format!("{}{}", file.trim_end_matches(".sql"), SQL_DOWN_SUFFIX);
+    if !Path::new(&down_file).exists() {
+        let message = format!("no {} to revert {}", down_file, file);
+        return Err(std::io::Error::new(std::io::ErrorKind::NotFound, message).into());
+    }
+    pexec(db, &down_file).await?;
+    sqlx::query("DELETE FROM schema_migrations WHERE file = ?")
+        .bind(&file)
+        .execute(db)
+        .await?;
+
+    Ok(Some(file))
+}
+
+/// Each migration (dev seeds left out), in order, with whether it is applied.
+pub async fn migration_status(db: &Db) -> Result<Vec<(String, bool)>, sqlx::Error> {
+    let pending = pending_migrations(db).await?;
+
+    Ok(app_sql_files()?
+        .into_iter()
+        .filter(|path| !is_dev_seed(path))
+        .map(|path| {
+            let applied = !pending.contains(&path);
+            (path, applied)
+        })
+        .collect())
+}
+
+/// The app sql files (dev seeds included) not applied to the db yet.
+async fn unapplied_sql_files(db: &Db) -> Result<Vec<String>, sqlx::Error> {
+    let applied: Vec<String> =
+        match sqlx::query_as::<_, (String,)>("SELECT file FROM schema_migrations")
+            .fetch_all(db)
+            .await
+        {
+            Ok(rows) => rows.into_iter().map(|(file,)| file).collect(),
+            // new db, no schema_migrations table yet
+            Err(sqlx::Error::Database(_)) => Vec::new(),
+            Err(ex) => return Err(ex),
+        };
+
+    Ok(app_sql_files()?
+        .into_iter()
+        .filter(|file| !applied.contains(file))
+        .collect())
+}
+
+/// Round trip to the db, for the readiness check.
+pub async fn ping(db: &Db) -> Result<(), sqlx::Error> {
+    sqlx::query("SELECT 1").execute(db).await?;
+    Ok(())
+}
+
+/// Run an idempotent read, retrying it a few times on the transient errors (connection lost, pool exhausted, deadlock).
+/// e.g. `retry_read(|| sqlx::query_as(sql).fetch_all(db)).await?`
+pub async fn retry_read<T, F, Fut>(mut read: F) -> Result<T, sqlx::Error>
+where
+    F: FnMut() -> Fut,
+    Fut: Future<Output = Result<T, sqlx::Error>>,
+{
+    let mut retries = 0;
+    loop {
+        match read().await {
+            Err(ex) if retries < READ_RETRIES && is_transient(&ex) => {
+                retries += 1;
+                warn!(retries, cause = %ex, "transient sql error, retrying the read");
+                sleep(READ_RETRY_BACKOFF * retries).await;
+            }
+            result => return result,
+        }
+    }
+}
+
+fn is_transient(ex: &sqlx::Error) -> bool {
+    match ex {
+        sqlx::Error::Io(_) | sqlx::Error::PoolTimedOut => true,
+        // deadlock, lock wait timeout, server gone away, lost connection
+        #[cfg(not(feature = "sqlite"))]
+        sqlx::Error::Database(db_ex) => matches!(
+            db_ex
+                .try_downcast_ref::<MySqlDatabaseError>()
+                .map(|ex| ex.number()),
+            Some(1205) | Some(1213) | Some(2006) | Some(2013)
+        ),
+        // SQLITE_BUSY, SQLITE_LOCKED
+        #[cfg(feature = "sqlite")]
+        sqlx::Error::Database(db_ex) => matches!(db_ex.code().as_deref(), Some("5") | Some("6")),
+        _ => false,
+    }
+}
+
+/// The .sql files of SQL_DIR but the recreate and down ones, in order.
+fn app_sql_files() -> Result<Vec<String>, sqlx::Error> {
+    let mut paths: Vec<PathBuf> = fs::read_dir(SQL_DIR)?
         .filter_map(|e| e.ok().map(|e| e.path()))
         .collect();
     paths.sort();
-    // execute each file
+
+    Ok(paths
+        .into_iter()
+        .filter_map(|path| path.to_str().map(str::to_string))
+        // only .sql and not recreate or down
+        .filter(|path| {
+            path.ends_with(".sql") && !is_recreate(path) && !path.ends_with(SQL_DOWN_SUFFIX)
+        })
+        .collect())
+}
+
+/// Execute each file, and keep track of it in schema_migrations.
+async fn apply_sql_files(db: &Db, paths: Vec<String>) -> Result<(), sqlx::Error> {
     for path in paths {
-        if let Some(path) = path.to_str() {
-            // only .sql and not recreate
-            if path.ends_with(".sql") && path != SQL_RECREATE {
-                pexec(&app_db, &path).await?;
-            }
-        }
+        pexec(db, &path).await?;
+        sqlx::query("INSERT INTO schema_migrations (file) VALUES (?)")
+            .bind(&path)
+            .execute(db)
+            .await?;
     }
 
-    //returning the app db
-    new_db_pool(HOST, APP_DB, APP_USER, APP_PWD, APP_MAX_CON).await
+    Ok(())
+}
+
+fn is_dev_seed(path: &str) -> bool {
+    path.ends_with(SQL_DEV_SEED_SUFFIX)
 }
 
+fn is_recreate(path: &str) -> bool {
+    #[cfg(not(feature = "sqlite"))]
+    return path == SQL_RECREATE;
+    #[cfg(feature = "sqlite")]
+    return path.ends_with("00-recreate-db.sql");
+}
+
+#[cfg(not(feature = "sqlite"))]
 async fn pexec(db: &Db, file: &str) -> Result<(), sqlx::Error> {
     // Read the file
     let content = fs::read_to_string(file).map_err(|ex| {
-        println!("ERROR reading {} (cause: {:?})", file, ex);
+        error!(file, cause = ?ex, "pexec - cannot read sql file");
         ex
     })?;
 
@@ -60,31 +486,81 @@ async fn pexec(db: &Db, file: &str) -> Result<(), sqlx::Error> {
         .collect();
 
     for sql in sqls {
-        match sqlx::query(&sql).execute(db).await {
+        match sqlx::query(sql).execute(db).await {
             Ok(_) => (),
-            Err(ex) => println!("WARNING - pexec - Sql file '{}' FAILED cause: {}", file, ex),
+            Err(ex) => warn!(file, cause = %ex, "pexec - sql file statement failed"),
         }
     }
 
     Ok(())
 }
 
+/// Note: sqlite runs the whole file at once, the triggers have `;` in their body.
+#[cfg(feature = "sqlite")]
+async fn pexec(db: &Db, file: &str) -> Result<(), sqlx::Error> {
+    use sqlx::Executor;
+
+    let content = fs::read_to_string(file).map_err(|ex| {
+        error!(file, cause = ?ex, "pexec - cannot read sql file");
+        ex
+    })?;
+
+    if let Err(ex) = db.execute(content.as_str()).await {
+        warn!(file, cause = %ex, "pexec - sql file failed");
+    }
+
+    Ok(())
+}
+
+#[cfg(not(feature = "sqlite"))]
 async fn new_db_pool(
     host: &str,
     db: &str,
     user: &str,
     pwd: &str,
     max_con: u32,
+    connect_timeout: Duration,
 ) -> Result<Db, sqlx::Error> {
     let con_string = format!("mysql://{}:{}@{}/{}", user, pwd, host, db);
+    // every statement is logged with its latency, at debug so it only shows when asked for
+    let mut options = MySqlConnectOptions::from_str(&con_string)?;
+    options
+        .log_statements(log::LevelFilter::Debug)
+        .log_slow_statements(log::LevelFilter::Warn, SLOW_STATEMENT);
 
     MySqlPoolOptions::new()
         .max_connections(max_con)
-        .connect_timeout(Duration::from_millis(500))
-        .connect(&con_string)
+        .connect_timeout(connect_timeout)
+        .connect_with(options)
         .await
 }
 
+/// A db file when `file` is set, otherwise a private in-memory db.
+/// Note: an in-memory db lives and dies with its connection, hence the single, never recycled, connection.
+#[cfg(feature = "sqlite")]
+async fn new_sqlite_pool(file: Option<&str>, connect_timeout: Duration) -> Result<Db, sqlx::Error> {
+    let mut options = match file {
+        Some(file) => SqliteConnectOptions::new()
+            .filename(file)
+            .create_if_missing(true),
+        None => SqliteConnectOptions::from_str("sqlite::memory:")?,
+    };
+    options
+        .log_statements(log::LevelFilter::Debug)
+        .log_slow_statements(log::LevelFilter::Warn, SLOW_STATEMENT);
+
+    let pool_options = SqlitePoolOptions::new().connect_timeout(connect_timeout);
+    let pool_options = match file {
+        Some(_) => pool_options.max_connections(APP_MAX_CON),
+        None => pool_options
+            .max_connections(1)
+            .min_connections(1)
+            .idle_timeout(None)
+            .max_lifetime(None),
+    };
+    pool_options.connect_with(options).await
+}
+
 #[cfg(test)]
 #[path = "../_tests/model_db.rs"]
 mod tests;
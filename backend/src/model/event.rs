@@ -0,0 +1,55 @@
+use std::sync::OnceLock;
+
+use serde::{Deserialize, Serialize};
+use tokio::sync::broadcast;
+
+/// Events buffered per subscriber before a slow one starts missing some (it is then told with `Lagged`).
+const EVENT_BUS_CAPACITY: usize = 256;
+
+static EVENT_BUS: OnceLock<broadcast::Sender<ChangeEvent>> = OnceLock::new();
+
+// region: Event Types
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ChangeAction {
+    Created,
+    Updated,
+    Deleted,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ChangeEvent {
+    /// "recipe" or "ingredient"
+    pub typ: String,
+    pub id: i64,
+    pub action: ChangeAction,
+    /// user who made the change
+    pub cid: i64,
+}
+// endregion: Event Types
+
+// region: EventBus
+/// In-process broadcast of the changes made through the Macs.
+pub struct EventBus;
+
+impl EventBus {
+    /// Fire and forget, nothing happens when no one listens.
+    pub fn publish(typ: &str, id: i64, action: ChangeAction, cid: i64) {
+        let event = ChangeEvent {
+            typ: typ.to_string(),
+            id,
+            action,
+            cid,
+        };
+        let _ = sender().send(event);
+    }
+
+    pub fn subscribe() -> broadcast::Receiver<ChangeEvent> {
+        sender().subscribe()
+    }
+}
+
+fn sender() -> &'static broadcast::Sender<ChangeEvent> {
+    EVENT_BUS.get_or_init(|| broadcast::channel(EVENT_BUS_CAPACITY).0)
+}
+// endregion: EventBus
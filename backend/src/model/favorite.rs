@@ -0,0 +1,103 @@
+use crate::{
+    metrics,
+    model::{
+        self,
+        db::{self, Db},
+    },
+    security::UserCtx,
+};
+
+use super::recipe::{Recipe, RecipeMac};
+use super::recipe_ingredient::RecipeIngredientMac;
+
+// region: FavoriteMac
+pub struct FavoriteMac;
+
+impl FavoriteMac {
+    /// Favorite the recipe for the current user (no-op when already a favorite).
+    pub async fn add(
+        db: &Db,
+        utx: &UserCtx,
+        recipe_id: i64,
+    ) -> Result<(Recipe, Vec<RecipeIngredientMac>), model::Error> {
+        let _timer = metrics::mac_timer("FavoriteMac::add");
+        RecipeMac::get(db, utx, recipe_id).await?;
+
+        let sql_insert = format!(
+            "{} INTO recipe_favorites (recipe_id, cid) VALUES (?, ?)",
+            db::INSERT_IGNORE
+        );
+        sqlx::query(&sql_insert)
+            .bind(recipe_id)
+            .bind(utx.user_id)
+            .execute(db)
+            .await?;
+
+        RecipeMac::get(db, utx, recipe_id).await
+    }
+
+    pub async fn remove(
+        db: &Db,
+        utx: &UserCtx,
+        recipe_id: i64,
+    ) -> Result<(Recipe, Vec<RecipeIngredientMac>), model::Error> {
+        let _timer = metrics::mac_timer("FavoriteMac::remove");
+        let sql_delete = "DELETE FROM recipe_favorites WHERE recipe_id = ? AND cid = ?";
+        let result = sqlx::query(sql_delete)
+            .bind(recipe_id)
+            .bind(utx.user_id)
+            .execute(db)
+            .await?;
+        if result.rows_affected() == 0 {
+            return Err(model::Error::EntityNotFound(
+                "recipe_favorites",
+                format!("{}@{}", recipe_id, utx.user_id),
+            ));
+        }
+
+        RecipeMac::get(db, utx, recipe_id).await
+    }
+
+    /// Favorite recipes of the current user, most recently favorited first.
+    pub async fn list(
+        db: &Db,
+        utx: &UserCtx,
+    ) -> Result<Vec<(Recipe, Vec<RecipeIngredientMac>)>, model::Error> {
+        let _timer = metrics::mac_timer("FavoriteMac::list");
+        let sql = "SELECT r.* FROM recipes r
+            JOIN recipe_favorites f ON f.recipe_id = r.id
+            WHERE f.cid = ? AND r.deleted_at IS NULL
+            ORDER BY f.ctime DESC, r.id DESC";
+
+        let recipes = sqlx::query_as::<_, Recipe>(sql)
+            .bind(utx.user_id)
+            .fetch_all(db)
+            .await?;
+
+        let mut result = Vec::new();
+        for mut recipe in recipes {
+            recipe.is_favorite = true;
+            let ingredients = RecipeIngredientMac::list_by_recipe(db, recipe.id).await?;
+            result.push((recipe, ingredients));
+        }
+
+        Ok(result)
+    }
+
+    /// Ids of the favorite recipes of the current user, to flag them in recipe responses.
+    pub async fn recipe_ids(db: &Db, utx: &UserCtx) -> Result<Vec<i64>, model::Error> {
+        let _timer = metrics::mac_timer("FavoriteMac::recipe_ids");
+        let sql = "SELECT recipe_id FROM recipe_favorites WHERE cid = ?";
+        let ids = sqlx::query_as::<_, (i64,)>(sql)
+            .bind(utx.user_id)
+            .fetch_all(db)
+            .await?;
+
+        Ok(ids.into_iter().map(|(id,)| id).collect())
+    }
+}
+// endregion: FavoriteMac
+
+#[cfg(test)]
+#[path = "../_tests/model_favorite.rs"]
+mod tests;
@@ -0,0 +1,193 @@
+use std::ops::Deref;
+use std::sync::Arc;
+
+use super::db::{self, Db};
+use super::ingredient::{Ingredient, IngredientMac, IngredientPatch};
+use super::patch::PatchValue;
+use super::recipe::{Recipe, RecipeMac, RecipePatch, RecipePatchInner};
+use super::recipe_ingredient::{RecipeIngredientMac, RecipeIngredientPatch};
+use crate::{model, security::UserCtx};
+
+// region: TestDb
+/// A db of its own for a test (schema only, no dev seed), dropped along with the fixture.
+/// e.g. `let db = TestDb::new().await?; IngredientMac::list(&db, &utx).await?;`
+pub struct TestDb {
+    db: Arc<Db>,
+    name: String,
+}
+
+impl TestDb {
+    pub async fn new() -> Result<Self, sqlx::Error> {
+        let (db, name) = db::init_test_db().await?;
+        Ok(TestDb {
+            db: Arc::new(db),
+            name,
+        })
+    }
+
+    /// For the web filters.
+    pub fn arc(&self) -> Arc<Db> {
+        self.db.clone()
+    }
+}
+
+impl Deref for TestDb {
+    type Target = Db;
+
+    fn deref(&self) -> &Db {
+        &self.db
+    }
+}
+
+impl Drop for TestDb {
+    fn drop(&mut self) {
+        // Note: no async drop, so on a thread of its own, the test runtime might be a current thread one
+        let name = std::mem::take(&mut self.name);
+        let _ = std::thread::spawn(move || {
+            let rt = tokio::runtime::Builder::new_current_thread()
+                .enable_all()
+                .build();
+            if let Ok(rt) = rt {
+                let _ = rt.block_on(db::drop_test_db(&name));
+            }
+        })
+        .join();
+    }
+}
+// endregion: TestDb
+
+// region: Builders
+/// Ingredient with test defaults, only the properties a test cares about need to be set.
+/// e.g. `IngredientBuilder::new().name("basil").create(&db, &utx).await?`
+pub struct IngredientBuilder {
+    name: String,
+    quantity: String,
+}
+
+impl Default for IngredientBuilder {
+    fn default() -> Self {
+        IngredientBuilder {
+            name: "test ingredient".to_string(),
+            quantity: "1 piece".to_string(),
+        }
+    }
+}
+
+impl IngredientBuilder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn name(mut self, name: &str) -> Self {
+        self.name = name.to_string();
+        self
+    }
+
+    pub fn quantity(mut self, quantity: &str) -> Self {
+        self.quantity = quantity.to_string();
+        self
+    }
+
+    pub fn patch(&self) -> IngredientPatch {
+        IngredientPatch {
+            name: PatchValue::Value(self.name.clone()),
+            quantity: PatchValue::Value(self.quantity.clone()),
+        }
+    }
+
+    pub async fn create(self, db: &Db, utx: &UserCtx) -> Result<Ingredient, model::Error> {
+        IngredientMac::create(db, utx, self.patch()).await
+    }
+}
+
+/// Recipe with test defaults, linked to the ingredients added with `ingredient`.
+/// e.g. `RecipeBuilder::new().title("soup").ingredient(&tomatoes, "200 g").create(&db, &utx).await?`
+pub struct RecipeBuilder {
+    title: String,
+    cid: Option<i64>,
+    ingredients: Vec<RecipeIngredientPatch>,
+}
+
+impl Default for RecipeBuilder {
+    fn default() -> Self {
+        RecipeBuilder {
+            title: "test recipe".to_string(),
+            cid: None,
+            ingredients: Vec::new(),
+        }
+    }
+}
+
+impl RecipeBuilder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn title(mut self, title: &str) -> Self {
+        self.title = title.to_string();
+        self
+    }
+
+    /// Owner, the calling user when not set.
+    pub fn cid(mut self, cid: i64) -> Self {
+        self.cid = Some(cid);
+        self
+    }
+
+    pub fn ingredient(mut self, ingredient: &Ingredient, quantity: &str) -> Self {
+        self.ingredients.push(RecipeIngredientPatch {
+            ingredient_id: ingredient.id,
+            ingredient_name: ingredient.name.clone(),
+            quantity: quantity.to_string(),
+        });
+        self
+    }
+
+    /// Patch of the recipe, `cid` defaults to `user_id`.
+    pub fn patch(&self, user_id: i64) -> RecipePatch {
+        RecipePatch {
+            recipe_patch: RecipePatchInner {
+                title: PatchValue::Value(self.title.clone()),
+                cid: PatchValue::Value(self.cid.unwrap_or(user_id)),
+            },
+            ingredients: match self.ingredients.is_empty() {
+                true => PatchValue::Absent,
+                false => PatchValue::Value(self.ingredients.clone()),
+            },
+        }
+    }
+
+    pub async fn create(
+        self,
+        db: &Db,
+        utx: &UserCtx,
+    ) -> Result<(Recipe, Vec<RecipeIngredientMac>), model::Error> {
+        RecipeMac::create(db, utx, self.patch(utx.user_id)).await
+    }
+}
+
+/// Same data as the dev seed (tomatoes, and spaghetti of user 123 using them), made through the `*Mac`
+/// so the ids are the ones of this db.
+pub struct Seed {
+    pub ingredient: Ingredient,
+    pub recipe: Recipe,
+}
+
+impl Seed {
+    pub async fn create(db: &Db, utx: &UserCtx) -> Result<Self, model::Error> {
+        let ingredient = IngredientBuilder::new()
+            .name("tomatoes")
+            .quantity("3 pieces")
+            .create(db, utx)
+            .await?;
+        let (recipe, _) = RecipeBuilder::new()
+            .title("spaghetti")
+            .cid(123)
+            .ingredient(&ingredient, "200 g")
+            .create(db, utx)
+            .await?;
+
+        Ok(Seed { ingredient, recipe })
+    }
+}
+// endregion: Builders
@@ -1,10 +1,15 @@
+use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
-use warp::filters::ws::ws;
 
+use super::audit::{snapshot, AuditMac};
+use super::change_feed::ChangeFeedMac;
 use super::db::{self, Db};
-use crate::{model, security::UserCtx};
-use sqlb::{HasFields, Raw, SqlBuilder};
-use sqlx::mysql;
+use super::event::ChangeAction;
+use super::patch::PatchValue;
+use super::recipe_ingredient::RecipeIngredientMac;
+use super::sql_builder::UpdateBuilder;
+use super::validate::{TextRule, Validate, Validator};
+use crate::{metrics, model, security::UserCtx};
 
 // region: Ingredient Types
 #[derive(sqlx::FromRow, Debug, Clone, Serialize, Deserialize)]
@@ -12,12 +17,23 @@ pub struct Ingredient {
     pub id: i64,
     pub name: String,
     pub quantity: String,
+    /// bumped on every update, used as the ETag
+    pub version: i64,
 }
 
-#[derive(Default, Clone, Serialize, Deserialize)]
+#[derive(Default, Debug, Clone, Serialize, Deserialize)]
 pub struct IngredientPatch {
-    pub name: Option<String>,
-    pub quantity: Option<String>,
+    #[serde(default, skip_serializing_if = "PatchValue::is_absent")]
+    pub name: PatchValue<String>,
+    #[serde(default, skip_serializing_if = "PatchValue::is_absent")]
+    pub quantity: PatchValue<String>,
+}
+
+impl Validate for IngredientPatch {
+    fn check(&mut self, v: &mut Validator) {
+        v.text("name", &mut self.name, TextRule::required(255))
+            .text("quantity", &mut self.quantity, TextRule::required(50));
+    }
 }
 // endregion: Ingredient Types
 
@@ -28,96 +44,369 @@ impl IngredientMac {
     pub async fn create(
         db: &Db,
         utx: &UserCtx,
-        data: IngredientPatch,
+        mut data: IngredientPatch,
     ) -> Result<Ingredient, model::Error> {
+        let _timer = metrics::mac_timer("IngredientMac::create");
+        data.validate()?;
+
         let sql_insert = "INSERT INTO ingredients (name, quantity) VALUES (?, ?)";
 
+        let name = data
+            .name
+            .not_null_or("ingredients", "name", "untitled".to_string())?;
+        let quantity =
+            data.quantity
+                .not_null_or("ingredients", "quantity", "unknown".to_string())?;
+
         let result = sqlx::query(sql_insert)
-            .bind(data.name.unwrap_or_else(|| "untitled".to_string()))
-            .bind(data.quantity.unwrap_or_else(|| "unknown".to_string()))
+            .bind(name)
+            .bind(quantity)
             .execute(db)
             .await?;
 
-        let last_insert_id = result.last_insert_id();
+        let last_insert_id = db::last_insert_id(&result);
 
-        let sql_select = "SELECT id, name, quantity FROM ingredients WHERE id = ?";
+        let sql_select = "SELECT id, name, quantity, version FROM ingredients WHERE id = ?";
         let ingredient = sqlx::query_as::<_, Ingredient>(sql_select)
             .bind(last_insert_id)
             .fetch_one(db)
             .await?;
 
+        ChangeFeedMac::record(db, utx, "ingredient", ingredient.id, ChangeAction::Created).await?;
+        AuditMac::record(
+            db,
+            utx,
+            "ingredient",
+            ingredient.id,
+            "created",
+            None,
+            snapshot(&ingredient),
+        )
+        .await?;
+
         Ok(ingredient)
     }
 
     pub async fn get(db: &Db, _utx: &UserCtx, id: i64) -> Result<Ingredient, model::Error> {
-        let sql = "SELECT * from ingredients WHERE id = (?)";
+        let _timer = metrics::mac_timer("IngredientMac::get");
+        let sql = "SELECT * from ingredients WHERE id = (?) AND deleted_at IS NULL";
 
-        let result = sqlx::query_as::<_, Ingredient>(sql)
-            .bind(id)
-            .fetch_one(db)
-            .await;
+        let result =
+            db::retry_read(|| sqlx::query_as::<_, Ingredient>(sql).bind(id).fetch_one(db)).await;
 
         handle_fetch_one_result(result, "ingredient", id)
     }
 
     pub async fn update(
         db: &Db,
-        _utx: &UserCtx,
+        utx: &UserCtx,
         id: i64,
         data: IngredientPatch,
     ) -> Result<Ingredient, model::Error> {
-        let sql = "UPDATE ingredients SET name = ?, quantity = ? WHERE id = ?";
+        let _timer = metrics::mac_timer("IngredientMac::update");
+        Self::update_versioned(db, utx, id, None, data).await
+    }
 
-        let name = data.name.unwrap_or_else(|| "untitled".to_string());
-        let quantity = data.quantity.unwrap_or_else(|| "unknown".to_string());
+    /// Update only if the ingredient is still at `version`, otherwise fail with `VersionMismatch`.
+    pub async fn update_if_match(
+        db: &Db,
+        utx: &UserCtx,
+        id: i64,
+        version: i64,
+        data: IngredientPatch,
+    ) -> Result<Ingredient, model::Error> {
+        let _timer = metrics::mac_timer("IngredientMac::update_if_match");
+        Self::update_versioned(db, utx, id, Some(version), data).await
+    }
+
+    async fn update_versioned(
+        db: &Db,
+        utx: &UserCtx,
+        id: i64,
+        version: Option<i64>,
+        mut data: IngredientPatch,
+    ) -> Result<Ingredient, model::Error> {
+        data.validate()?;
+        let before = Self::get(db, utx, id).await?;
+
+        // Only the properties present in the patch are set
+        let mut sb = UpdateBuilder::new("ingredients")
+            .patch("name", data.name)?
+            .patch("quantity", data.quantity)?
+            .set_raw("version", "version + 1")
+            .and_where_eq("id", id)
+            .and_where_raw("deleted_at IS NULL");
+        if let Some(version) = version {
+            sb = sb.and_where_eq("version", version);
+        }
 
         // Perform the update query
-        sqlx::query(sql)
-            .bind(name)
-            .bind(quantity)
-            .bind(id)
-            .execute(db)
-            .await?;
+        let result = sb.execute(db).await?;
+        if result.rows_affected() == 0 {
+            return Err(Self::no_match_error(db, utx, id).await);
+        }
 
         // Return the updated ingredient by fetching it again
-        let result = sqlx::query_as::<_, Ingredient>("SELECT * FROM ingredients WHERE id = ?")
-            .bind(id)
-            .fetch_one(db)
-            .await;
+        let result = sqlx::query_as::<_, Ingredient>(
+            "SELECT * FROM ingredients WHERE id = ? AND deleted_at IS NULL",
+        )
+        .bind(id)
+        .fetch_one(db)
+        .await;
+        let ingredient = handle_fetch_one_result(result, "ingredients", id)?;
+
+        ChangeFeedMac::record(db, utx, "ingredient", id, ChangeAction::Updated).await?;
+        AuditMac::record(
+            db,
+            utx,
+            "ingredient",
+            id,
+            "updated",
+            snapshot(&before),
+            snapshot(&ingredient),
+        )
+        .await?;
 
-        handle_fetch_one_result(result, "ingredients", id)
+        Ok(ingredient)
     }
 
     pub async fn list(db: &Db, _utx: &UserCtx) -> Result<Vec<Ingredient>, model::Error> {
-        let sql = "SELECT * FROM ingredients ORDER BY id DESC";
+        let _timer = metrics::mac_timer("IngredientMac::list");
+        let sql = "SELECT * FROM ingredients WHERE deleted_at IS NULL ORDER BY id DESC";
 
-        // build the sqlx-query
-        let query = sqlx::query_as(&sql);
-        //execute the query
-        let ingredients = query.fetch_all(db).await?;
+        // build and execute the sqlx-query (retried on transient errors)
+        let ingredients = db::retry_read(|| sqlx::query_as(sql).fetch_all(db)).await?;
 
         Ok(ingredients)
     }
 
-    pub async fn delete(db: &Db, _utx: &UserCtx, id: i64) -> Result<Ingredient, model::Error> {
+    /// The live ingredients among `ids`, missing ones are left out.
+    pub async fn get_many(
+        db: &Db,
+        _utx: &UserCtx,
+        ids: &[i64],
+    ) -> Result<Vec<Ingredient>, model::Error> {
+        let _timer = metrics::mac_timer("IngredientMac::get_many");
+        let sql = format!(
+            "SELECT * FROM ingredients WHERE id IN {} AND deleted_at IS NULL",
+            db::in_list(ids.len())
+        );
+
+        let ingredients = db::retry_read(|| {
+            let mut query = sqlx::query_as::<_, Ingredient>(&sql);
+            for id in ids {
+                query = query.bind(id);
+            }
+            query.fetch_all(db)
+        })
+        .await?;
+
+        Ok(ingredients)
+    }
+
+    /// Live (not trashed) ingredients, for the metrics.
+    pub async fn count(db: &Db) -> Result<i64, model::Error> {
+        let _timer = metrics::mac_timer("IngredientMac::count");
+        let sql = "SELECT COUNT(*) FROM ingredients WHERE deleted_at IS NULL";
+        let (count,) = db::retry_read(|| sqlx::query_as::<_, (i64,)>(sql).fetch_one(db)).await?;
+
+        Ok(count)
+    }
+
+    /// Move the ingredient to the trash, hiding its recipe links along with it.
+    pub async fn delete(db: &Db, utx: &UserCtx, id: i64) -> Result<Ingredient, model::Error> {
+        let _timer = metrics::mac_timer("IngredientMac::delete");
+        Self::delete_versioned(db, utx, id, None).await
+    }
+
+    /// Delete only if the ingredient is still at `version`, otherwise fail with `VersionMismatch`.
+    pub async fn delete_if_match(
+        db: &Db,
+        utx: &UserCtx,
+        id: i64,
+        version: i64,
+    ) -> Result<Ingredient, model::Error> {
+        let _timer = metrics::mac_timer("IngredientMac::delete_if_match");
+        Self::delete_versioned(db, utx, id, Some(version)).await
+    }
+
+    async fn delete_versioned(
+        db: &Db,
+        utx: &UserCtx,
+        id: i64,
+        version: Option<i64>,
+    ) -> Result<Ingredient, model::Error> {
         // Fetch the ingredient before deleting it
-        let sql_select = "SELECT * FROM ingredients WHERE id = ?";
-        let ingredient = sqlx::query_as::<_, Ingredient>(sql_select)
+        let ingredient = Self::get(db, utx, id).await?;
+
+        // Now trash the ingredient and its links, with the same timestamp so they can be restored together
+        let deleted_at = Utc::now();
+        let sql_delete = "UPDATE ingredients SET deleted_at = ? WHERE id = ? AND deleted_at IS NULL AND (? IS NULL OR version = ?)";
+        let result = sqlx::query(sql_delete)
+            .bind(deleted_at)
             .bind(id)
-            .fetch_one(db)
+            .bind(version)
+            .bind(version)
+            .execute(db)
+            .await?;
+        if result.rows_affected() == 0 {
+            return Err(Self::no_match_error(db, utx, id).await);
+        }
+        let recipe_ids = linked_recipe_ids(db, id, None).await?;
+        let links_before = recipe_links(db, &recipe_ids).await?;
+        let sql_delete_links = "UPDATE recipe_ingredients SET deleted_at = ? WHERE ingredient_id = ? AND deleted_at IS NULL";
+        sqlx::query(sql_delete_links)
+            .bind(deleted_at)
+            .bind(id)
+            .execute(db)
             .await?;
 
-        // Now delete the ingredient
-        let sql_delete = "DELETE FROM ingredients WHERE id = ?";
-        sqlx::query(sql_delete).bind(id).execute(db).await?;
+        ChangeFeedMac::record(db, utx, "ingredient", id, ChangeAction::Deleted).await?;
+        AuditMac::record(
+            db,
+            utx,
+            "ingredient",
+            id,
+            "deleted",
+            snapshot(&ingredient),
+            None,
+        )
+        .await?;
+        for (recipe_id, before) in recipe_ids.into_iter().zip(links_before) {
+            ChangeFeedMac::record(
+                db,
+                utx,
+                "recipe_ingredients",
+                recipe_id,
+                ChangeAction::Updated,
+            )
+            .await?;
+            audit_links_updated(db, utx, recipe_id, before).await?;
+        }
 
         // Return the fetched ingredient as the deleted one
         Ok(ingredient)
     }
+
+    /// Bring an ingredient back from the trash, along with the recipe links trashed with it.
+    pub async fn restore(db: &Db, utx: &UserCtx, id: i64) -> Result<Ingredient, model::Error> {
+        let _timer = metrics::mac_timer("IngredientMac::restore");
+        let sql_select =
+            "SELECT deleted_at FROM ingredients WHERE id = ? AND deleted_at IS NOT NULL";
+        let (deleted_at,) = sqlx::query_as::<_, (DateTime<Utc>,)>(sql_select)
+            .bind(id)
+            .fetch_one(db)
+            .await
+            .map_err(|sqlx_error| match sqlx_error {
+                sqlx::Error::RowNotFound => {
+                    model::Error::EntityNotFound("ingredients", id.to_string())
+                }
+                other => model::Error::SqlxError(other),
+            })?;
+
+        let recipe_ids = linked_recipe_ids(db, id, Some(deleted_at)).await?;
+        let links_before = recipe_links(db, &recipe_ids).await?;
+        let sql_restore_links = "UPDATE recipe_ingredients SET deleted_at = NULL WHERE ingredient_id = ? AND deleted_at = ?";
+        sqlx::query(sql_restore_links)
+            .bind(id)
+            .bind(deleted_at)
+            .execute(db)
+            .await?;
+        let sql_restore = "UPDATE ingredients SET deleted_at = NULL WHERE id = ?";
+        sqlx::query(sql_restore).bind(id).execute(db).await?;
+
+        // Back in the lists, so "created" for the listeners
+        ChangeFeedMac::record(db, utx, "ingredient", id, ChangeAction::Created).await?;
+        for (recipe_id, before) in recipe_ids.into_iter().zip(links_before) {
+            ChangeFeedMac::record(
+                db,
+                utx,
+                "recipe_ingredients",
+                recipe_id,
+                ChangeAction::Updated,
+            )
+            .await?;
+            audit_links_updated(db, utx, recipe_id, before).await?;
+        }
+
+        let ingredient = Self::get(db, utx, id).await?;
+        AuditMac::record(
+            db,
+            utx,
+            "ingredient",
+            id,
+            "restored",
+            None,
+            snapshot(&ingredient),
+        )
+        .await?;
+
+        Ok(ingredient)
+    }
+
+    /// Tell apart a conditional write that found no row (gone) from one that lost the race (stale version).
+    async fn no_match_error(db: &Db, utx: &UserCtx, id: i64) -> model::Error {
+        match Self::get(db, utx, id).await {
+            Ok(_) => model::Error::VersionMismatch("ingredients", id.to_string()),
+            Err(ex) => ex,
+        }
+    }
 }
 // endregion: IngredientMac
 
 // region: Utils
+/// Recipes linked to the ingredient, by live links (`None`) or by links trashed at `deleted_at`.
+async fn linked_recipe_ids(
+    db: &Db,
+    ingredient_id: i64,
+    deleted_at: Option<DateTime<Utc>>,
+) -> Result<Vec<i64>, model::Error> {
+    let sql = format!(
+        "SELECT DISTINCT recipe_id FROM recipe_ingredients WHERE ingredient_id = ? AND deleted_at {} ?",
+        db::NULL_SAFE_EQ
+    );
+    let ids = sqlx::query_as::<_, (i64,)>(&sql)
+        .bind(ingredient_id)
+        .bind(deleted_at)
+        .fetch_all(db)
+        .await?;
+
+    Ok(ids.into_iter().map(|(id,)| id).collect())
+}
+
+/// Live links of each recipe, to keep the state before a change in the audit log.
+async fn recipe_links(
+    db: &Db,
+    recipe_ids: &[i64],
+) -> Result<Vec<Vec<RecipeIngredientMac>>, model::Error> {
+    let mut links = Vec::new();
+    for recipe_id in recipe_ids {
+        links.push(RecipeIngredientMac::list_by_recipe(db, *recipe_id).await?);
+    }
+
+    Ok(links)
+}
+
+/// Audit the links of a recipe trashed or restored along with an ingredient.
+async fn audit_links_updated(
+    db: &Db,
+    utx: &UserCtx,
+    recipe_id: i64,
+    before: Vec<RecipeIngredientMac>,
+) -> Result<(), model::Error> {
+    let after = RecipeIngredientMac::list_by_recipe(db, recipe_id).await?;
+    AuditMac::record(
+        db,
+        utx,
+        "recipe_ingredients",
+        recipe_id,
+        "updated",
+        snapshot(&before),
+        snapshot(&after),
+    )
+    .await
+}
+
 fn handle_fetch_one_result(
     result: Result<Ingredient, sqlx::Error>,
     typ: &'static str,
@@ -0,0 +1,437 @@
+use std::cmp::Ordering;
+use std::collections::BTreeMap;
+use std::sync::{Mutex, MutexGuard};
+
+use async_trait::async_trait;
+use chrono::Utc;
+
+use super::ingredient::{Ingredient, IngredientPatch};
+use super::patch::PatchValue;
+use super::recipe::{Recipe, RecipePatch, RecipeSort};
+use super::recipe_ingredient::{RecipeIngredientMac, RecipeIngredientPatch};
+use super::repo::{IngredientRepo, RecipeRepo};
+use super::validate::{Validate, Validator};
+use crate::{model, security::UserCtx};
+
+// same starting point as the sql schema
+const FIRST_ID: i64 = 1000;
+
+// region: MemRepo
+/// The repositories fully in memory, for the web tests (no db, deterministic, parallel safe).
+/// Note: same rules as the `*Mac` (validation, versions, not found), but no change feed, audit log,
+///       revisions, favorites nor trash (deleted is gone).
+#[derive(Default)]
+pub struct MemRepo {
+    store: Mutex<MemStore>,
+}
+
+#[derive(Default)]
+struct MemStore {
+    ingredients: BTreeMap<i64, Ingredient>,
+    recipes: BTreeMap<i64, Recipe>,
+    // by recipe id
+    links: BTreeMap<i64, Vec<RecipeIngredientMac>>,
+    last_ingredient_id: i64,
+    last_recipe_id: i64,
+}
+
+impl MemRepo {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    fn store(&self) -> MutexGuard<'_, MemStore> {
+        // a panic while holding the lock cannot leave the maps half updated, so keep going
+        self.store.lock().unwrap_or_else(|ex| ex.into_inner())
+    }
+}
+// endregion: MemRepo
+
+// region: IngredientRepo
+#[async_trait]
+impl IngredientRepo for MemRepo {
+    async fn list(&self, _utx: &UserCtx) -> Result<Vec<Ingredient>, model::Error> {
+        Ok(self.store().ingredients.values().rev().cloned().collect())
+    }
+
+    async fn get(&self, _utx: &UserCtx, id: i64) -> Result<Ingredient, model::Error> {
+        self.store().ingredient(id).cloned()
+    }
+
+    async fn create(
+        &self,
+        _utx: &UserCtx,
+        mut data: IngredientPatch,
+    ) -> Result<Ingredient, model::Error> {
+        data.validate()?;
+        let name = data
+            .name
+            .not_null_or("ingredients", "name", "untitled".to_string())?;
+        let quantity =
+            data.quantity
+                .not_null_or("ingredients", "quantity", "unknown".to_string())?;
+
+        let mut store = self.store();
+        let id = next_id(&mut store.last_ingredient_id);
+        let ingredient = Ingredient {
+            id,
+            name,
+            quantity,
+            version: 1,
+        };
+        store.ingredients.insert(id, ingredient.clone());
+
+        Ok(ingredient)
+    }
+
+    async fn update(
+        &self,
+        utx: &UserCtx,
+        id: i64,
+        data: IngredientPatch,
+    ) -> Result<Ingredient, model::Error> {
+        self.store().update_ingredient(utx, id, None, data)
+    }
+
+    async fn update_if_match(
+        &self,
+        utx: &UserCtx,
+        id: i64,
+        version: i64,
+        data: IngredientPatch,
+    ) -> Result<Ingredient, model::Error> {
+        self.store().update_ingredient(utx, id, Some(version), data)
+    }
+
+    async fn delete(&self, _utx: &UserCtx, id: i64) -> Result<Ingredient, model::Error> {
+        self.store().delete_ingredient(id, None)
+    }
+
+    async fn delete_if_match(
+        &self,
+        _utx: &UserCtx,
+        id: i64,
+        version: i64,
+    ) -> Result<Ingredient, model::Error> {
+        self.store().delete_ingredient(id, Some(version))
+    }
+}
+// endregion: IngredientRepo
+
+// region: RecipeRepo
+#[async_trait]
+impl RecipeRepo for MemRepo {
+    async fn list_sorted(
+        &self,
+        _utx: &UserCtx,
+        sort: RecipeSort,
+    ) -> Result<Vec<(Recipe, Vec<RecipeIngredientMac>)>, model::Error> {
+        let store = self.store();
+        let mut recipes: Vec<Recipe> = store.recipes.values().rev().cloned().collect();
+        if sort == RecipeSort::Rating {
+            // same order as the sql one: unrated last, then best average, most reviews, newest
+            recipes.sort_by(|a, b| {
+                a.rating_avg
+                    .is_none()
+                    .cmp(&b.rating_avg.is_none())
+                    .then(
+                        b.rating_avg
+                            .partial_cmp(&a.rating_avg)
+                            .unwrap_or(Ordering::Equal),
+                    )
+                    .then(b.rating_count.cmp(&a.rating_count))
+                    .then(b.id.cmp(&a.id))
+            });
+        }
+
+        Ok(recipes
+            .into_iter()
+            .map(|recipe| {
+                let ingredients = store.recipe_links(recipe.id);
+                (recipe, ingredients)
+            })
+            .collect())
+    }
+
+    async fn get(
+        &self,
+        _utx: &UserCtx,
+        id: i64,
+    ) -> Result<(Recipe, Vec<RecipeIngredientMac>), model::Error> {
+        let store = self.store();
+        let recipe = store.recipe(id)?.clone();
+
+        Ok((recipe, store.recipe_links(id)))
+    }
+
+    async fn create(
+        &self,
+        utx: &UserCtx,
+        mut data: RecipePatch,
+    ) -> Result<(Recipe, Vec<RecipeIngredientMac>), model::Error> {
+        let mut store = self.store();
+        store.validate_recipe_patch(&mut data)?;
+
+        let title = data.recipe_patch.title.not_null_or(
+            "recipes",
+            "title",
+            "Untitled Recipe".to_string(),
+        )?;
+        let cid = match data.recipe_patch.cid {
+            PatchValue::Absent => Some(0),
+            cid => cid.value(),
+        };
+
+        let id = next_id(&mut store.last_recipe_id);
+        let now = Utc::now();
+        let recipe = Recipe {
+            id,
+            title,
+            cid,
+            ctime: now,
+            mtime: now,
+            version: 1,
+            rating_avg: None,
+            rating_count: 0,
+            is_favorite: false,
+        };
+        store.recipes.insert(id, recipe.clone());
+        if let Some(ingredients) = data.ingredients.value() {
+            store.set_recipe_links(utx, id, ingredients);
+        }
+
+        Ok((recipe, store.recipe_links(id)))
+    }
+
+    async fn update(
+        &self,
+        utx: &UserCtx,
+        id: i64,
+        data: RecipePatch,
+    ) -> Result<(Recipe, Vec<RecipeIngredientMac>), model::Error> {
+        self.store().update_recipe(utx, id, None, data)
+    }
+
+    async fn update_if_match(
+        &self,
+        utx: &UserCtx,
+        id: i64,
+        version: i64,
+        data: RecipePatch,
+    ) -> Result<(Recipe, Vec<RecipeIngredientMac>), model::Error> {
+        self.store().update_recipe(utx, id, Some(version), data)
+    }
+
+    async fn delete(
+        &self,
+        _utx: &UserCtx,
+        id: i64,
+    ) -> Result<(Recipe, Vec<RecipeIngredientMac>), model::Error> {
+        self.store().delete_recipe(id, None)
+    }
+
+    async fn delete_if_match(
+        &self,
+        _utx: &UserCtx,
+        id: i64,
+        version: i64,
+    ) -> Result<(Recipe, Vec<RecipeIngredientMac>), model::Error> {
+        self.store().delete_recipe(id, Some(version))
+    }
+}
+// endregion: RecipeRepo
+
+// region: MemStore
+impl MemStore {
+    fn ingredient(&self, id: i64) -> Result<&Ingredient, model::Error> {
+        self.ingredients
+            .get(&id)
+            .ok_or_else(|| model::Error::EntityNotFound("ingredients", id.to_string()))
+    }
+
+    fn update_ingredient(
+        &mut self,
+        _utx: &UserCtx,
+        id: i64,
+        version: Option<i64>,
+        mut data: IngredientPatch,
+    ) -> Result<Ingredient, model::Error> {
+        data.validate()?;
+        check_version(self.ingredient(id)?.version, version, "ingredients", id)?;
+
+        let name = patch_not_null(data.name, "ingredients", "name")?;
+        let quantity = patch_not_null(data.quantity, "ingredients", "quantity")?;
+        let ingredient = self
+            .ingredients
+            .get_mut(&id)
+            .ok_or_else(|| model::Error::EntityNotFound("ingredients", id.to_string()))?;
+        if let Some(name) = name {
+            ingredient.name = name;
+        }
+        if let Some(quantity) = quantity {
+            ingredient.quantity = quantity;
+        }
+        ingredient.version += 1;
+
+        Ok(ingredient.clone())
+    }
+
+    fn delete_ingredient(
+        &mut self,
+        id: i64,
+        version: Option<i64>,
+    ) -> Result<Ingredient, model::Error> {
+        check_version(self.ingredient(id)?.version, version, "ingredients", id)?;
+
+        // the links go along with the ingredient, as they do to the trash
+        for links in self.links.values_mut() {
+            links.retain(|link| link.ingredient_id != id);
+        }
+        self.ingredients
+            .remove(&id)
+            .ok_or_else(|| model::Error::EntityNotFound("ingredients", id.to_string()))
+    }
+
+    fn recipe(&self, id: i64) -> Result<&Recipe, model::Error> {
+        self.recipes
+            .get(&id)
+            .ok_or_else(|| model::Error::EntityNotFound("recipes", id.to_string()))
+    }
+
+    fn recipe_links(&self, recipe_id: i64) -> Vec<RecipeIngredientMac> {
+        self.links.get(&recipe_id).cloned().unwrap_or_default()
+    }
+
+    fn set_recipe_links(
+        &mut self,
+        utx: &UserCtx,
+        recipe_id: i64,
+        ingredients: Vec<RecipeIngredientPatch>,
+    ) {
+        let now = Utc::now();
+        let links = ingredients
+            .into_iter()
+            .map(|ingredient| RecipeIngredientMac {
+                recipe_id,
+                ingredient_id: ingredient.ingredient_id,
+                ingredient_name: ingredient.ingredient_name,
+                quantity: ingredient.quantity,
+                cid: utx.user_id,
+                ctime: now,
+                mtime: now,
+            })
+            .collect();
+        self.links.insert(recipe_id, links);
+    }
+
+    fn update_recipe(
+        &mut self,
+        utx: &UserCtx,
+        id: i64,
+        version: Option<i64>,
+        mut data: RecipePatch,
+    ) -> Result<(Recipe, Vec<RecipeIngredientMac>), model::Error> {
+        self.validate_recipe_patch(&mut data)?;
+        check_version(self.recipe(id)?.version, version, "recipes", id)?;
+
+        let title = patch_not_null(data.recipe_patch.title, "recipes", "title")?;
+        let recipe = self
+            .recipes
+            .get_mut(&id)
+            .ok_or_else(|| model::Error::EntityNotFound("recipes", id.to_string()))?;
+        if let Some(title) = title {
+            recipe.title = title;
+        }
+        match data.recipe_patch.cid {
+            PatchValue::Absent => (),
+            cid => recipe.cid = cid.value(),
+        }
+        recipe.version += 1;
+        recipe.mtime = Utc::now();
+        let recipe = recipe.clone();
+
+        // explicit null clears the ingredients
+        match data.ingredients {
+            PatchValue::Absent => (),
+            PatchValue::Null => self.set_recipe_links(utx, id, Vec::new()),
+            PatchValue::Value(ingredients) => self.set_recipe_links(utx, id, ingredients),
+        }
+
+        Ok((recipe, self.recipe_links(id)))
+    }
+
+    fn delete_recipe(
+        &mut self,
+        id: i64,
+        version: Option<i64>,
+    ) -> Result<(Recipe, Vec<RecipeIngredientMac>), model::Error> {
+        check_version(self.recipe(id)?.version, version, "recipes", id)?;
+
+        let ingredients = self.links.remove(&id).unwrap_or_default();
+        let recipe = self
+            .recipes
+            .remove(&id)
+            .ok_or_else(|| model::Error::EntityNotFound("recipes", id.to_string()))?;
+
+        Ok((recipe, ingredients))
+    }
+
+    /// Same as the `RecipeMac` one: field rules, then the ingredient ids lookup.
+    fn validate_recipe_patch(&self, data: &mut RecipePatch) -> Result<(), model::Error> {
+        let mut v = Validator::default();
+        data.check(&mut v);
+        if let PatchValue::Value(ingredients) = &data.ingredients {
+            for (idx, ingredient) in ingredients.iter().enumerate() {
+                let id = ingredient.ingredient_id;
+                if id > 0 && !self.ingredients.contains_key(&id) {
+                    v.nested(&format!("ingredients[{}]", idx), |v| {
+                        v.error(
+                            "ingredient_id",
+                            &format!("ingredient {} does not exist", id),
+                        )
+                    });
+                }
+            }
+        }
+        v.finish()
+    }
+}
+// endregion: MemStore
+
+// region: Utils
+fn next_id(last_id: &mut i64) -> i64 {
+    *last_id = (*last_id).max(FIRST_ID - 1) + 1;
+    *last_id
+}
+
+fn check_version(
+    current: i64,
+    expected: Option<i64>,
+    typ: &'static str,
+    id: i64,
+) -> Result<(), model::Error> {
+    match expected {
+        Some(version) if version != current => {
+            Err(model::Error::VersionMismatch(typ, id.to_string()))
+        }
+        _ => Ok(()),
+    }
+}
+
+/// New value of a NOT NULL column, `None` to leave it untouched, error when null.
+fn patch_not_null<T>(
+    val: PatchValue<T>,
+    typ: &'static str,
+    field: &'static str,
+) -> Result<Option<T>, model::Error> {
+    match val {
+        PatchValue::Absent => Ok(None),
+        PatchValue::Null => Err(model::Error::PatchNullNotAllowed(typ, field)),
+        PatchValue::Value(v) => Ok(Some(v)),
+    }
+}
+// endregion: Utils
+
+#[cfg(test)]
+#[path = "../_tests/model_mem_repo.rs"]
+mod tests;
@@ -1,23 +1,80 @@
 use thiserror::Error as ThisError;
 
+mod audit;
+mod change_feed;
+mod collection;
 mod db;
+mod event;
+mod favorite;
+#[cfg(test)]
+pub mod fixture;
 mod ingredient;
+mod mem_repo;
+mod patch;
 mod recipe;
+mod recipe_comment;
 mod recipe_ingredient;
+mod recipe_review;
+mod recipe_revision;
+mod repo;
+mod share_link;
+mod sql_builder;
+mod trash;
+mod user;
+mod validate;
 
 // re-export
-pub use db::{init_db, Db};
+pub use audit::{AuditEntry, AuditFilter, AuditMac};
+pub use change_feed::{
+    Change, ChangeFeed, ChangeFeedMac, ClientChange, ClientChangeResult, ClientChangeTyp,
+};
+pub use collection::{Collection, CollectionItem, CollectionMac, CollectionPatch};
+pub use db::{
+    connect_db, init_db, init_db_with_retry, migrate_down, migrate_up, migration_status,
+    pending_migrations, ping, Db,
+};
+pub use event::{ChangeAction, ChangeEvent, EventBus};
+pub use favorite::FavoriteMac;
 pub use ingredient::{Ingredient, IngredientMac, IngredientPatch};
-pub use recipe::{Recipe, RecipeMac, RecipePatch};
+pub use mem_repo::MemRepo;
+pub use patch::PatchValue;
+pub use recipe::{Recipe, RecipeMac, RecipePatch, RecipePatchInner, RecipeSort};
+pub use recipe_comment::{
+    CommentOrder, RecipeComment, RecipeCommentMac, RecipeCommentPatch, RecipeCommentThread,
+};
+pub use recipe_ingredient::{RecipeIngredientMac, RecipeIngredientPatch};
+pub use recipe_review::{RecipeReview, RecipeReviewMac, RecipeReviewPatch};
+pub use recipe_revision::{RecipeRevision, RecipeRevisionDiff, RecipeRevisionMac};
+pub use repo::{DbRepo, IngredientRepo, RecipeRepo};
+pub use share_link::{ShareLink, ShareLinkMac, ShareLinkPatch, SharedView};
+pub use trash::{TrashItem, TrashMac};
+pub use user::{User, UserMac, UserPatch};
+pub use validate::FieldError;
 
+#[allow(clippy::enum_variant_names)]
 #[derive(ThisError, Debug)]
 pub enum Error {
     #[error("Entity Not Found - {0}[{1}]")]
     EntityNotFound(&'static str, String),
 
+    #[error("Entity Version Mismatch - {0}[{1}]")]
+    VersionMismatch(&'static str, String),
+
+    #[error("Patch Null Not Allowed - {0}.{1}")]
+    PatchNullNotAllowed(&'static str, &'static str),
+
+    #[error("Access Denied - {0}[{1}]")]
+    AccessDenied(&'static str, String),
+
+    #[error("Validation Failed - {0:?}")]
+    ValidationFailed(Vec<FieldError>),
+
     #[error(transparent)]
     SqlxError(#[from] sqlx::Error),
 
     #[error(transparent)]
     IOError(#[from] std::io::Error),
+
+    #[error(transparent)]
+    SecurityError(#[from] crate::security::Error),
 }
@@ -0,0 +1,85 @@
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+use crate::model;
+
+/// A field of a PATCH body, telling apart a missing property from an explicit `null`.
+///
+/// Struct fields must be annotated with
+/// `#[serde(default, skip_serializing_if = "PatchValue::is_absent")]`
+/// so a missing property deserializes to `Absent` (and is not serialized back).
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub enum PatchValue<T> {
+    /// property not sent, leave the column untouched
+    #[default]
+    Absent,
+    /// property sent as `null`, clear the column
+    Null,
+    /// property sent with a value
+    Value(T),
+}
+
+impl<T> PatchValue<T> {
+    pub fn is_absent(&self) -> bool {
+        matches!(self, PatchValue::Absent)
+    }
+
+    /// `Some` only when a value was sent.
+    pub fn value(self) -> Option<T> {
+        match self {
+            PatchValue::Value(v) => Some(v),
+            _ => None,
+        }
+    }
+
+    /// Panics when no value was sent (test helper, mirrors `Option::unwrap`).
+    pub fn unwrap(self) -> T {
+        match self {
+            PatchValue::Value(v) => v,
+            PatchValue::Absent => panic!("called `PatchValue::unwrap()` on an `Absent` value"),
+            PatchValue::Null => panic!("called `PatchValue::unwrap()` on a `Null` value"),
+        }
+    }
+
+    /// Value for a NOT NULL column on insert, `default` when absent, error when null.
+    pub fn not_null_or(
+        self,
+        typ: &'static str,
+        field: &'static str,
+        default: T,
+    ) -> Result<T, model::Error> {
+        match self {
+            PatchValue::Absent => Ok(default),
+            PatchValue::Null => Err(model::Error::PatchNullNotAllowed(typ, field)),
+            PatchValue::Value(v) => Ok(v),
+        }
+    }
+}
+
+impl<T> From<Option<T>> for PatchValue<T> {
+    fn from(val: Option<T>) -> Self {
+        match val {
+            Some(v) => PatchValue::Value(v),
+            None => PatchValue::Null,
+        }
+    }
+}
+
+impl<T: Serialize> Serialize for PatchValue<T> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        match self {
+            PatchValue::Value(v) => serializer.serialize_some(v),
+            _ => serializer.serialize_none(),
+        }
+    }
+}
+
+impl<'de, T: Deserialize<'de>> Deserialize<'de> for PatchValue<T> {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        // only called when the property is present, absent is handled by #[serde(default)]
+        Option::<T>::deserialize(deserializer).map(PatchValue::from)
+    }
+}
+
+#[cfg(test)]
+#[path = "../_tests/model_patch.rs"]
+mod tests;
@@ -1,35 +1,95 @@
 use crate::{
+    metrics,
     model::recipe_ingredient,
-    model::{self, db::Db},
+    model::{
+        self,
+        db::{self, Db},
+    },
     security::UserCtx,
 };
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
-use sqlx::mysql;
-use warp::filters::ws::ws;
+use std::collections::HashMap;
 
+use super::audit::{snapshot, AuditMac};
+use super::change_feed::ChangeFeedMac;
+use super::event::ChangeAction;
+use super::favorite::FavoriteMac;
+use super::patch::PatchValue;
 use super::recipe_ingredient::{RecipeIngredientMac, RecipeIngredientPatch};
+use super::recipe_revision::RecipeRevisionMac;
+use super::sql_builder::UpdateBuilder;
+use super::validate::{TextRule, Validate, Validator};
 
 // region: Recipe Types
 #[derive(sqlx::FromRow, Debug, Clone, Serialize, Deserialize)]
 pub struct Recipe {
     pub id: i64,
     pub title: String,
-    pub cid: i64,
+    pub cid: Option<i64>,
     pub ctime: DateTime<Utc>,
     pub mtime: DateTime<Utc>,
+    /// bumped on every update, used as the ETag
+    pub version: i64,
+    /// average review rating, `None` until the first review
+    pub rating_avg: Option<f64>,
+    pub rating_count: i64,
+    /// favorite of the calling user, not a column
+    #[sqlx(default)]
+    pub is_favorite: bool,
+}
+
+/// Order of the recipe list.
+#[derive(Default, Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RecipeSort {
+    /// most recently created first
+    #[default]
+    Newest,
+    /// best rated first, unrated recipes last
+    Rating,
 }
 
-#[derive(Default, Clone, Serialize, Deserialize)]
+#[derive(Default, Debug, Clone, Serialize, Deserialize)]
 pub struct RecipePatch {
+    #[serde(default)]
     pub recipe_patch: RecipePatchInner,
-    pub ingredients: Option<Vec<RecipeIngredientPatch>>,
+    /// replaces all the recipe ingredients when present, `null` clears them
+    #[serde(default, skip_serializing_if = "PatchValue::is_absent")]
+    pub ingredients: PatchValue<Vec<RecipeIngredientPatch>>,
 }
 
-#[derive(Default, Serialize, Deserialize, Clone)]
+#[derive(Default, Debug, Serialize, Deserialize, Clone)]
 pub struct RecipePatchInner {
-    pub title: Option<String>,
-    pub cid: Option<i64>,
+    #[serde(default, skip_serializing_if = "PatchValue::is_absent")]
+    pub title: PatchValue<String>,
+    #[serde(default, skip_serializing_if = "PatchValue::is_absent")]
+    pub cid: PatchValue<i64>,
+}
+
+impl Validate for RecipePatchInner {
+    fn check(&mut self, v: &mut Validator) {
+        v.text("title", &mut self.title, TextRule::required(255));
+    }
+}
+
+impl Validate for RecipePatch {
+    fn check(&mut self, v: &mut Validator) {
+        v.nested("recipe_patch", |v| self.recipe_patch.check(v));
+
+        if let PatchValue::Value(ingredients) = &mut self.ingredients {
+            let mut seen = Vec::new();
+            for (idx, ingredient) in ingredients.iter_mut().enumerate() {
+                v.nested(&format!("ingredients[{}]", idx), |v| {
+                    ingredient.check(v);
+                    if seen.contains(&ingredient.ingredient_id) {
+                        v.error("ingredient_id", "is listed more than once");
+                    }
+                });
+                seen.push(ingredient.ingredient_id);
+            }
+        }
+    }
 }
 // endregion: Recipe Types
 
@@ -40,15 +100,22 @@ impl RecipeMac {
     pub async fn create(
         db: &Db,
         utx: &UserCtx,
-        data: RecipePatch,
+        mut data: RecipePatch,
     ) -> Result<(Recipe, Vec<RecipeIngredientMac>), model::Error> {
+        let _timer = metrics::mac_timer("RecipeMac::create");
+        validate_patch(db, &mut data).await?;
+
         let sql_insert = "INSERT INTO recipes (title, cid) VALUES (?, ?)";
 
-        let title = data
-            .recipe_patch
-            .title
-            .unwrap_or_else(|| "Untitled Recipe".to_string());
-        let cid = data.recipe_patch.cid.unwrap_or(0);
+        let title = data.recipe_patch.title.not_null_or(
+            "recipes",
+            "title",
+            "Untitled Recipe".to_string(),
+        )?;
+        let cid = match data.recipe_patch.cid {
+            PatchValue::Absent => Some(0),
+            cid => cid.value(),
+        };
 
         let result = sqlx::query(sql_insert)
             .bind(title)
@@ -56,23 +123,27 @@ impl RecipeMac {
             .execute(db)
             .await?;
 
-        let last_insert_id = result.last_insert_id();
+        let last_insert_id = db::last_insert_id(&result);
 
         // Insert recipe ingredients
-        if let Some(ingredients) = data.ingredients {
+        if let Some(ingredients) = data.ingredients.value() {
             for ingredient in ingredients {
                 sqlx::query(
-                    "INSERT INTO recipe_ingredients (recipe_id, ingredient_id, ingredient_name, quantity) VALUES (?, ?, ?, ?)",
+                    "INSERT INTO recipe_ingredients (recipe_id, ingredient_id, ingredient_name, quantity, cid) VALUES (?, ?, ?, ?, ?)",
                 )
                 .bind(last_insert_id)
                 .bind(ingredient.ingredient_id)
                 .bind(ingredient.ingredient_name)
                 .bind(ingredient.quantity)
+                .bind(utx.user_id)
                 .execute(db)
                 .await?;
             }
         }
 
+        // Record the first revision
+        RecipeRevisionMac::record(db, utx, last_insert_id).await?;
+
         // Fetch the recipe
         let sql_select_recipe = "SELECT * FROM recipes WHERE id = ?";
         let recipe = sqlx::query_as::<_, Recipe>(sql_select_recipe)
@@ -81,26 +152,65 @@ impl RecipeMac {
             .await?;
 
         // Fetch the ingredients
-        let ingredients = RecipeIngredientMac::list_by_recipe(db, last_insert_id as i64).await?;
+        let ingredients = RecipeIngredientMac::list_by_recipe(db, last_insert_id).await?;
+
+        ChangeFeedMac::record(db, utx, "recipe", recipe.id, ChangeAction::Created).await?;
+        if !ingredients.is_empty() {
+            ChangeFeedMac::record(
+                db,
+                utx,
+                "recipe_ingredients",
+                recipe.id,
+                ChangeAction::Created,
+            )
+            .await?;
+        }
+
+        AuditMac::record(
+            db,
+            utx,
+            "recipe",
+            recipe.id,
+            "created",
+            None,
+            snapshot(&recipe),
+        )
+        .await?;
+        if !ingredients.is_empty() {
+            AuditMac::record(
+                db,
+                utx,
+                "recipe_ingredients",
+                recipe.id,
+                "created",
+                None,
+                snapshot(&ingredients),
+            )
+            .await?;
+        }
 
         Ok((recipe, ingredients))
     }
 
     pub async fn get(
         db: &Db,
-        _utx: &UserCtx,
+        utx: &UserCtx,
         id: i64,
     ) -> Result<(Recipe, Vec<RecipeIngredientMac>), model::Error> {
+        let _timer = metrics::mac_timer("RecipeMac::get");
         // Fetch the recipe
-        let sql_recipe = "SELECT * FROM recipes WHERE id = ?";
-        let recipe = sqlx::query_as::<_, Recipe>(sql_recipe)
-            .bind(id)
-            .fetch_one(db)
-            .await
-            .map_err(|sqlx_error| match sqlx_error {
-                sqlx::Error::RowNotFound => model::Error::EntityNotFound("recipes", id.to_string()),
-                other => model::Error::SqlxError(other),
-            })?;
+        let sql_recipe = "SELECT * FROM recipes WHERE id = ? AND deleted_at IS NULL";
+        let mut recipe = db::retry_read(|| {
+            sqlx::query_as::<_, Recipe>(sql_recipe)
+                .bind(id)
+                .fetch_one(db)
+        })
+        .await
+        .map_err(|sqlx_error| match sqlx_error {
+            sqlx::Error::RowNotFound => model::Error::EntityNotFound("recipes", id.to_string()),
+            other => model::Error::SqlxError(other),
+        })?;
+        recipe.is_favorite = FavoriteMac::recipe_ids(db, utx).await?.contains(&id);
 
         // Fetch the ingredients associated with the recipe
         let ingredients = RecipeIngredientMac::list_by_recipe(db, id).await?;
@@ -110,101 +220,318 @@ impl RecipeMac {
 
     pub async fn update(
         db: &Db,
-        _utx: &UserCtx,
+        utx: &UserCtx,
         id: i64,
         data: RecipePatch,
     ) -> Result<(Recipe, Vec<RecipeIngredientMac>), model::Error> {
-        let sql = "UPDATE recipes SET title = ?, cid = ? WHERE id = ?";
+        let _timer = metrics::mac_timer("RecipeMac::update");
+        Self::update_versioned(db, utx, id, None, data).await
+    }
 
-        let title = data
-            .recipe_patch
-            .title
-            .unwrap_or_else(|| "Untitled Recipe".to_string());
-        let cid = data.recipe_patch.cid.unwrap_or(0);
+    /// Update only if the recipe is still at `version`, otherwise fail with `VersionMismatch`.
+    pub async fn update_if_match(
+        db: &Db,
+        utx: &UserCtx,
+        id: i64,
+        version: i64,
+        data: RecipePatch,
+    ) -> Result<(Recipe, Vec<RecipeIngredientMac>), model::Error> {
+        let _timer = metrics::mac_timer("RecipeMac::update_if_match");
+        Self::update_versioned(db, utx, id, Some(version), data).await
+    }
+
+    async fn update_versioned(
+        db: &Db,
+        utx: &UserCtx,
+        id: i64,
+        version: Option<i64>,
+        mut data: RecipePatch,
+    ) -> Result<(Recipe, Vec<RecipeIngredientMac>), model::Error> {
+        validate_patch(db, &mut data).await?;
+
+        // Make sure the state being overwritten is kept in the history
+        RecipeRevisionMac::record_baseline(db, utx, id).await?;
+        let (before, before_ingredients) = Self::get(db, utx, id).await?;
+
+        // Only the properties present in the patch are set.
+        // Note: version is always bumped, since the ingredients might change alone
+        let mut sb = UpdateBuilder::new("recipes")
+            .patch("title", data.recipe_patch.title)?
+            .patch_nullable("cid", data.recipe_patch.cid)
+            .set_raw("version", "version + 1")
+            .and_where_eq("id", id)
+            .and_where_raw("deleted_at IS NULL");
+        if let Some(version) = version {
+            sb = sb.and_where_eq("version", version);
+        }
 
         // Perform the update query
-        sqlx::query(sql)
-            .bind(title)
-            .bind(cid)
+        let result = sb.execute(db).await?;
+        if result.rows_affected() == 0 {
+            return Err(Self::no_match_error(db, utx, id).await);
+        }
+
+        // Update recipe ingredients (explicit null clears them)
+        let links_changed = !data.ingredients.is_absent();
+        let ingredients = match data.ingredients {
+            PatchValue::Absent => None,
+            PatchValue::Null => Some(Vec::new()),
+            PatchValue::Value(ingredients) => Some(ingredients),
+        };
+        if let Some(ingredients) = ingredients {
+            // First delete existing ingredients (links trashed with their ingredient are kept for restore)
+            sqlx::query(
+                "DELETE FROM recipe_ingredients WHERE recipe_id = ? AND deleted_at IS NULL",
+            )
             .bind(id)
             .execute(db)
             .await?;
 
-        // Update recipe ingredients
-        if let Some(ingredients) = data.ingredients {
-            // First delete existing ingredients
-            sqlx::query("DELETE FROM recipe_ingredients WHERE recipe_id = ?")
-                .bind(id)
-                .execute(db)
-                .await?;
-
             // Insert updated ingredients
             for ingredient in ingredients {
                 sqlx::query(
-                    "INSERT INTO recipe_ingredients (recipe_id, ingredient_id, ingredient_name, quantity) VALUES (?, ?, ?, ?)",
+                    "INSERT INTO recipe_ingredients (recipe_id, ingredient_id, ingredient_name, quantity, cid) VALUES (?, ?, ?, ?, ?)",
                 )
                 .bind(id)
                 .bind(ingredient.ingredient_id)
                 .bind(ingredient.ingredient_name)
                 .bind(ingredient.quantity)
+                .bind(utx.user_id)
                 .execute(db)
                 .await?;
             }
         }
 
+        // Record the new revision
+        RecipeRevisionMac::record(db, utx, id).await?;
+
+        ChangeFeedMac::record(db, utx, "recipe", id, ChangeAction::Updated).await?;
+        if links_changed {
+            ChangeFeedMac::record(db, utx, "recipe_ingredients", id, ChangeAction::Updated).await?;
+        }
+
         // Return the updated recipe and its ingredients
-        let sql_select_recipe = "SELECT * FROM recipes WHERE id = ?";
-        let recipe = sqlx::query_as::<_, Recipe>(sql_select_recipe)
-            .bind(id)
-            .fetch_one(db)
+        let (recipe, ingredients) = Self::get(db, utx, id).await?;
+
+        AuditMac::record(
+            db,
+            utx,
+            "recipe",
+            id,
+            "updated",
+            snapshot(&before),
+            snapshot(&recipe),
+        )
+        .await?;
+        if links_changed {
+            AuditMac::record(
+                db,
+                utx,
+                "recipe_ingredients",
+                id,
+                "updated",
+                snapshot(&before_ingredients),
+                snapshot(&ingredients),
+            )
             .await?;
-
-        let ingredients = RecipeIngredientMac::list_by_recipe(db, id).await?;
+        }
 
         Ok((recipe, ingredients))
     }
 
     pub async fn list(
         db: &Db,
-        _utx: &UserCtx,
+        utx: &UserCtx,
+    ) -> Result<Vec<(Recipe, Vec<RecipeIngredientMac>)>, model::Error> {
+        let _timer = metrics::mac_timer("RecipeMac::list");
+        Self::list_sorted(db, utx, RecipeSort::Newest).await
+    }
+
+    pub async fn list_sorted(
+        db: &Db,
+        utx: &UserCtx,
+        sort: RecipeSort,
     ) -> Result<Vec<(Recipe, Vec<RecipeIngredientMac>)>, model::Error> {
-        let sql = "SELECT * FROM recipes ORDER BY id DESC";
+        let _timer = metrics::mac_timer("RecipeMac::list_sorted");
+        let recipes = Self::list_recipes(db, utx, sort).await?;
+
+        // Fetch the ingredients of all the recipes at once
+        let ids: Vec<i64> = recipes.iter().map(|recipe| recipe.id).collect();
+        let mut links_by_recipe: HashMap<i64, Vec<RecipeIngredientMac>> = HashMap::new();
+        for link in RecipeIngredientMac::list_by_recipe_ids(db, &ids).await? {
+            links_by_recipe
+                .entry(link.recipe_id)
+                .or_default()
+                .push(link);
+        }
 
-        let recipes = sqlx::query_as::<_, Recipe>(sql).fetch_all(db).await?;
+        Ok(recipes
+            .into_iter()
+            .map(|recipe| {
+                let ingredients = links_by_recipe.remove(&recipe.id).unwrap_or_default();
+                (recipe, ingredients)
+            })
+            .collect())
+    }
 
-        // Fetch ingredients for each recipe
-        let mut result = Vec::new();
-        for recipe in recipes {
-            let ingredients = RecipeIngredientMac::list_by_recipe(db, recipe.id).await?;
-            result.push((recipe, ingredients));
+    /// Recipes only, without their ingredients (e.g. for the GraphQL list).
+    pub async fn list_recipes(
+        db: &Db,
+        utx: &UserCtx,
+        sort: RecipeSort,
+    ) -> Result<Vec<Recipe>, model::Error> {
+        let _timer = metrics::mac_timer("RecipeMac::list_recipes");
+        let order_by = match sort {
+            RecipeSort::Newest => "id DESC",
+            RecipeSort::Rating => "rating_avg IS NULL, rating_avg DESC, rating_count DESC, id DESC",
+        };
+        let sql = format!(
+            "SELECT * FROM recipes WHERE deleted_at IS NULL ORDER BY {}",
+            order_by
+        );
+
+        let mut recipes =
+            db::retry_read(|| sqlx::query_as::<_, Recipe>(&sql).fetch_all(db)).await?;
+        let favorite_ids = FavoriteMac::recipe_ids(db, utx).await?;
+        for recipe in recipes.iter_mut() {
+            recipe.is_favorite = favorite_ids.contains(&recipe.id);
         }
 
-        Ok(result)
+        Ok(recipes)
     }
 
+    /// The live recipes among `ids`, without their ingredients. Missing ones are left out.
+    pub async fn get_many(
+        db: &Db,
+        utx: &UserCtx,
+        ids: &[i64],
+    ) -> Result<Vec<Recipe>, model::Error> {
+        let _timer = metrics::mac_timer("RecipeMac::get_many");
+        let sql = format!(
+            "SELECT * FROM recipes WHERE id IN {} AND deleted_at IS NULL",
+            db::in_list(ids.len())
+        );
+
+        let mut recipes = db::retry_read(|| {
+            let mut query = sqlx::query_as::<_, Recipe>(&sql);
+            for id in ids {
+                query = query.bind(id);
+            }
+            query.fetch_all(db)
+        })
+        .await?;
+        let favorite_ids = FavoriteMac::recipe_ids(db, utx).await?;
+        for recipe in recipes.iter_mut() {
+            recipe.is_favorite = favorite_ids.contains(&recipe.id);
+        }
+
+        Ok(recipes)
+    }
+
+    /// Live (not trashed) recipes, for the metrics.
+    pub async fn count(db: &Db) -> Result<i64, model::Error> {
+        let _timer = metrics::mac_timer("RecipeMac::count");
+        let sql = "SELECT COUNT(*) FROM recipes WHERE deleted_at IS NULL";
+        let (count,) = db::retry_read(|| sqlx::query_as::<_, (i64,)>(sql).fetch_one(db)).await?;
+
+        Ok(count)
+    }
+
+    /// Move the recipe to the trash. Its ingredient links stay untouched and come back on restore.
     pub async fn delete(
         db: &Db,
-        _utx: &UserCtx,
+        utx: &UserCtx,
         id: i64,
     ) -> Result<(Recipe, Vec<RecipeIngredientMac>), model::Error> {
-        // Fetch the recipe
-        let sql_select = "SELECT * FROM recipes WHERE id = ?";
-        let recipe = sqlx::query_as::<_, Recipe>(sql_select)
+        let _timer = metrics::mac_timer("RecipeMac::delete");
+        Self::delete_versioned(db, utx, id, None).await
+    }
+
+    /// Delete only if the recipe is still at `version`, otherwise fail with `VersionMismatch`.
+    pub async fn delete_if_match(
+        db: &Db,
+        utx: &UserCtx,
+        id: i64,
+        version: i64,
+    ) -> Result<(Recipe, Vec<RecipeIngredientMac>), model::Error> {
+        let _timer = metrics::mac_timer("RecipeMac::delete_if_match");
+        Self::delete_versioned(db, utx, id, Some(version)).await
+    }
+
+    async fn delete_versioned(
+        db: &Db,
+        utx: &UserCtx,
+        id: i64,
+        version: Option<i64>,
+    ) -> Result<(Recipe, Vec<RecipeIngredientMac>), model::Error> {
+        // Fetch the recipe and its ingredients
+        let (recipe, ingredients) = Self::get(db, utx, id).await?;
+
+        // Trash the recipe
+        let sql_delete = "UPDATE recipes SET deleted_at = ? WHERE id = ? AND deleted_at IS NULL AND (? IS NULL OR version = ?)";
+        let result = sqlx::query(sql_delete)
+            .bind(Utc::now())
             .bind(id)
-            .fetch_one(db)
+            .bind(version)
+            .bind(version)
+            .execute(db)
             .await?;
+        if result.rows_affected() == 0 {
+            return Err(Self::no_match_error(db, utx, id).await);
+        }
 
-        // Fetch the ingredients
-        let ingredients = RecipeIngredientMac::list_by_recipe(db, id).await?;
+        ChangeFeedMac::record(db, utx, "recipe", id, ChangeAction::Deleted).await?;
+        AuditMac::record(db, utx, "recipe", id, "deleted", snapshot(&recipe), None).await?;
+
+        Ok((recipe, ingredients))
+    }
+
+    /// Bring a recipe back from the trash.
+    pub async fn restore(
+        db: &Db,
+        utx: &UserCtx,
+        id: i64,
+    ) -> Result<(Recipe, Vec<RecipeIngredientMac>), model::Error> {
+        let _timer = metrics::mac_timer("RecipeMac::restore");
+        let sql_restore =
+            "UPDATE recipes SET deleted_at = NULL WHERE id = ? AND deleted_at IS NOT NULL";
+        let result = sqlx::query(sql_restore).bind(id).execute(db).await?;
+        if result.rows_affected() == 0 {
+            return Err(model::Error::EntityNotFound("recipes", id.to_string()));
+        }
+
+        // Back in the lists, so "created" for the listeners
+        ChangeFeedMac::record(db, utx, "recipe", id, ChangeAction::Created).await?;
 
-        // Delete the recipe
-        let sql_delete = "DELETE FROM recipes WHERE id = ?";
-        sqlx::query(sql_delete).bind(id).execute(db).await?;
+        let (recipe, ingredients) = Self::get(db, utx, id).await?;
+        AuditMac::record(db, utx, "recipe", id, "restored", None, snapshot(&recipe)).await?;
 
         Ok((recipe, ingredients))
     }
+
+    /// Tell apart a conditional write that found no row (gone) from one that lost the race (stale version).
+    async fn no_match_error(db: &Db, utx: &UserCtx, id: i64) -> model::Error {
+        match Self::get(db, utx, id).await {
+            Ok(_) => model::Error::VersionMismatch("recipes", id.to_string()),
+            Err(ex) => ex,
+        }
+    }
 }
 // endregion: RecipeMac
+
+// region: Utils
+/// Field rules first, then the ingredient ids lookup, so all the errors come back together.
+async fn validate_patch(db: &Db, data: &mut RecipePatch) -> Result<(), model::Error> {
+    let mut v = Validator::default();
+    data.check(&mut v);
+    if let PatchValue::Value(ingredients) = &data.ingredients {
+        RecipeIngredientMac::check_ingredients_exist(db, "ingredients", ingredients, &mut v)
+            .await?;
+    }
+    v.finish()
+}
+// endregion: Utils
+
 #[cfg(test)]
 #[path = "../_tests/model_recipe.rs"]
 mod tests;
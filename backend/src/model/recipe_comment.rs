@@ -0,0 +1,240 @@
+use crate::{
+    metrics,
+    model::{
+        self,
+        db::{self, Db},
+    },
+    security::UserCtx,
+};
+use chrono::{DateTime, Utc};
+use pulldown_cmark::{html, Options, Parser};
+use serde::{Deserialize, Serialize};
+
+use super::recipe::RecipeMac;
+use super::validate::{TextRule, Validate, Validator};
+
+const PAGE_LIMIT_MAX: i64 = 100;
+
+// region: Recipe Comment Types
+#[derive(sqlx::FromRow, Debug, Clone, Serialize, Deserialize)]
+pub struct RecipeComment {
+    pub id: i64,
+    pub recipe_id: i64,
+    pub parent_id: Option<i64>,
+    pub root_id: Option<i64>,
+    /// Markdown source, empty once deleted
+    pub body: String,
+    /// sanitized HTML rendering of `body`, safe to insert as is
+    pub body_html: String,
+    /// author
+    pub cid: i64,
+    pub ctime: DateTime<Utc>,
+    pub mtime: DateTime<Utc>,
+    pub deleted_at: Option<DateTime<Utc>>,
+}
+
+/// A root comment with all its replies, oldest reply first.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RecipeCommentThread {
+    pub comment: RecipeComment,
+    pub replies: Vec<RecipeComment>,
+}
+
+#[derive(Default, Debug, Clone, Serialize, Deserialize)]
+pub struct RecipeCommentPatch {
+    pub body: String,
+    /// comment replied to, ignored on edit
+    #[serde(default)]
+    pub parent_id: Option<i64>,
+}
+
+impl Validate for RecipeCommentPatch {
+    fn check(&mut self, v: &mut Validator) {
+        v.text_value("body", &mut self.body, TextRule::required(10000));
+        if let Some(parent_id) = self.parent_id {
+            v.id("parent_id", parent_id);
+        }
+    }
+}
+
+/// Order of the comment threads, by root comment creation.
+#[derive(Default, Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CommentOrder {
+    #[default]
+    Newest,
+    Oldest,
+}
+// endregion: Recipe Comment Types
+
+// region: RecipeCommentMac
+pub struct RecipeCommentMac;
+
+impl RecipeCommentMac {
+    pub async fn create(
+        db: &Db,
+        utx: &UserCtx,
+        recipe_id: i64,
+        mut data: RecipeCommentPatch,
+    ) -> Result<RecipeComment, model::Error> {
+        let _timer = metrics::mac_timer("RecipeCommentMac::create");
+        data.validate()?;
+        RecipeMac::get(db, utx, recipe_id).await?;
+
+        // Replies join the thread of their parent
+        let root_id = match data.parent_id {
+            Some(parent_id) => {
+                let parent = Self::get(db, utx, recipe_id, parent_id).await?;
+                Some(parent.root_id.unwrap_or(parent.id))
+            }
+            None => None,
+        };
+
+        let sql_insert = "INSERT INTO recipe_comments (recipe_id, parent_id, root_id, body, body_html, cid) VALUES (?, ?, ?, ?, ?, ?)";
+        let result = sqlx::query(sql_insert)
+            .bind(recipe_id)
+            .bind(data.parent_id)
+            .bind(root_id)
+            .bind(&data.body)
+            .bind(render_markdown(&data.body))
+            .bind(utx.user_id)
+            .execute(db)
+            .await?;
+
+        Self::get(db, utx, recipe_id, db::last_insert_id(&result)).await
+    }
+
+    pub async fn get(
+        db: &Db,
+        _utx: &UserCtx,
+        recipe_id: i64,
+        id: i64,
+    ) -> Result<RecipeComment, model::Error> {
+        let _timer = metrics::mac_timer("RecipeCommentMac::get");
+        let sql = "SELECT * FROM recipe_comments WHERE id = ? AND recipe_id = ?";
+        sqlx::query_as::<_, RecipeComment>(sql)
+            .bind(id)
+            .bind(recipe_id)
+            .fetch_one(db)
+            .await
+            .map_err(|sqlx_error| match sqlx_error {
+                sqlx::Error::RowNotFound => {
+                    model::Error::EntityNotFound("recipe_comments", id.to_string())
+                }
+                other => model::Error::SqlxError(other),
+            })
+    }
+
+    /// A page of threads. `limit` and `offset` count root comments, replies come along.
+    pub async fn list(
+        db: &Db,
+        utx: &UserCtx,
+        recipe_id: i64,
+        order: CommentOrder,
+        limit: i64,
+        offset: i64,
+    ) -> Result<Vec<RecipeCommentThread>, model::Error> {
+        let _timer = metrics::mac_timer("RecipeCommentMac::list");
+        RecipeMac::get(db, utx, recipe_id).await?;
+
+        let order_by = match order {
+            CommentOrder::Newest => "id DESC",
+            CommentOrder::Oldest => "id ASC",
+        };
+        let sql_roots = format!(
+            "SELECT * FROM recipe_comments WHERE recipe_id = ? AND root_id IS NULL ORDER BY {} LIMIT ? OFFSET ?",
+            order_by
+        );
+        let roots = sqlx::query_as::<_, RecipeComment>(&sql_roots)
+            .bind(recipe_id)
+            .bind(limit.clamp(1, PAGE_LIMIT_MAX))
+            .bind(offset.max(0))
+            .fetch_all(db)
+            .await?;
+
+        let sql_replies = "SELECT * FROM recipe_comments WHERE root_id = ? ORDER BY id ASC";
+        let mut threads = Vec::new();
+        for comment in roots {
+            let replies = sqlx::query_as::<_, RecipeComment>(sql_replies)
+                .bind(comment.id)
+                .fetch_all(db)
+                .await?;
+            threads.push(RecipeCommentThread { comment, replies });
+        }
+
+        Ok(threads)
+    }
+
+    /// Only the author can edit a comment.
+    pub async fn update(
+        db: &Db,
+        utx: &UserCtx,
+        recipe_id: i64,
+        id: i64,
+        mut data: RecipeCommentPatch,
+    ) -> Result<RecipeComment, model::Error> {
+        let _timer = metrics::mac_timer("RecipeCommentMac::update");
+        data.validate()?;
+        let comment = Self::get(db, utx, recipe_id, id).await?;
+        if comment.cid != utx.user_id || comment.deleted_at.is_some() {
+            return Err(model::Error::AccessDenied(
+                "recipe_comments",
+                id.to_string(),
+            ));
+        }
+
+        let sql_update = "UPDATE recipe_comments SET body = ?, body_html = ? WHERE id = ?";
+        sqlx::query(sql_update)
+            .bind(&data.body)
+            .bind(render_markdown(&data.body))
+            .bind(id)
+            .execute(db)
+            .await?;
+
+        Self::get(db, utx, recipe_id, id).await
+    }
+
+    /// The author or the recipe owner (moderation) can delete a comment.
+    /// It is blanked rather than removed, so the replies keep their thread.
+    pub async fn delete(
+        db: &Db,
+        utx: &UserCtx,
+        recipe_id: i64,
+        id: i64,
+    ) -> Result<RecipeComment, model::Error> {
+        let _timer = metrics::mac_timer("RecipeCommentMac::delete");
+        let comment = Self::get(db, utx, recipe_id, id).await?;
+        let (recipe, _) = RecipeMac::get(db, utx, recipe_id).await?;
+        if comment.cid != utx.user_id && recipe.cid != Some(utx.user_id) {
+            return Err(model::Error::AccessDenied(
+                "recipe_comments",
+                id.to_string(),
+            ));
+        }
+
+        let sql_delete = "UPDATE recipe_comments SET body = '', body_html = '', deleted_at = ? WHERE id = ? AND deleted_at IS NULL";
+        sqlx::query(sql_delete)
+            .bind(Utc::now())
+            .bind(id)
+            .execute(db)
+            .await?;
+
+        Self::get(db, utx, recipe_id, id).await
+    }
+}
+// endregion: RecipeCommentMac
+
+// region: Utils
+/// Markdown to HTML, then through the ammonia allow-list (no scripts, event handlers or `javascript:` links).
+fn render_markdown(body: &str) -> String {
+    let parser = Parser::new_ext(body, Options::ENABLE_STRIKETHROUGH);
+    let mut unsafe_html = String::new();
+    html::push_html(&mut unsafe_html, parser);
+
+    ammonia::clean(&unsafe_html)
+}
+// endregion: Utils
+
+#[cfg(test)]
+#[path = "../_tests/model_recipe_comment.rs"]
+mod tests;
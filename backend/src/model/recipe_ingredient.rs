@@ -1,8 +1,9 @@
-use crate::model::db::Db;
+use crate::metrics;
+use crate::model::db::{self, Db};
+use crate::model::validate::{TextRule, Validate, Validator};
 use crate::security::UserCtx;
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
-use sqlx::mysql;
 
 // region: Recipe Ingredient Types
 #[derive(sqlx::FromRow, Debug, Clone, Serialize, Deserialize)]
@@ -16,12 +17,24 @@ pub struct RecipeIngredientMac {
     pub mtime: DateTime<Utc>,
 }
 
-#[derive(Default, Serialize, Deserialize, Clone)]
+#[derive(Default, Debug, Serialize, Deserialize, Clone, PartialEq)]
 pub struct RecipeIngredientPatch {
     pub ingredient_id: i64,
     pub ingredient_name: String,
     pub quantity: String,
 }
+
+impl Validate for RecipeIngredientPatch {
+    fn check(&mut self, v: &mut Validator) {
+        v.id("ingredient_id", self.ingredient_id)
+            .text_value(
+                "ingredient_name",
+                &mut self.ingredient_name,
+                TextRule::required(255),
+            )
+            .text_value("quantity", &mut self.quantity, TextRule::required(50));
+    }
+}
 // endregion: Recipe Ingredient Types
 
 // region: RecipeIngredientMac
@@ -30,15 +43,104 @@ impl RecipeIngredientMac {
         db: &Db,
         recipe_id: i64,
     ) -> Result<Vec<RecipeIngredientMac>, sqlx::Error> {
-        let sql = "SELECT * FROM recipe_ingredients WHERE recipe_id = ?";
-        let ingredients = sqlx::query_as::<_, RecipeIngredientMac>(sql)
-            .bind(recipe_id)
-            .fetch_all(db)
-            .await?;
+        let _timer = metrics::mac_timer("RecipeIngredientMac::list_by_recipe");
+        let sql = "SELECT * FROM recipe_ingredients WHERE recipe_id = ? AND deleted_at IS NULL";
+        let ingredients = db::retry_read(|| {
+            sqlx::query_as::<_, RecipeIngredientMac>(sql)
+                .bind(recipe_id)
+                .fetch_all(db)
+        })
+        .await?;
         Ok(ingredients)
     }
 
+    /// Links of several recipes at once, e.g. for a recipe list without one query per recipe.
+    pub async fn list_by_recipe_ids(
+        db: &Db,
+        recipe_ids: &[i64],
+    ) -> Result<Vec<RecipeIngredientMac>, sqlx::Error> {
+        let _timer = metrics::mac_timer("RecipeIngredientMac::list_by_recipe_ids");
+        let sql = format!(
+            "SELECT * FROM recipe_ingredients WHERE recipe_id IN {} AND deleted_at IS NULL",
+            db::in_list(recipe_ids.len())
+        );
+        db::retry_read(|| {
+            let mut query = sqlx::query_as::<_, RecipeIngredientMac>(&sql);
+            for id in recipe_ids {
+                query = query.bind(id);
+            }
+            query.fetch_all(db)
+        })
+        .await
+    }
+
+    /// Links to several ingredients at once (the recipes using them), trashed recipes excluded.
+    pub async fn list_by_ingredient_ids(
+        db: &Db,
+        ingredient_ids: &[i64],
+    ) -> Result<Vec<RecipeIngredientMac>, sqlx::Error> {
+        let _timer = metrics::mac_timer("RecipeIngredientMac::list_by_ingredient_ids");
+        let sql = format!(
+            "SELECT ri.* FROM recipe_ingredients ri JOIN recipes r ON r.id = ri.recipe_id \
+             WHERE ri.ingredient_id IN {} AND ri.deleted_at IS NULL AND r.deleted_at IS NULL \
+             ORDER BY ri.recipe_id DESC",
+            db::in_list(ingredient_ids.len())
+        );
+        db::retry_read(|| {
+            let mut query = sqlx::query_as::<_, RecipeIngredientMac>(&sql);
+            for id in ingredient_ids {
+                query = query.bind(id);
+            }
+            query.fetch_all(db)
+        })
+        .await
+    }
+
+    /// Report the patches pointing to ingredients that do not exist (or are in the trash).
+    /// `prefix` is the path of the list in the request body, e.g. "ingredients".
+    pub async fn check_ingredients_exist(
+        db: &Db,
+        prefix: &str,
+        patches: &[RecipeIngredientPatch],
+        v: &mut Validator,
+    ) -> Result<(), sqlx::Error> {
+        let _timer = metrics::mac_timer("RecipeIngredientMac::check_ingredients_exist");
+        let ids: Vec<i64> = patches.iter().map(|p| p.ingredient_id).collect();
+        if ids.is_empty() {
+            return Ok(());
+        }
+
+        let sql = format!(
+            "SELECT id FROM ingredients WHERE deleted_at IS NULL AND id IN {}",
+            db::in_list(ids.len())
+        );
+        let mut query = sqlx::query_as::<_, (i64,)>(&sql);
+        for id in &ids {
+            query = query.bind(id);
+        }
+        let existing: Vec<i64> = query
+            .fetch_all(db)
+            .await?
+            .into_iter()
+            .map(|(id,)| id)
+            .collect();
+
+        for (idx, id) in ids.iter().enumerate() {
+            if *id > 0 && !existing.contains(id) {
+                v.nested(&format!("{}[{}]", prefix, idx), |v| {
+                    v.error(
+                        "ingredient_id",
+                        &format!("ingredient {} does not exist", id),
+                    )
+                });
+            }
+        }
+
+        Ok(())
+    }
+
     pub async fn delete_by_recipe(db: &Db, recipe_id: i64) -> Result<(), sqlx::Error> {
+        let _timer = metrics::mac_timer("RecipeIngredientMac::delete_by_recipe");
         let sql = "DELETE FROM recipe_ingredients WHERE recipe_id = ?";
         sqlx::query(sql).bind(recipe_id).execute(db).await?;
         Ok(())
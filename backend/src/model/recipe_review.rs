@@ -0,0 +1,157 @@
+use crate::{
+    metrics,
+    model::{
+        self,
+        db::{self, Db},
+    },
+    security::UserCtx,
+};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+use super::recipe::RecipeMac;
+use super::validate::{TextRule, Validate, Validator};
+
+// region: Recipe Review Types
+#[derive(sqlx::FromRow, Debug, Clone, Serialize, Deserialize)]
+pub struct RecipeReview {
+    pub id: i64,
+    pub recipe_id: i64,
+    /// 1 to 5
+    pub rating: i8,
+    pub body: String,
+    /// user who wrote the review
+    pub cid: i64,
+    pub ctime: DateTime<Utc>,
+    pub mtime: DateTime<Utc>,
+}
+
+/// Body of a review write. The review is replaced as a whole, so there is no partial patch here.
+#[derive(Default, Debug, Clone, Serialize, Deserialize)]
+pub struct RecipeReviewPatch {
+    pub rating: i8,
+    #[serde(default)]
+    pub body: String,
+}
+
+impl Validate for RecipeReviewPatch {
+    fn check(&mut self, v: &mut Validator) {
+        v.range("rating", self.rating.into(), 1, 5).text_value(
+            "body",
+            &mut self.body,
+            TextRule::optional(5000),
+        );
+    }
+}
+// endregion: Recipe Review Types
+
+// region: RecipeReviewMac
+pub struct RecipeReviewMac;
+
+impl RecipeReviewMac {
+    pub async fn list(
+        db: &Db,
+        utx: &UserCtx,
+        recipe_id: i64,
+    ) -> Result<Vec<RecipeReview>, model::Error> {
+        let _timer = metrics::mac_timer("RecipeReviewMac::list");
+        // no reviews for recipes in the trash
+        RecipeMac::get(db, utx, recipe_id).await?;
+
+        let sql = "SELECT * FROM recipe_reviews WHERE recipe_id = ? ORDER BY mtime DESC, id DESC";
+        let reviews = sqlx::query_as::<_, RecipeReview>(sql)
+            .bind(recipe_id)
+            .fetch_all(db)
+            .await?;
+
+        Ok(reviews)
+    }
+
+    /// The review of the current user on the recipe.
+    pub async fn get(db: &Db, utx: &UserCtx, recipe_id: i64) -> Result<RecipeReview, model::Error> {
+        let _timer = metrics::mac_timer("RecipeReviewMac::get");
+        let sql = "SELECT * FROM recipe_reviews WHERE recipe_id = ? AND cid = ?";
+        sqlx::query_as::<_, RecipeReview>(sql)
+            .bind(recipe_id)
+            .bind(utx.user_id)
+            .fetch_one(db)
+            .await
+            .map_err(|sqlx_error| match sqlx_error {
+                sqlx::Error::RowNotFound => model::Error::EntityNotFound(
+                    "recipe_reviews",
+                    format!("{}@{}", recipe_id, utx.user_id),
+                ),
+                other => model::Error::SqlxError(other),
+            })
+    }
+
+    /// Create the review of the current user, or replace it when they already reviewed the recipe.
+    pub async fn upsert(
+        db: &Db,
+        utx: &UserCtx,
+        recipe_id: i64,
+        mut data: RecipeReviewPatch,
+    ) -> Result<RecipeReview, model::Error> {
+        let _timer = metrics::mac_timer("RecipeReviewMac::upsert");
+        data.validate()?;
+        RecipeMac::get(db, utx, recipe_id).await?;
+
+        let sql_upsert = format!(
+            "INSERT INTO recipe_reviews (recipe_id, rating, body, cid) VALUES (?, ?, ?, ?) {}",
+            db::on_conflict_update("recipe_id, cid", &["rating", "body"])
+        );
+        sqlx::query(&sql_upsert)
+            .bind(recipe_id)
+            .bind(data.rating)
+            .bind(data.body)
+            .bind(utx.user_id)
+            .execute(db)
+            .await?;
+
+        update_recipe_rating(db, recipe_id).await?;
+
+        Self::get(db, utx, recipe_id).await
+    }
+
+    /// Delete the review of the current user.
+    pub async fn delete(
+        db: &Db,
+        utx: &UserCtx,
+        recipe_id: i64,
+    ) -> Result<RecipeReview, model::Error> {
+        let _timer = metrics::mac_timer("RecipeReviewMac::delete");
+        let review = Self::get(db, utx, recipe_id).await?;
+
+        let sql_delete = "DELETE FROM recipe_reviews WHERE id = ?";
+        sqlx::query(sql_delete).bind(review.id).execute(db).await?;
+
+        update_recipe_rating(db, recipe_id).await?;
+
+        Ok(review)
+    }
+}
+// endregion: RecipeReviewMac
+
+// region: Utils
+/// Refresh the rating aggregate stored on the recipe (kept there so recipe lists can sort on it).
+/// Note: `mtime = mtime` since a review is not a change of the recipe itself.
+async fn update_recipe_rating(db: &Db, recipe_id: i64) -> Result<(), model::Error> {
+    let sql = "UPDATE recipes SET
+            rating_avg = (SELECT AVG(rating) FROM recipe_reviews WHERE recipe_id = ?),
+            rating_count = (SELECT COUNT(*) FROM recipe_reviews WHERE recipe_id = ?),
+            mtime = mtime
+        WHERE id = ?";
+    sqlx::query(sql)
+        .bind(recipe_id)
+        .bind(recipe_id)
+        .bind(recipe_id)
+        .execute(db)
+        .await?;
+
+    Ok(())
+}
+// endregion: Utils
+
+#[cfg(test)]
+#[path = "../_tests/model_recipe_review.rs"]
+mod tests;
@@ -0,0 +1,280 @@
+use std::collections::BTreeMap;
+
+use crate::{
+    metrics,
+    model::{
+        self,
+        db::{self, Db},
+    },
+    security::UserCtx,
+};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use sqlx::types::Json;
+
+use super::patch::PatchValue;
+use super::recipe::{Recipe, RecipeMac, RecipePatch, RecipePatchInner};
+use super::recipe_ingredient::{RecipeIngredientMac, RecipeIngredientPatch};
+
+// region: Recipe Revision Types
+#[derive(sqlx::FromRow, Debug, Clone, Serialize, Deserialize)]
+pub struct RecipeRevision {
+    pub id: i64,
+    pub recipe_id: i64,
+    pub rev: i64,
+    pub title: String,
+    pub recipe_cid: Option<i64>,
+    pub ingredients: Json<Vec<RecipeIngredientPatch>>,
+    /// user who made the change
+    pub cid: i64,
+    pub ctime: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct RecipeRevisionDiff {
+    pub recipe_id: i64,
+    pub from_rev: i64,
+    pub to_rev: i64,
+    pub changes: Vec<RecipeFieldChange>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct RecipeFieldChange {
+    /// e.g. "title", "ingredients[1000]", "ingredients[1000].quantity"
+    pub field: String,
+    pub from: Value,
+    pub to: Value,
+}
+// endregion: Recipe Revision Types
+
+// region: RecipeRevisionMac
+pub struct RecipeRevisionMac;
+
+impl RecipeRevisionMac {
+    /// Snapshot the current state of the recipe as its next revision.
+    pub async fn record(
+        db: &Db,
+        utx: &UserCtx,
+        recipe_id: i64,
+    ) -> Result<RecipeRevision, model::Error> {
+        let _timer = metrics::mac_timer("RecipeRevisionMac::record");
+        let (recipe, ingredients) = RecipeMac::get(db, utx, recipe_id).await?;
+        insert_snapshot(db, &recipe, &ingredients, utx.user_id).await
+    }
+
+    /// Recipes created before revision history (e.g., the seed) have no revision yet.
+    /// Their current state gets recorded as the first revision, credited to the recipe creator.
+    pub async fn record_baseline(
+        db: &Db,
+        utx: &UserCtx,
+        recipe_id: i64,
+    ) -> Result<(), model::Error> {
+        let _timer = metrics::mac_timer("RecipeRevisionMac::record_baseline");
+        if next_rev(db, recipe_id).await? > 1 {
+            return Ok(());
+        }
+
+        let (recipe, ingredients) = RecipeMac::get(db, utx, recipe_id).await?;
+        insert_snapshot(db, &recipe, &ingredients, recipe.cid.unwrap_or(0)).await?;
+
+        Ok(())
+    }
+
+    pub async fn list(
+        db: &Db,
+        _utx: &UserCtx,
+        recipe_id: i64,
+    ) -> Result<Vec<RecipeRevision>, model::Error> {
+        let _timer = metrics::mac_timer("RecipeRevisionMac::list");
+        let sql = "SELECT * FROM recipe_revisions WHERE recipe_id = ? ORDER BY rev DESC";
+
+        let revisions = sqlx::query_as::<_, RecipeRevision>(sql)
+            .bind(recipe_id)
+            .fetch_all(db)
+            .await?;
+
+        Ok(revisions)
+    }
+
+    pub async fn get(
+        db: &Db,
+        _utx: &UserCtx,
+        recipe_id: i64,
+        rev: i64,
+    ) -> Result<RecipeRevision, model::Error> {
+        let _timer = metrics::mac_timer("RecipeRevisionMac::get");
+        let sql = "SELECT * FROM recipe_revisions WHERE recipe_id = ? AND rev = ?";
+
+        sqlx::query_as::<_, RecipeRevision>(sql)
+            .bind(recipe_id)
+            .bind(rev)
+            .fetch_one(db)
+            .await
+            .map_err(|sqlx_error| match sqlx_error {
+                sqlx::Error::RowNotFound => model::Error::EntityNotFound(
+                    "recipe_revisions",
+                    format!("{}@{}", recipe_id, rev),
+                ),
+                other => model::Error::SqlxError(other),
+            })
+    }
+
+    pub async fn diff(
+        db: &Db,
+        utx: &UserCtx,
+        recipe_id: i64,
+        from_rev: i64,
+        to_rev: i64,
+    ) -> Result<RecipeRevisionDiff, model::Error> {
+        let _timer = metrics::mac_timer("RecipeRevisionMac::diff");
+        let from = Self::get(db, utx, recipe_id, from_rev).await?;
+        let to = Self::get(db, utx, recipe_id, to_rev).await?;
+
+        Ok(RecipeRevisionDiff {
+            recipe_id,
+            from_rev,
+            to_rev,
+            changes: diff_revisions(&from, &to),
+        })
+    }
+
+    /// Restore an old revision by applying it as a regular update, which records it as a new revision.
+    pub async fn restore(
+        db: &Db,
+        utx: &UserCtx,
+        recipe_id: i64,
+        rev: i64,
+    ) -> Result<(Recipe, Vec<RecipeIngredientMac>), model::Error> {
+        let _timer = metrics::mac_timer("RecipeRevisionMac::restore");
+        let revision = Self::get(db, utx, recipe_id, rev).await?;
+
+        let data = RecipePatch {
+            recipe_patch: RecipePatchInner {
+                title: PatchValue::Value(revision.title),
+                cid: revision.recipe_cid.into(),
+            },
+            ingredients: PatchValue::Value(revision.ingredients.0),
+        };
+
+        RecipeMac::update(db, utx, recipe_id, data).await
+    }
+}
+// endregion: RecipeRevisionMac
+
+// region: Utils
+async fn next_rev(db: &Db, recipe_id: i64) -> Result<i64, model::Error> {
+    let sql = "SELECT CAST(COALESCE(MAX(rev), 0) + 1 AS SIGNED INTEGER) FROM recipe_revisions WHERE recipe_id = ?";
+    let (rev,) = sqlx::query_as::<_, (i64,)>(sql)
+        .bind(recipe_id)
+        .fetch_one(db)
+        .await?;
+    Ok(rev)
+}
+
+async fn insert_snapshot(
+    db: &Db,
+    recipe: &Recipe,
+    ingredients: &[RecipeIngredientMac],
+    cid: i64,
+) -> Result<RecipeRevision, model::Error> {
+    let rev = next_rev(db, recipe.id).await?;
+    let ingredients: Vec<RecipeIngredientPatch> = ingredients
+        .iter()
+        .map(|i| RecipeIngredientPatch {
+            ingredient_id: i.ingredient_id,
+            ingredient_name: i.ingredient_name.clone(),
+            quantity: i.quantity.clone(),
+        })
+        .collect();
+
+    let sql_insert = "INSERT INTO recipe_revisions (recipe_id, rev, title, recipe_cid, ingredients, cid) VALUES (?, ?, ?, ?, ?, ?)";
+    let result = sqlx::query(sql_insert)
+        .bind(recipe.id)
+        .bind(rev)
+        .bind(&recipe.title)
+        .bind(recipe.cid)
+        .bind(Json(ingredients))
+        .bind(cid)
+        .execute(db)
+        .await?;
+
+    let sql_select = "SELECT * FROM recipe_revisions WHERE id = ?";
+    let revision = sqlx::query_as::<_, RecipeRevision>(sql_select)
+        .bind(db::last_insert_id(&result))
+        .fetch_one(db)
+        .await?;
+
+    Ok(revision)
+}
+
+fn diff_revisions(from: &RecipeRevision, to: &RecipeRevision) -> Vec<RecipeFieldChange> {
+    let mut changes = Vec::new();
+
+    if from.title != to.title {
+        changes.push(field_change("title", json!(from.title), json!(to.title)));
+    }
+    if from.recipe_cid != to.recipe_cid {
+        changes.push(field_change(
+            "cid",
+            json!(from.recipe_cid),
+            json!(to.recipe_cid),
+        ));
+    }
+
+    // ingredients are matched by ingredient_id
+    let from_ings: BTreeMap<i64, &RecipeIngredientPatch> = from
+        .ingredients
+        .iter()
+        .map(|i| (i.ingredient_id, i))
+        .collect();
+    let to_ings: BTreeMap<i64, &RecipeIngredientPatch> = to
+        .ingredients
+        .iter()
+        .map(|i| (i.ingredient_id, i))
+        .collect();
+
+    let mut ids: Vec<i64> = from_ings.keys().chain(to_ings.keys()).copied().collect();
+    ids.sort_unstable();
+    ids.dedup();
+
+    for id in ids {
+        let field = format!("ingredients[{}]", id);
+        match (from_ings.get(&id), to_ings.get(&id)) {
+            (Some(f), Some(t)) => {
+                if f.ingredient_name != t.ingredient_name {
+                    changes.push(field_change(
+                        &format!("{}.ingredient_name", field),
+                        json!(f.ingredient_name),
+                        json!(t.ingredient_name),
+                    ));
+                }
+                if f.quantity != t.quantity {
+                    changes.push(field_change(
+                        &format!("{}.quantity", field),
+                        json!(f.quantity),
+                        json!(t.quantity),
+                    ));
+                }
+            }
+            (Some(f), None) => changes.push(field_change(&field, json!(f), Value::Null)),
+            (None, Some(t)) => changes.push(field_change(&field, Value::Null, json!(t))),
+            (None, None) => (),
+        }
+    }
+
+    changes
+}
+
+fn field_change(field: &str, from: Value, to: Value) -> RecipeFieldChange {
+    RecipeFieldChange {
+        field: field.to_string(),
+        from,
+        to,
+    }
+}
+// endregion: Utils
+
+#[cfg(test)]
+#[path = "../_tests/model_recipe_revision.rs"]
+mod tests;
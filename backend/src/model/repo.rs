@@ -0,0 +1,231 @@
+use std::sync::Arc;
+
+use async_trait::async_trait;
+
+use super::db::Db;
+use super::ingredient::{Ingredient, IngredientMac, IngredientPatch};
+use super::recipe::{Recipe, RecipeMac, RecipePatch, RecipeSort};
+use super::recipe_ingredient::RecipeIngredientMac;
+use crate::{model, security::UserCtx};
+
+// region: Traits
+/// Ingredients store of the web layer, `DbRepo` in production, `MemRepo` for tests without a db.
+#[async_trait]
+pub trait IngredientRepo: Send + Sync {
+    async fn list(&self, utx: &UserCtx) -> Result<Vec<Ingredient>, model::Error>;
+
+    async fn get(&self, utx: &UserCtx, id: i64) -> Result<Ingredient, model::Error>;
+
+    async fn create(
+        &self,
+        utx: &UserCtx,
+        data: IngredientPatch,
+    ) -> Result<Ingredient, model::Error>;
+
+    async fn update(
+        &self,
+        utx: &UserCtx,
+        id: i64,
+        data: IngredientPatch,
+    ) -> Result<Ingredient, model::Error>;
+
+    /// Update only if the ingredient is still at `version`, otherwise fail with `VersionMismatch`.
+    async fn update_if_match(
+        &self,
+        utx: &UserCtx,
+        id: i64,
+        version: i64,
+        data: IngredientPatch,
+    ) -> Result<Ingredient, model::Error>;
+
+    async fn delete(&self, utx: &UserCtx, id: i64) -> Result<Ingredient, model::Error>;
+
+    /// Delete only if the ingredient is still at `version`, otherwise fail with `VersionMismatch`.
+    async fn delete_if_match(
+        &self,
+        utx: &UserCtx,
+        id: i64,
+        version: i64,
+    ) -> Result<Ingredient, model::Error>;
+}
+
+/// Recipes store of the web layer, `DbRepo` in production, `MemRepo` for tests without a db.
+#[async_trait]
+pub trait RecipeRepo: Send + Sync {
+    async fn list_sorted(
+        &self,
+        utx: &UserCtx,
+        sort: RecipeSort,
+    ) -> Result<Vec<(Recipe, Vec<RecipeIngredientMac>)>, model::Error>;
+
+    async fn get(
+        &self,
+        utx: &UserCtx,
+        id: i64,
+    ) -> Result<(Recipe, Vec<RecipeIngredientMac>), model::Error>;
+
+    async fn create(
+        &self,
+        utx: &UserCtx,
+        data: RecipePatch,
+    ) -> Result<(Recipe, Vec<RecipeIngredientMac>), model::Error>;
+
+    async fn update(
+        &self,
+        utx: &UserCtx,
+        id: i64,
+        data: RecipePatch,
+    ) -> Result<(Recipe, Vec<RecipeIngredientMac>), model::Error>;
+
+    /// Update only if the recipe is still at `version`, otherwise fail with `VersionMismatch`.
+    async fn update_if_match(
+        &self,
+        utx: &UserCtx,
+        id: i64,
+        version: i64,
+        data: RecipePatch,
+    ) -> Result<(Recipe, Vec<RecipeIngredientMac>), model::Error>;
+
+    async fn delete(
+        &self,
+        utx: &UserCtx,
+        id: i64,
+    ) -> Result<(Recipe, Vec<RecipeIngredientMac>), model::Error>;
+
+    /// Delete only if the recipe is still at `version`, otherwise fail with `VersionMismatch`.
+    async fn delete_if_match(
+        &self,
+        utx: &UserCtx,
+        id: i64,
+        version: i64,
+    ) -> Result<(Recipe, Vec<RecipeIngredientMac>), model::Error>;
+}
+// endregion: Traits
+
+// region: DbRepo
+/// The repositories over the sql db, delegating to the `*Mac`.
+#[derive(Clone)]
+pub struct DbRepo {
+    db: Arc<Db>,
+}
+
+impl DbRepo {
+    pub fn new(db: Arc<Db>) -> Self {
+        DbRepo { db }
+    }
+}
+
+#[async_trait]
+impl IngredientRepo for DbRepo {
+    async fn list(&self, utx: &UserCtx) -> Result<Vec<Ingredient>, model::Error> {
+        IngredientMac::list(&self.db, utx).await
+    }
+
+    async fn get(&self, utx: &UserCtx, id: i64) -> Result<Ingredient, model::Error> {
+        IngredientMac::get(&self.db, utx, id).await
+    }
+
+    async fn create(
+        &self,
+        utx: &UserCtx,
+        data: IngredientPatch,
+    ) -> Result<Ingredient, model::Error> {
+        IngredientMac::create(&self.db, utx, data).await
+    }
+
+    async fn update(
+        &self,
+        utx: &UserCtx,
+        id: i64,
+        data: IngredientPatch,
+    ) -> Result<Ingredient, model::Error> {
+        IngredientMac::update(&self.db, utx, id, data).await
+    }
+
+    async fn update_if_match(
+        &self,
+        utx: &UserCtx,
+        id: i64,
+        version: i64,
+        data: IngredientPatch,
+    ) -> Result<Ingredient, model::Error> {
+        IngredientMac::update_if_match(&self.db, utx, id, version, data).await
+    }
+
+    async fn delete(&self, utx: &UserCtx, id: i64) -> Result<Ingredient, model::Error> {
+        IngredientMac::delete(&self.db, utx, id).await
+    }
+
+    async fn delete_if_match(
+        &self,
+        utx: &UserCtx,
+        id: i64,
+        version: i64,
+    ) -> Result<Ingredient, model::Error> {
+        IngredientMac::delete_if_match(&self.db, utx, id, version).await
+    }
+}
+
+#[async_trait]
+impl RecipeRepo for DbRepo {
+    async fn list_sorted(
+        &self,
+        utx: &UserCtx,
+        sort: RecipeSort,
+    ) -> Result<Vec<(Recipe, Vec<RecipeIngredientMac>)>, model::Error> {
+        RecipeMac::list_sorted(&self.db, utx, sort).await
+    }
+
+    async fn get(
+        &self,
+        utx: &UserCtx,
+        id: i64,
+    ) -> Result<(Recipe, Vec<RecipeIngredientMac>), model::Error> {
+        RecipeMac::get(&self.db, utx, id).await
+    }
+
+    async fn create(
+        &self,
+        utx: &UserCtx,
+        data: RecipePatch,
+    ) -> Result<(Recipe, Vec<RecipeIngredientMac>), model::Error> {
+        RecipeMac::create(&self.db, utx, data).await
+    }
+
+    async fn update(
+        &self,
+        utx: &UserCtx,
+        id: i64,
+        data: RecipePatch,
+    ) -> Result<(Recipe, Vec<RecipeIngredientMac>), model::Error> {
+        RecipeMac::update(&self.db, utx, id, data).await
+    }
+
+    async fn update_if_match(
+        &self,
+        utx: &UserCtx,
+        id: i64,
+        version: i64,
+        data: RecipePatch,
+    ) -> Result<(Recipe, Vec<RecipeIngredientMac>), model::Error> {
+        RecipeMac::update_if_match(&self.db, utx, id, version, data).await
+    }
+
+    async fn delete(
+        &self,
+        utx: &UserCtx,
+        id: i64,
+    ) -> Result<(Recipe, Vec<RecipeIngredientMac>), model::Error> {
+        RecipeMac::delete(&self.db, utx, id).await
+    }
+
+    async fn delete_if_match(
+        &self,
+        utx: &UserCtx,
+        id: i64,
+        version: i64,
+    ) -> Result<(Recipe, Vec<RecipeIngredientMac>), model::Error> {
+        RecipeMac::delete_if_match(&self.db, utx, id, version).await
+    }
+}
+// endregion: DbRepo
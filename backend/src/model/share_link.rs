@@ -0,0 +1,260 @@
+use crate::{
+    metrics,
+    model::{
+        self,
+        db::{self, Db},
+    },
+    security::UserCtx,
+};
+use chrono::{DateTime, Utc};
+use rand::{distributions::Alphanumeric, Rng};
+use serde::{Deserialize, Serialize};
+
+use super::collection::CollectionMac;
+use super::recipe::{Recipe, RecipeMac};
+use super::recipe_ingredient::RecipeIngredientMac;
+use super::validate::{Validate, Validator};
+
+/// ~256 bits of randomness with the 62 alphanumeric characters.
+const TOKEN_LEN: usize = 43;
+
+// region: Share Link Types
+#[derive(sqlx::FromRow, Debug, Clone, Serialize, Deserialize)]
+pub struct ShareLink {
+    pub id: i64,
+    pub token: String,
+    /// "recipe" or "collection"
+    pub typ: String,
+    pub target_id: i64,
+    /// owner of the link
+    pub cid: i64,
+    pub expires_at: Option<DateTime<Utc>>,
+    pub revoked_at: Option<DateTime<Utc>>,
+    pub view_count: i64,
+    pub ctime: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ShareTyp {
+    Recipe,
+    Collection,
+}
+
+impl ShareTyp {
+    fn as_str(&self) -> &'static str {
+        match self {
+            ShareTyp::Recipe => "recipe",
+            ShareTyp::Collection => "collection",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ShareLinkPatch {
+    pub typ: ShareTyp,
+    pub target_id: i64,
+    /// no expiry when absent
+    #[serde(default)]
+    pub expires_at: Option<DateTime<Utc>>,
+}
+
+impl Validate for ShareLinkPatch {
+    fn check(&mut self, v: &mut Validator) {
+        v.id("target_id", self.target_id);
+        if matches!(self.expires_at, Some(expires_at) if expires_at <= Utc::now()) {
+            v.error("expires_at", "must be in the future");
+        }
+    }
+}
+
+/// What a visitor of a share link gets. Owner-only data (`cid`, favorites, versions) is left out.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "typ", rename_all = "snake_case")]
+pub enum SharedView {
+    Recipe(SharedRecipe),
+    Collection(SharedCollection),
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SharedRecipe {
+    pub title: String,
+    pub ingredients: Vec<SharedIngredient>,
+    pub rating_avg: Option<f64>,
+    pub rating_count: i64,
+    pub ctime: DateTime<Utc>,
+    pub mtime: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SharedIngredient {
+    pub ingredient_name: String,
+    pub quantity: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SharedCollection {
+    pub name: String,
+    pub description: Option<String>,
+    pub recipes: Vec<SharedRecipe>,
+}
+// endregion: Share Link Types
+
+// region: ShareLinkMac
+pub struct ShareLinkMac;
+
+impl ShareLinkMac {
+    pub async fn create(
+        db: &Db,
+        utx: &UserCtx,
+        mut data: ShareLinkPatch,
+    ) -> Result<ShareLink, model::Error> {
+        let _timer = metrics::mac_timer("ShareLinkMac::create");
+        data.validate()?;
+
+        // Only what the user can see can be shared
+        match data.typ {
+            ShareTyp::Recipe => {
+                RecipeMac::get(db, utx, data.target_id).await?;
+            }
+            ShareTyp::Collection => {
+                CollectionMac::get(db, utx, data.target_id).await?;
+            }
+        }
+
+        let token: String = rand::thread_rng()
+            .sample_iter(&Alphanumeric)
+            .take(TOKEN_LEN)
+            .map(char::from)
+            .collect();
+
+        let sql_insert =
+            "INSERT INTO share_links (token, typ, target_id, cid, expires_at) VALUES (?, ?, ?, ?, ?)";
+        let result = sqlx::query(sql_insert)
+            .bind(token)
+            .bind(data.typ.as_str())
+            .bind(data.target_id)
+            .bind(utx.user_id)
+            .bind(data.expires_at)
+            .execute(db)
+            .await?;
+
+        get_owned(db, utx, db::last_insert_id(&result)).await
+    }
+
+    /// Share links of the current user, with their view counts.
+    pub async fn list(db: &Db, utx: &UserCtx) -> Result<Vec<ShareLink>, model::Error> {
+        let _timer = metrics::mac_timer("ShareLinkMac::list");
+        let sql = "SELECT * FROM share_links WHERE cid = ? ORDER BY id DESC";
+        let links = sqlx::query_as::<_, ShareLink>(sql)
+            .bind(utx.user_id)
+            .fetch_all(db)
+            .await?;
+
+        Ok(links)
+    }
+
+    /// Revoked links are kept (with their view count), they just stop resolving.
+    pub async fn revoke(db: &Db, utx: &UserCtx, id: i64) -> Result<ShareLink, model::Error> {
+        let _timer = metrics::mac_timer("ShareLinkMac::revoke");
+        get_owned(db, utx, id).await?;
+
+        let sql_revoke =
+            "UPDATE share_links SET revoked_at = ? WHERE id = ? AND cid = ? AND revoked_at IS NULL";
+        sqlx::query(sql_revoke)
+            .bind(Utc::now())
+            .bind(id)
+            .bind(utx.user_id)
+            .execute(db)
+            .await?;
+
+        get_owned(db, utx, id).await
+    }
+
+    /// Resolve a token for an unauthenticated visitor, counting the view.
+    /// Unknown, expired and revoked tokens all fail the same way.
+    pub async fn view(db: &Db, token: &str) -> Result<SharedView, model::Error> {
+        let _timer = metrics::mac_timer("ShareLinkMac::view");
+        let sql_view = "UPDATE share_links SET view_count = view_count + 1
+            WHERE token = ? AND revoked_at IS NULL AND (expires_at IS NULL OR expires_at > ?)";
+        let result = sqlx::query(sql_view)
+            .bind(token)
+            .bind(Utc::now())
+            .execute(db)
+            .await?;
+        if result.rows_affected() == 0 {
+            return Err(model::Error::EntityNotFound(
+                "share_links",
+                "token".to_string(),
+            ));
+        }
+
+        let sql = "SELECT * FROM share_links WHERE token = ?";
+        let link = sqlx::query_as::<_, ShareLink>(sql)
+            .bind(token)
+            .fetch_one(db)
+            .await?;
+
+        // Read on behalf of the owner, since collections are personal
+        let owner = UserCtx {
+            user_id: link.cid,
+            is_admin: false,
+            request_id: None,
+        };
+        let shared = if link.typ == ShareTyp::Recipe.as_str() {
+            let (recipe, ingredients) = RecipeMac::get(db, &owner, link.target_id).await?;
+            SharedView::Recipe(shared_recipe(recipe, ingredients))
+        } else {
+            let (collection, items) = CollectionMac::get(db, &owner, link.target_id).await?;
+            let mut recipes = Vec::new();
+            for item in items {
+                let (recipe, ingredients) = RecipeMac::get(db, &owner, item.recipe_id).await?;
+                recipes.push(shared_recipe(recipe, ingredients));
+            }
+            SharedView::Collection(SharedCollection {
+                name: collection.name,
+                description: collection.description,
+                recipes,
+            })
+        };
+
+        Ok(shared)
+    }
+}
+// endregion: ShareLinkMac
+
+// region: Utils
+async fn get_owned(db: &Db, utx: &UserCtx, id: i64) -> Result<ShareLink, model::Error> {
+    let sql = "SELECT * FROM share_links WHERE id = ? AND cid = ?";
+    sqlx::query_as::<_, ShareLink>(sql)
+        .bind(id)
+        .bind(utx.user_id)
+        .fetch_one(db)
+        .await
+        .map_err(|sqlx_error| match sqlx_error {
+            sqlx::Error::RowNotFound => model::Error::EntityNotFound("share_links", id.to_string()),
+            other => model::Error::SqlxError(other),
+        })
+}
+
+fn shared_recipe(recipe: Recipe, ingredients: Vec<RecipeIngredientMac>) -> SharedRecipe {
+    SharedRecipe {
+        title: recipe.title,
+        ingredients: ingredients
+            .into_iter()
+            .map(|i| SharedIngredient {
+                ingredient_name: i.ingredient_name,
+                quantity: i.quantity,
+            })
+            .collect(),
+        rating_avg: recipe.rating_avg,
+        rating_count: recipe.rating_count,
+        ctime: recipe.ctime,
+        mtime: recipe.mtime,
+    }
+}
+// endregion: Utils
+
+#[cfg(test)]
+#[path = "../_tests/model_share_link.rs"]
+mod tests;
@@ -0,0 +1,183 @@
+use chrono::{DateTime, Utc};
+use sqlx::query::{Query, QueryAs};
+
+use super::db::{Db, DbArguments, DbDriver, DbQueryResult, DbRow};
+use super::patch::PatchValue;
+use crate::model;
+
+// region: SqlVal
+/// A value to bind, kept as an enum so the builder can hold heterogeneous columns.
+pub enum SqlVal {
+    Null,
+    I64(i64),
+    Str(String),
+    DateTime(DateTime<Utc>),
+}
+
+impl SqlVal {
+    fn bind<'q>(
+        self,
+        query: Query<'q, DbDriver, DbArguments<'q>>,
+    ) -> Query<'q, DbDriver, DbArguments<'q>> {
+        match self {
+            SqlVal::Null => query.bind(None::<String>),
+            SqlVal::I64(v) => query.bind(v),
+            SqlVal::Str(v) => query.bind(v),
+            SqlVal::DateTime(v) => query.bind(v),
+        }
+    }
+
+    /// Same as `bind`, for a SELECT mapped with `query_as`.
+    pub fn bind_as<'q, O>(
+        self,
+        query: QueryAs<'q, DbDriver, O, DbArguments<'q>>,
+    ) -> QueryAs<'q, DbDriver, O, DbArguments<'q>>
+    where
+        O: for<'r> sqlx::FromRow<'r, DbRow>,
+    {
+        match self {
+            SqlVal::Null => query.bind(None::<String>),
+            SqlVal::I64(v) => query.bind(v),
+            SqlVal::Str(v) => query.bind(v),
+            SqlVal::DateTime(v) => query.bind(v),
+        }
+    }
+}
+
+impl From<i64> for SqlVal {
+    fn from(v: i64) -> Self {
+        SqlVal::I64(v)
+    }
+}
+
+impl From<String> for SqlVal {
+    fn from(v: String) -> Self {
+        SqlVal::Str(v)
+    }
+}
+
+impl From<DateTime<Utc>> for SqlVal {
+    fn from(v: DateTime<Utc>) -> Self {
+        SqlVal::DateTime(v)
+    }
+}
+// endregion: SqlVal
+
+// region: UpdateBuilder
+/// Dynamic UPDATE for partial patches.
+/// Note: `sqlb` 0.0.x only speaks Postgres (`$1` placeholders, `"col"` quoting), hence this small one with `?` placeholders (MySQL and SQLite).
+pub struct UpdateBuilder {
+    table: &'static str,
+    sets: Vec<(&'static str, SqlVal)>,
+    raw_sets: Vec<(&'static str, &'static str)>,
+    wheres: Vec<(&'static str, SqlVal)>,
+    raw_wheres: Vec<&'static str>,
+}
+
+impl UpdateBuilder {
+    pub fn new(table: &'static str) -> Self {
+        UpdateBuilder {
+            table,
+            sets: Vec::new(),
+            raw_sets: Vec::new(),
+            wheres: Vec::new(),
+            raw_wheres: Vec::new(),
+        }
+    }
+
+    /// `column = ?`, always set.
+    pub fn set(mut self, column: &'static str, val: impl Into<SqlVal>) -> Self {
+        self.sets.push((column, val.into()));
+        self
+    }
+
+    /// `column = <sql>`, e.g. `version = version + 1`.
+    pub fn set_raw(mut self, column: &'static str, sql: &'static str) -> Self {
+        self.raw_sets.push((column, sql));
+        self
+    }
+
+    /// Set a NOT NULL column only if the patch carries a value. Explicit `null` is an error.
+    pub fn patch<T: Into<SqlVal>>(
+        self,
+        column: &'static str,
+        val: PatchValue<T>,
+    ) -> Result<Self, model::Error> {
+        match val {
+            PatchValue::Absent => Ok(self),
+            PatchValue::Null => Err(model::Error::PatchNullNotAllowed(self.table, column)),
+            PatchValue::Value(v) => Ok(self.set(column, v)),
+        }
+    }
+
+    /// Set a nullable column if present in the patch, explicit `null` clears it.
+    pub fn patch_nullable<T: Into<SqlVal>>(
+        mut self,
+        column: &'static str,
+        val: PatchValue<T>,
+    ) -> Self {
+        match val {
+            PatchValue::Absent => (),
+            PatchValue::Null => self.sets.push((column, SqlVal::Null)),
+            PatchValue::Value(v) => self.sets.push((column, v.into())),
+        }
+        self
+    }
+
+    /// `AND column = ?`
+    pub fn and_where_eq(mut self, column: &'static str, val: impl Into<SqlVal>) -> Self {
+        self.wheres.push((column, val.into()));
+        self
+    }
+
+    /// `AND <sql>`, e.g. `deleted_at IS NULL`.
+    pub fn and_where_raw(mut self, sql: &'static str) -> Self {
+        self.raw_wheres.push(sql);
+        self
+    }
+
+    pub fn sql(&self) -> String {
+        let sets: Vec<String> = self
+            .sets
+            .iter()
+            .map(|(column, _)| format!("{} = ?", column))
+            .chain(
+                self.raw_sets
+                    .iter()
+                    .map(|(column, sql)| format!("{} = {}", column, sql)),
+            )
+            .collect();
+        let wheres: Vec<String> = self
+            .wheres
+            .iter()
+            .map(|(column, _)| format!("{} = ?", column))
+            .chain(self.raw_wheres.iter().map(|sql| sql.to_string()))
+            .collect();
+
+        // Note: no WHERE would update the whole table, so it is a programming error
+        assert!(!wheres.is_empty(), "UpdateBuilder without a WHERE clause");
+
+        format!(
+            "UPDATE {} SET {} WHERE {}",
+            self.table,
+            sets.join(", "),
+            wheres.join(" AND ")
+        )
+    }
+
+    pub async fn execute(self, db: &Db) -> Result<DbQueryResult, sqlx::Error> {
+        let sql = self.sql();
+
+        let mut query = sqlx::query(&sql);
+        for (_, val) in self.sets.into_iter().chain(self.wheres) {
+            query = val.bind(query);
+        }
+
+        query.execute(db).await
+    }
+}
+// endregion: UpdateBuilder
+
+#[cfg(test)]
+#[path = "../_tests/model_sql_builder.rs"]
+mod tests;
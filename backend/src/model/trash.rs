@@ -0,0 +1,63 @@
+use crate::{
+    metrics,
+    model::{self, db::Db},
+    security::UserCtx,
+};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+// region: Trash Types
+#[derive(sqlx::FromRow, Debug, Clone, Serialize, Deserialize)]
+pub struct TrashItem {
+    /// "recipe" or "ingredient"
+    pub typ: String,
+    pub id: i64,
+    /// recipe title or ingredient name
+    pub label: String,
+    pub deleted_at: DateTime<Utc>,
+}
+// endregion: Trash Types
+
+// region: TrashMac
+pub struct TrashMac;
+
+impl TrashMac {
+    pub async fn list(db: &Db, _utx: &UserCtx) -> Result<Vec<TrashItem>, model::Error> {
+        let _timer = metrics::mac_timer("TrashMac::list");
+        let sql = "
+            SELECT 'recipe' AS typ, id, title AS label, deleted_at FROM recipes WHERE deleted_at IS NOT NULL
+            UNION ALL
+            SELECT 'ingredient' AS typ, id, name AS label, deleted_at FROM ingredients WHERE deleted_at IS NOT NULL
+            ORDER BY deleted_at DESC, id DESC";
+
+        let items = sqlx::query_as::<_, TrashItem>(sql).fetch_all(db).await?;
+
+        Ok(items)
+    }
+
+    /// Permanently delete everything trashed before `older_than`.
+    /// Returns the number of recipes and ingredients removed.
+    pub async fn purge(db: &Db, older_than: DateTime<Utc>) -> Result<u64, model::Error> {
+        let _timer = metrics::mac_timer("TrashMac::purge");
+        // recipe links and revisions go away with the ON DELETE CASCADE
+        let sql_recipes = "DELETE FROM recipes WHERE deleted_at IS NOT NULL AND deleted_at < ?";
+        let recipes = sqlx::query(sql_recipes)
+            .bind(older_than)
+            .execute(db)
+            .await?;
+
+        let sql_ingredients =
+            "DELETE FROM ingredients WHERE deleted_at IS NOT NULL AND deleted_at < ?";
+        let ingredients = sqlx::query(sql_ingredients)
+            .bind(older_than)
+            .execute(db)
+            .await?;
+
+        Ok(recipes.rows_affected() + ingredients.rows_affected())
+    }
+}
+// endregion: TrashMac
+
+#[cfg(test)]
+#[path = "../_tests/model_trash.rs"]
+mod tests;
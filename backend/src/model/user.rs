@@ -0,0 +1,157 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+use super::db::{self, Db};
+use super::validate::{TextRule, Validate, Validator};
+use crate::{
+    metrics, model,
+    security::{self, UserCtx},
+};
+
+/// Shortest password accepted (in characters).
+const PASSWORD_MIN_LEN: usize = 8;
+
+// region: User Types
+#[derive(sqlx::FromRow, Debug, Clone, Serialize, Deserialize)]
+pub struct User {
+    /// also the X-Auth-Token of the user, until the tokens are real
+    pub id: i64,
+    pub username: String,
+    pub ctime: DateTime<Utc>,
+    pub mtime: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone)]
+pub struct UserPatch {
+    pub username: String,
+    /// stored as an Argon2 hash only
+    pub password: String,
+}
+
+impl Validate for UserPatch {
+    fn check(&mut self, v: &mut Validator) {
+        v.text_value("username", &mut self.username, TextRule::required(64));
+        if self.password.chars().count() < PASSWORD_MIN_LEN {
+            v.error(
+                "password",
+                &format!("must be at least {} characters", PASSWORD_MIN_LEN),
+            );
+        }
+    }
+}
+// endregion: User Types
+
+// region: UserMac
+/// Admin only (e.g. the CLI).
+pub struct UserMac;
+
+impl UserMac {
+    pub async fn create(db: &Db, utx: &UserCtx, mut data: UserPatch) -> Result<User, model::Error> {
+        let _timer = metrics::mac_timer("UserMac::create");
+        check_admin(utx)?;
+        data.validate()?;
+        if Self::find(db, &data.username).await?.is_some() {
+            let mut v = Validator::default();
+            v.error("username", "is already taken");
+            v.finish()?;
+        }
+
+        let password_hash = security::hash_password(&data.password)?;
+        let sql_insert = "INSERT INTO users (username, password_hash) VALUES (?, ?)";
+        let result = sqlx::query(sql_insert)
+            .bind(&data.username)
+            .bind(password_hash)
+            .execute(db)
+            .await?;
+
+        Self::get(db, db::last_insert_id(&result)).await
+    }
+
+    /// Replace the password of `data.username`.
+    pub async fn reset_password(
+        db: &Db,
+        utx: &UserCtx,
+        mut data: UserPatch,
+    ) -> Result<User, model::Error> {
+        let _timer = metrics::mac_timer("UserMac::reset_password");
+        check_admin(utx)?;
+        data.validate()?;
+        let user = Self::find(db, &data.username)
+            .await?
+            .ok_or_else(|| model::Error::EntityNotFound("users", data.username.clone()))?;
+
+        let password_hash = security::hash_password(&data.password)?;
+        let sql_update = "UPDATE users SET password_hash = ?, mtime = ? WHERE id = ?";
+        sqlx::query(sql_update)
+            .bind(password_hash)
+            .bind(Utc::now())
+            .bind(user.id)
+            .execute(db)
+            .await?;
+
+        Self::get(db, user.id).await
+    }
+
+    /// Whether `password` is the one of `username` (false for an unknown user).
+    pub async fn check_password(
+        db: &Db,
+        username: &str,
+        password: &str,
+    ) -> Result<bool, model::Error> {
+        let _timer = metrics::mac_timer("UserMac::check_password");
+        let sql = "SELECT password_hash FROM users WHERE username = ?";
+        let hash = db::retry_read(|| {
+            sqlx::query_as::<_, (String,)>(sql)
+                .bind(username)
+                .fetch_optional(db)
+        })
+        .await?;
+
+        Ok(hash.is_some_and(|(hash,)| security::verify_password(password, &hash)))
+    }
+
+    pub async fn list(db: &Db, utx: &UserCtx) -> Result<Vec<User>, model::Error> {
+        let _timer = metrics::mac_timer("UserMac::list");
+        check_admin(utx)?;
+        let sql = "SELECT id, username, ctime, mtime FROM users ORDER BY id";
+        let users = db::retry_read(|| sqlx::query_as(sql).fetch_all(db)).await?;
+
+        Ok(users)
+    }
+
+    async fn get(db: &Db, id: i64) -> Result<User, model::Error> {
+        let sql = "SELECT id, username, ctime, mtime FROM users WHERE id = ?";
+        sqlx::query_as::<_, User>(sql)
+            .bind(id)
+            .fetch_one(db)
+            .await
+            .map_err(|sqlx_error| match sqlx_error {
+                sqlx::Error::RowNotFound => model::Error::EntityNotFound("users", id.to_string()),
+                other => model::Error::SqlxError(other),
+            })
+    }
+
+    async fn find(db: &Db, username: &str) -> Result<Option<User>, model::Error> {
+        let sql = "SELECT id, username, ctime, mtime FROM users WHERE username = ?";
+        let user = sqlx::query_as::<_, User>(sql)
+            .bind(username)
+            .fetch_optional(db)
+            .await?;
+
+        Ok(user)
+    }
+}
+// endregion: UserMac
+
+// region: Utils
+fn check_admin(utx: &UserCtx) -> Result<(), model::Error> {
+    match utx.is_admin {
+        true => Ok(()),
+        false => Err(model::Error::AccessDenied("users", utx.user_id.to_string())),
+    }
+}
+// endregion: Utils
+
+#[cfg(test)]
+#[path = "../_tests/model_user.rs"]
+mod tests;
@@ -0,0 +1,157 @@
+use serde::{Deserialize, Serialize};
+use unicode_normalization::UnicodeNormalization;
+
+use super::patch::PatchValue;
+use crate::model;
+
+// region: Validation Types
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct FieldError {
+    /// path of the property in the request body, e.g. "recipe_patch.title" or "ingredients[0].quantity"
+    pub field: String,
+    pub reason: String,
+}
+
+/// Rule for a text property. Lengths are in characters, after normalization.
+#[derive(Debug, Clone, Copy)]
+pub struct TextRule {
+    pub min_len: usize,
+    pub max_len: usize,
+}
+
+impl TextRule {
+    /// Non-blank text of at most `max_len` characters.
+    pub const fn required(max_len: usize) -> TextRule {
+        TextRule {
+            min_len: 1,
+            max_len,
+        }
+    }
+
+    /// Possibly empty text of at most `max_len` characters.
+    pub const fn optional(max_len: usize) -> TextRule {
+        TextRule {
+            min_len: 0,
+            max_len,
+        }
+    }
+}
+// endregion: Validation Types
+
+// region: Validate
+/// Patch types declare their rules in `check`. `validate` normalizes them in place and
+/// fails with every invalid field at once.
+pub trait Validate {
+    fn check(&mut self, v: &mut Validator);
+
+    fn validate(&mut self) -> Result<(), model::Error> {
+        let mut v = Validator::default();
+        self.check(&mut v);
+        v.finish()
+    }
+}
+
+#[derive(Default)]
+pub struct Validator {
+    path: Vec<String>,
+    errors: Vec<FieldError>,
+}
+
+impl Validator {
+    /// Trim and NFC-normalize a patch text property, then check it against `rule`.
+    pub fn text(&mut self, field: &str, val: &mut PatchValue<String>, rule: TextRule) -> &mut Self {
+        match val {
+            PatchValue::Absent => (),
+            PatchValue::Null => self.error(field, "must not be null"),
+            PatchValue::Value(text) => {
+                self.text_value(field, text, rule);
+            }
+        }
+        self
+    }
+
+    /// Same as `text` for nullable columns, where `null` clears the value.
+    pub fn text_nullable(
+        &mut self,
+        field: &str,
+        val: &mut PatchValue<String>,
+        rule: TextRule,
+    ) -> &mut Self {
+        if let PatchValue::Value(text) = val {
+            self.text_value(field, text, rule);
+        }
+        self
+    }
+
+    /// Same as `text` for properties that are always present.
+    pub fn text_value(&mut self, field: &str, text: &mut String, rule: TextRule) -> &mut Self {
+        *text = normalize_text(text);
+
+        let len = text.chars().count();
+        if len < rule.min_len {
+            self.error(field, "must not be empty");
+        } else if len > rule.max_len {
+            self.error(
+                field,
+                &format!("must be at most {} characters", rule.max_len),
+            );
+        }
+        self
+    }
+
+    /// Ids are generated by the database, so they are always positive.
+    pub fn id(&mut self, field: &str, id: i64) -> &mut Self {
+        if id <= 0 {
+            self.error(field, "must be a positive id");
+        }
+        self
+    }
+
+    /// Inclusive range check for numbers, e.g. a 1 to 5 rating.
+    pub fn range(&mut self, field: &str, val: i64, min: i64, max: i64) -> &mut Self {
+        if val < min || val > max {
+            self.error(field, &format!("must be between {} and {}", min, max));
+        }
+        self
+    }
+
+    /// Run `f` with `prefix` prepended to the field paths, e.g. "recipe_patch" or "ingredients[0]".
+    pub fn nested(&mut self, prefix: &str, f: impl FnOnce(&mut Validator)) -> &mut Self {
+        self.path.push(prefix.to_string());
+        f(self);
+        self.path.pop();
+        self
+    }
+
+    pub fn error(&mut self, field: &str, reason: &str) {
+        let mut path = self.path.join(".");
+        if !path.is_empty() {
+            path.push('.');
+        }
+        path.push_str(field);
+
+        self.errors.push(FieldError {
+            field: path,
+            reason: reason.to_string(),
+        });
+    }
+
+    pub fn finish(self) -> Result<(), model::Error> {
+        if self.errors.is_empty() {
+            Ok(())
+        } else {
+            Err(model::Error::ValidationFailed(self.errors))
+        }
+    }
+}
+// endregion: Validate
+
+// region: Utils
+fn normalize_text(text: &str) -> String {
+    text.trim().nfc().collect()
+}
+// endregion: Utils
+
+#[cfg(test)]
+#[path = "../_tests/model_validate.rs"]
+mod tests;
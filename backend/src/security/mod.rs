@@ -1,22 +1,82 @@
+use argon2::password_hash::{PasswordHash, PasswordHasher, PasswordVerifier, SaltString};
+use argon2::Argon2;
 use thiserror::Error as ThisError;
 
-use crate::model::Db;
+use std::sync::OnceLock;
 
+use crate::config::Config;
+
+/// Leading chars of an invalid token kept in the error message
+const TOKEN_VISIBLE_CHARS: usize = 2;
+
+static ADMIN_USER_IDS: OnceLock<Vec<i64>> = OnceLock::new();
+
+#[derive(Clone)]
 pub struct UserCtx {
     pub user_id: i64,
+    /// listed in ADMIN_USER_IDS
+    pub is_admin: bool,
+    /// id of the web request being served (set by do_auth), recorded in the audit log
+    pub request_id: Option<String>,
 }
 
-pub async fn utx_from_token(_db: &Db, token: &str) -> Result<UserCtx, Error> {
+pub async fn utx_from_token(token: &str) -> Result<UserCtx, Error> {
     // TODO: real validation needed
     // for now, just parse to i64
     match token.parse::<i64>() {
-        Ok(user_id) => Ok(UserCtx { user_id }),
-        Err(_) => Err(Error::InvalidToken(token.to_string())),
+        Ok(user_id) => Ok(UserCtx {
+            user_id,
+            is_admin: admin_user_ids().contains(&user_id),
+            request_id: None,
+        }),
+        Err(_) => Err(Error::InvalidToken(redact(token))),
+    }
+}
+
+/// The admin CLI, acting as user 0 (as the dev seed), recorded in the changes and audit log.
+pub fn utx_system() -> UserCtx {
+    UserCtx {
+        user_id: 0,
+        is_admin: true,
+        request_id: None,
     }
 }
 
+/// Argon2 hash of a password, as a PHC string (salt and parameters included).
+pub fn hash_password(password: &str) -> Result<String, Error> {
+    let salt = SaltString::generate(&mut rand::rngs::OsRng);
+    Argon2::default()
+        .hash_password(password.as_bytes(), &salt)
+        .map(|hash| hash.to_string())
+        .map_err(|ex| Error::FailHashPassword(ex.to_string()))
+}
+
+/// Whether `password` matches a hash made by `hash_password`.
+pub fn verify_password(password: &str, hash: &str) -> bool {
+    PasswordHash::new(hash)
+        .map(|hash| {
+            Argon2::default()
+                .verify_password(password.as_bytes(), &hash)
+                .is_ok()
+        })
+        .unwrap_or(false)
+}
+
+/// Keep only the start of a token, so it can show in errors and logs without leaking it.
+fn redact(token: &str) -> String {
+    let start: String = token.chars().take(TOKEN_VISIBLE_CHARS).collect();
+    format!("{}***", start)
+}
+
+fn admin_user_ids() -> &'static [i64] {
+    ADMIN_USER_IDS.get_or_init(|| Config::from_env().admin_user_ids)
+}
+
 #[derive(ThisError, Debug)]
 pub enum Error {
     #[error("Invalud Token {0}")]
     InvalidToken(String),
+
+    #[error("Fail to hash the password. Cause: {0}")]
+    FailHashPassword(String),
 }
@@ -0,0 +1,72 @@
+use std::sync::Arc;
+use tokio::sync::watch;
+use tracing::info;
+
+/// Shutdown request shared by the server, the WebSocket sessions and the background tasks.
+/// Cheap to clone, every clone sees the same trigger.
+#[derive(Clone)]
+pub struct Shutdown {
+    tx: Arc<watch::Sender<bool>>,
+    rx: watch::Receiver<bool>,
+}
+
+impl Shutdown {
+    pub fn new() -> Shutdown {
+        let (tx, rx) = watch::channel(false);
+        Shutdown {
+            tx: Arc::new(tx),
+            rx,
+        }
+    }
+
+    pub fn trigger(&self) {
+        self.tx.send_replace(true);
+    }
+
+    pub fn is_triggered(&self) -> bool {
+        *self.rx.borrow()
+    }
+
+    /// Resolve once triggered (right away when it already was).
+    pub async fn wait(&self) {
+        let mut rx = self.rx.clone();
+        // the sender lives as long as self, so it cannot be dropped here
+        let _ = rx.wait_for(|triggered| *triggered).await;
+    }
+}
+
+impl Default for Shutdown {
+    fn default() -> Self {
+        Shutdown::new()
+    }
+}
+
+/// Trigger the shutdown on SIGINT (Ctrl-C) or SIGTERM (redeploy).
+pub async fn trigger_on_signal(shutdown: Shutdown) {
+    let ctrl_c = async {
+        tokio::signal::ctrl_c()
+            .await
+            .expect("Cannot listen to SIGINT");
+    };
+
+    #[cfg(unix)]
+    let terminate = async {
+        tokio::signal::unix::signal(tokio::signal::unix::SignalKind::terminate())
+            .expect("Cannot listen to SIGTERM")
+            .recv()
+            .await;
+    };
+    #[cfg(not(unix))]
+    let terminate = std::future::pending::<()>();
+
+    let signal = tokio::select! {
+        _ = ctrl_c => "SIGINT",
+        _ = terminate => "SIGTERM",
+    };
+    info!(signal, "shutdown requested");
+    shutdown.trigger();
+}
+
+#[cfg(test)]
+#[path = "_tests/shutdown.rs"]
+mod tests;
@@ -0,0 +1,86 @@
+use thiserror::Error as ThisError;
+use tracing_subscriber::{layer::SubscriberExt, util::SubscriberInitExt, EnvFilter, Layer};
+
+use crate::config::Config;
+
+/// Used when RUST_LOG is not set. The SQL statements (with their latency) show up at debug.
+const DEFAULT_LOG_FILTER: &str = "info";
+
+/// Install the global subscriber: leveled logs (pretty or JSON) filtered by RUST_LOG,
+/// plus the OTLP export of the spans when built with the "otel" feature and OTEL_EXPORTER_OTLP_ENDPOINT is set.
+///
+/// To try the export against a local collector:
+/// `docker run -p 4317:4317 otel/opentelemetry-collector` then
+/// `OTEL_EXPORTER_OTLP_ENDPOINT=http://localhost:4317 cargo run --features otel -- serve ../frontend/web-folder`
+pub fn init_tracing(config: &Config) -> Result<(), Error> {
+    let filter =
+        EnvFilter::try_from_default_env().unwrap_or_else(|_| EnvFilter::new(DEFAULT_LOG_FILTER));
+    let fmt_layer = if config.log_json {
+        tracing_subscriber::fmt::layer()
+            .json()
+            .with_current_span(true)
+            .boxed()
+    } else {
+        tracing_subscriber::fmt::layer().pretty().boxed()
+    };
+
+    let registry = tracing_subscriber::registry().with(filter).with(fmt_layer);
+
+    #[cfg(feature = "otel")]
+    let registry = registry.with(otel::layer(config)?);
+
+    registry
+        .try_init()
+        .map_err(|ex| Error::FailInit(ex.to_string()))
+}
+
+/// Flush the spans not exported yet (no-op without the "otel" feature).
+pub fn shutdown_tracing() {
+    #[cfg(feature = "otel")]
+    opentelemetry::global::shutdown_tracer_provider();
+}
+
+#[cfg(feature = "otel")]
+mod otel {
+    use opentelemetry::KeyValue;
+    use opentelemetry_otlp::WithExportConfig;
+    use opentelemetry_sdk::{runtime, trace, Resource};
+    use tracing_subscriber::{registry::LookupSpan, Layer};
+
+    use super::Error;
+    use crate::config::Config;
+
+    const SERVICE_NAME: &str = "cookbook";
+
+    pub fn layer<S>(config: &Config) -> Result<Option<impl Layer<S>>, Error>
+    where
+        S: tracing::Subscriber + for<'span> LookupSpan<'span>,
+    {
+        let endpoint = match &config.otel_endpoint {
+            Some(endpoint) => endpoint,
+            None => return Ok(None),
+        };
+
+        let tracer =
+            opentelemetry_otlp::new_pipeline()
+                .tracing()
+                .with_exporter(
+                    opentelemetry_otlp::new_exporter()
+                        .tonic()
+                        .with_endpoint(endpoint),
+                )
+                .with_trace_config(trace::config().with_resource(Resource::new(vec![
+                    KeyValue::new("service.name", SERVICE_NAME),
+                ])))
+                .install_batch(runtime::Tokio)
+                .map_err(|ex| Error::FailInit(ex.to_string()))?;
+
+        Ok(Some(tracing_opentelemetry::layer().with_tracer(tracer)))
+    }
+}
+
+#[derive(ThisError, Debug)]
+pub enum Error {
+    #[error("Tracing failed to initialize. Cause: {0}")]
+    FailInit(String),
+}
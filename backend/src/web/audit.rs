@@ -0,0 +1,48 @@
+use crate::model::{AuditFilter, AuditMac, Db};
+use crate::security::UserCtx;
+use serde::Serialize;
+use serde_json::json;
+use std::sync::Arc;
+use warp::reply::Json;
+use warp::Filter;
+
+use super::filter_auth::do_auth;
+use super::filter_utils::with_db;
+
+pub fn audit_rest_filters(
+    base_path: &'static str,
+    db: Arc<Db>,
+) -> impl Filter<Extract = impl warp::Reply, Error = warp::Rejection> + Clone {
+    let audit_path = warp::path(base_path)
+        .and(warp::path("admin"))
+        .and(warp::path("audit"));
+    let common = with_db(db.clone()).and(do_auth());
+
+    /// LIST audit log 'GET /admin/audit?typ=recipe&entity_id=1000&since=2024-01-01T00:00:00Z'
+    audit_path
+        .and(warp::get())
+        .and(warp::path::end())
+        .and(common.clone())
+        .and(warp::query::<AuditFilter>())
+        .and_then(audit_list)
+}
+
+async fn audit_list(
+    db: Arc<Db>,
+    utx: UserCtx,
+    filter: AuditFilter,
+) -> Result<Json, warp::Rejection> {
+    let entries = AuditMac::list(&db, &utx, filter).await?;
+    json_response(entries)
+}
+
+fn json_response<D: Serialize>(data: D) -> Result<Json, warp::Rejection> {
+    let response = json!({"data": data});
+    Ok(warp::reply::json(&response))
+}
+
+// region: Test
+#[cfg(test)]
+#[path = "../_tests/web_audit.rs"]
+mod tests;
+// endregion: Test
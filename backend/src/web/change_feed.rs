@@ -0,0 +1,77 @@
+use crate::model::{ChangeFeedMac, ClientChange, Db};
+use crate::security::UserCtx;
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use std::sync::Arc;
+use warp::reply::Json;
+use warp::Filter;
+
+use super::filter_auth::do_auth;
+use super::filter_utils::with_db;
+
+pub fn change_feed_rest_filters(
+    base_path: &'static str,
+    db: Arc<Db>,
+) -> impl Filter<Extract = impl warp::Reply, Error = warp::Rejection> + Clone {
+    let changes_path = warp::path(base_path).and(warp::path("changes"));
+    let common = with_db(db.clone()).and(do_auth());
+
+    /// PULL changes 'GET /changes?since=0&limit=100'
+    let pull = changes_path
+        .and(warp::get())
+        .and(warp::path::end())
+        .and(common.clone())
+        .and(warp::query::<ChangesParams>())
+        .and_then(changes_pull);
+
+    /// PUSH offline changes 'POST /changes with body [ClientChange]'
+    let push = changes_path
+        .and(warp::post())
+        .and(warp::path::end())
+        .and(common.clone())
+        .and(warp::body::json())
+        .and_then(changes_push);
+
+    pull.or(push)
+}
+
+#[derive(Deserialize)]
+struct ChangesParams {
+    #[serde(default)]
+    since: i64,
+    #[serde(default = "default_limit")]
+    limit: i64,
+}
+
+fn default_limit() -> i64 {
+    100
+}
+
+async fn changes_pull(
+    db: Arc<Db>,
+    utx: UserCtx,
+    params: ChangesParams,
+) -> Result<Json, warp::Rejection> {
+    let feed = ChangeFeedMac::since(&db, &utx, params.since, params.limit).await?;
+    json_response(feed)
+}
+
+async fn changes_push(
+    db: Arc<Db>,
+    utx: UserCtx,
+    changes: Vec<ClientChange>,
+) -> Result<Json, warp::Rejection> {
+    let results = ChangeFeedMac::apply(&db, &utx, changes).await?;
+    json_response(results)
+}
+
+fn json_response<D: Serialize>(data: D) -> Result<Json, warp::Rejection> {
+    let response = json!({"data": data});
+    Ok(warp::reply::json(&response))
+}
+
+// region: Test
+#[cfg(test)]
+#[path = "../_tests/web_change_feed.rs"]
+mod tests;
+// endregion: Test
@@ -0,0 +1,181 @@
+use crate::model::{CollectionMac, CollectionPatch, Db};
+use crate::security::UserCtx;
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use std::sync::Arc;
+use warp::reply::Json;
+use warp::Filter;
+
+use super::filter_auth::do_auth;
+use super::filter_utils::with_db;
+
+pub fn collection_rest_filters(
+    base_path: &'static str,
+    db: Arc<Db>,
+) -> impl Filter<Extract = impl warp::Reply, Error = warp::Rejection> + Clone {
+    let collections_path = warp::path(base_path).and(warp::path("collections"));
+    let common = with_db(db.clone()).and(do_auth());
+
+    /// LIST collections 'GET /collections'
+    let list = collections_path
+        .and(warp::get())
+        .and(warp::path::end())
+        .and(common.clone())
+        .and_then(collection_list);
+
+    /// GET collection 'GET /collections/1000'
+    let get = collections_path
+        .and(warp::get())
+        .and(common.clone())
+        .and(warp::path::param())
+        .and(warp::path::end())
+        .and_then(collection_get);
+
+    /// CREATE collection 'POST /collections with body CollectionPatch'
+    let create = collections_path
+        .and(warp::post())
+        .and(warp::path::end())
+        .and(common.clone())
+        .and(warp::body::json())
+        .and_then(collection_create);
+
+    /// UPDATE collection 'PATCH /collections/1000 with body CollectionPatch'
+    let update = collections_path
+        .and(warp::patch())
+        .and(common.clone())
+        .and(warp::path::param())
+        .and(warp::path::end())
+        .and(warp::body::json())
+        .and_then(collection_update);
+
+    /// DELETE collection 'DELETE /collections/1000'
+    let delete = collections_path
+        .and(warp::delete())
+        .and(common.clone())
+        .and(warp::path::param())
+        .and(warp::path::end())
+        .and_then(collection_delete);
+
+    /// ADD recipe 'POST /collections/1000/recipes with body {"recipe_id": 1000}'
+    let add_recipe = collections_path
+        .and(warp::post())
+        .and(common.clone())
+        .and(warp::path::param())
+        .and(warp::path("recipes"))
+        .and(warp::path::end())
+        .and(warp::body::json())
+        .and_then(collection_add_recipe);
+
+    /// REMOVE recipe 'DELETE /collections/1000/recipes/1000'
+    let remove_recipe = collections_path
+        .and(warp::delete())
+        .and(common.clone())
+        .and(warp::path::param())
+        .and(warp::path("recipes"))
+        .and(warp::path::param())
+        .and(warp::path::end())
+        .and_then(collection_remove_recipe);
+
+    /// REORDER recipes 'PUT /collections/1000/recipes with body {"recipe_ids": [1001, 1000]}'
+    let reorder = collections_path
+        .and(warp::put())
+        .and(common.clone())
+        .and(warp::path::param())
+        .and(warp::path("recipes"))
+        .and(warp::path::end())
+        .and(warp::body::json())
+        .and_then(collection_reorder);
+
+    list.or(get)
+        .or(create)
+        .or(update)
+        .or(delete)
+        .or(add_recipe)
+        .or(remove_recipe)
+        .or(reorder)
+}
+
+#[derive(Deserialize)]
+struct AddRecipeBody {
+    recipe_id: i64,
+}
+
+#[derive(Deserialize)]
+struct ReorderBody {
+    recipe_ids: Vec<i64>,
+}
+
+async fn collection_list(db: Arc<Db>, utx: UserCtx) -> Result<Json, warp::Rejection> {
+    let collections = CollectionMac::list(&db, &utx).await?;
+    json_response(collections)
+}
+
+async fn collection_get(db: Arc<Db>, utx: UserCtx, id: i64) -> Result<Json, warp::Rejection> {
+    let collection = CollectionMac::get(&db, &utx, id).await?;
+    json_response(collection)
+}
+
+async fn collection_create(
+    db: Arc<Db>,
+    utx: UserCtx,
+    patch: CollectionPatch,
+) -> Result<Json, warp::Rejection> {
+    let collection = CollectionMac::create(&db, &utx, patch).await?;
+    json_response(collection)
+}
+
+async fn collection_update(
+    db: Arc<Db>,
+    utx: UserCtx,
+    id: i64,
+    patch: CollectionPatch,
+) -> Result<Json, warp::Rejection> {
+    let collection = CollectionMac::update(&db, &utx, id, patch).await?;
+    json_response(collection)
+}
+
+async fn collection_delete(db: Arc<Db>, utx: UserCtx, id: i64) -> Result<Json, warp::Rejection> {
+    let collection = CollectionMac::delete(&db, &utx, id).await?;
+    json_response(collection)
+}
+
+async fn collection_add_recipe(
+    db: Arc<Db>,
+    utx: UserCtx,
+    id: i64,
+    body: AddRecipeBody,
+) -> Result<Json, warp::Rejection> {
+    let collection = CollectionMac::add_recipe(&db, &utx, id, body.recipe_id).await?;
+    json_response(collection)
+}
+
+async fn collection_remove_recipe(
+    db: Arc<Db>,
+    utx: UserCtx,
+    id: i64,
+    recipe_id: i64,
+) -> Result<Json, warp::Rejection> {
+    let collection = CollectionMac::remove_recipe(&db, &utx, id, recipe_id).await?;
+    json_response(collection)
+}
+
+async fn collection_reorder(
+    db: Arc<Db>,
+    utx: UserCtx,
+    id: i64,
+    body: ReorderBody,
+) -> Result<Json, warp::Rejection> {
+    let collection = CollectionMac::reorder(&db, &utx, id, body.recipe_ids).await?;
+    json_response(collection)
+}
+
+fn json_response<D: Serialize>(data: D) -> Result<Json, warp::Rejection> {
+    let response = json!({"data": data});
+    Ok(warp::reply::json(&response))
+}
+
+// region: Test
+#[cfg(test)]
+#[path = "../_tests/web_collection.rs"]
+mod tests;
+// endregion: Test
@@ -0,0 +1,70 @@
+use crate::model::{Db, FavoriteMac};
+use crate::security::UserCtx;
+use serde::Serialize;
+use serde_json::json;
+use std::sync::Arc;
+use warp::reply::Json;
+use warp::Filter;
+
+use super::filter_auth::do_auth;
+use super::filter_utils::with_db;
+
+pub fn favorite_rest_filters(
+    base_path: &'static str,
+    db: Arc<Db>,
+) -> impl Filter<Extract = impl warp::Reply, Error = warp::Rejection> + Clone {
+    let common = with_db(db.clone()).and(do_auth());
+
+    /// LIST favorite recipes 'GET /favorites'
+    let list = warp::path(base_path)
+        .and(warp::path("favorites"))
+        .and(warp::get())
+        .and(warp::path::end())
+        .and(common.clone())
+        .and_then(favorite_list);
+
+    /// ADD favorite 'PUT /recipes/1000/favorite'
+    let recipe_favorite_path = warp::path(base_path).and(warp::path("recipes"));
+    let add = recipe_favorite_path
+        .and(warp::put())
+        .and(common.clone())
+        .and(warp::path::param())
+        .and(warp::path("favorite"))
+        .and(warp::path::end())
+        .and_then(favorite_add);
+
+    /// REMOVE favorite 'DELETE /recipes/1000/favorite'
+    let remove = recipe_favorite_path
+        .and(warp::delete())
+        .and(common.clone())
+        .and(warp::path::param())
+        .and(warp::path("favorite"))
+        .and(warp::path::end())
+        .and_then(favorite_remove);
+
+    list.or(add).or(remove)
+}
+
+async fn favorite_list(db: Arc<Db>, utx: UserCtx) -> Result<Json, warp::Rejection> {
+    let recipes = FavoriteMac::list(&db, &utx).await?;
+    json_response(recipes)
+}
+
+async fn favorite_add(db: Arc<Db>, utx: UserCtx, recipe_id: i64) -> Result<Json, warp::Rejection> {
+    let recipe = FavoriteMac::add(&db, &utx, recipe_id).await?;
+    json_response(recipe)
+}
+
+async fn favorite_remove(
+    db: Arc<Db>,
+    utx: UserCtx,
+    recipe_id: i64,
+) -> Result<Json, warp::Rejection> {
+    let recipe = FavoriteMac::remove(&db, &utx, recipe_id).await?;
+    json_response(recipe)
+}
+
+fn json_response<D: Serialize>(data: D) -> Result<Json, warp::Rejection> {
+    let response = json!({"data": data});
+    Ok(warp::reply::json(&response))
+}
@@ -1,28 +1,51 @@
-use std::sync::Arc;
-
+use serde::Deserialize;
 use warp::{reject::Rejection, Filter};
 
-use crate::{
-    model::Db,
-    security::{utx_from_token, UserCtx},
-};
+use crate::security::{utx_from_token, UserCtx};
 
-use super::{filter_utils::with_db, Error};
+use super::{
+    trace::{new_request_id, HEADER_REQUEST_ID},
+    Error,
+};
 
-const HEADER_XAUTH: &str = "X-Auth-Token";
+pub const HEADER_XAUTH: &str = "X-Auth-Token";
 
-pub fn do_auth(db: Arc<Db>) -> impl Filter<Extract = (UserCtx,), Error = Rejection> + Clone {
+pub fn do_auth() -> impl Filter<Extract = (UserCtx,), Error = Rejection> + Clone {
     //warp::any().and_then(|| async { Ok::<UserCtx, Rejection>(utx_from_token("123").await?) })
     warp::any()
-        .and(with_db(db))
         .and(warp::header::optional(HEADER_XAUTH))
-        .and_then(|db: Arc<Db>, xauth: Option<String>| async move {
-            match xauth {
+        .and(warp::header::optional(HEADER_REQUEST_ID))
+        .and_then(
+            |xauth: Option<String>, request_id: Option<String>| async move {
+                match xauth {
+                    Some(xauth) => {
+                        let mut utx = utx_from_token(&xauth).await?;
+                        utx.request_id = Some(request_id.unwrap_or_else(new_request_id));
+                        Ok::<UserCtx, Rejection>(utx)
+                    }
+                    None => Err(Error::FailAuthMissingXAuth.into()),
+                }
+            },
+        )
+}
+
+/// Same as `do_auth`, with a `?token=` fallback for the WebSocket handshake, since browsers cannot set headers on it.
+pub fn do_auth_ws() -> impl Filter<Extract = (UserCtx,), Error = Rejection> + Clone {
+    warp::any()
+        .and(warp::header::optional(HEADER_XAUTH))
+        .and(warp::query::<WsAuthParams>())
+        .and_then(|xauth: Option<String>, params: WsAuthParams| async move {
+            match xauth.or(params.token) {
                 Some(xauth) => {
-                    let utx = utx_from_token(&db, &xauth).await?;
+                    let utx = utx_from_token(&xauth).await?;
                     Ok::<UserCtx, Rejection>(utx)
                 }
                 None => Err(Error::FailAuthMissingXAuth.into()),
             }
         })
 }
+
+#[derive(Deserialize)]
+struct WsAuthParams {
+    token: Option<String>,
+}
@@ -0,0 +1,71 @@
+use warp::{
+    http::{header, HeaderValue},
+    path::FullPath,
+    reject::Rejection,
+    reply::{Reply, Response},
+    Filter,
+};
+
+/// Shortest fingerprint (content hash) in a file name, e.g. `app.3f2a9c1b.js` or `app-BqXz12aF.js`.
+const FINGERPRINT_MIN_LEN: usize = 8;
+
+// region: Filters
+/// Add a `Cache-Control` to the replies of the `static_site`: cached for `immutable_max_age_secs`
+/// for the fingerprinted files (their name changes with their content), revalidated (`no-cache`)
+/// for the others, `index.html` first. A max age of 0 revalidates them all.
+pub fn with_static_cache<F, R>(
+    immutable_max_age_secs: u64,
+    static_site: F,
+) -> impl Filter<Extract = (Response,), Error = Rejection> + Clone
+where
+    F: Filter<Extract = (R,), Error = Rejection> + Clone + Send + Sync + 'static,
+    R: Reply,
+{
+    warp::path::full()
+        .and(static_site)
+        .map(move |path: FullPath, reply: R| {
+            let mut response = reply.into_response();
+            let status = response.status();
+            if !status.is_client_error() && !status.is_server_error() {
+                let cache_control = cache_control(path.as_str(), immutable_max_age_secs);
+                response
+                    .headers_mut()
+                    .insert(header::CACHE_CONTROL, cache_control);
+            }
+            response
+        })
+}
+// endregion: Filters
+
+// region: Utils
+fn cache_control(path: &str, immutable_max_age_secs: u64) -> HeaderValue {
+    match immutable_max_age_secs > 0 && is_fingerprinted(path) {
+        true => HeaderValue::from_str(&format!(
+            "public, max-age={}, immutable",
+            immutable_max_age_secs
+        ))
+        .unwrap_or_else(|_| HeaderValue::from_static("no-cache")),
+        false => HeaderValue::from_static("no-cache"),
+    }
+}
+
+/// Whether the file name of `path` has a fingerprint, a `.` or `-` separated part (not the first,
+/// not the extension) of at least `FINGERPRINT_MIN_LEN` letters and digits, with a digit.
+fn is_fingerprinted(path: &str) -> bool {
+    let name = path.rsplit('/').next().unwrap_or_default();
+    let Some((stem, _extension)) = name.rsplit_once('.') else {
+        return false;
+    };
+    stem.split(['.', '-']).skip(1).any(|part| {
+        part.len() >= FINGERPRINT_MIN_LEN
+            && part.chars().all(|c| c.is_ascii_alphanumeric())
+            && part.chars().any(|c| c.is_ascii_digit())
+    })
+}
+// endregion: Utils
+
+// region: Test
+#[cfg(test)]
+#[path = "../_tests/web_cache.rs"]
+mod tests;
+// endregion: Test
@@ -0,0 +1,213 @@
+use std::{convert::Infallible, io::Write};
+use tracing::warn;
+use warp::{
+    http::{header, HeaderMap, HeaderValue, Method, StatusCode},
+    hyper::{body, Body},
+    reply::{Reply, Response},
+    Filter,
+};
+
+/// Brotli quality (0-11), a balance of size and speed for on the fly compression.
+const BROTLI_QUALITY: u32 = 5;
+const BROTLI_WINDOW: u32 = 22;
+const BROTLI_BUFFER: usize = 4096;
+
+/// Content types worth compressing (images, fonts... are compressed already).
+const COMPRESSIBLE_TYPES: &[&str] = &[
+    "application/javascript",
+    "application/json",
+    "application/manifest+json",
+    "application/xml",
+    "image/svg+xml",
+];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Encoding {
+    Brotli,
+    Gzip,
+}
+
+impl Encoding {
+    fn as_str(&self) -> &'static str {
+        match self {
+            Encoding::Brotli => "br",
+            Encoding::Gzip => "gzip",
+        }
+    }
+
+    fn compress(&self, data: &[u8]) -> std::io::Result<Vec<u8>> {
+        match self {
+            Encoding::Brotli => {
+                let mut writer = brotli::CompressorWriter::new(
+                    Vec::new(),
+                    BROTLI_BUFFER,
+                    BROTLI_QUALITY,
+                    BROTLI_WINDOW,
+                );
+                writer.write_all(data)?;
+                Ok(writer.into_inner())
+            }
+            Encoding::Gzip => {
+                let mut encoder =
+                    flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::default());
+                encoder.write_all(data)?;
+                encoder.finish()
+            }
+        }
+    }
+}
+
+// region: Filters
+/// Compress the replies of `routes` with brotli or gzip, as preferred by the `Accept-Encoding` of the request.
+/// Only the text like replies of `min_bytes` or more are, the others (and all when not `enabled`) go as is.
+pub fn with_compression<F>(
+    enabled: bool,
+    min_bytes: usize,
+    routes: F,
+) -> impl Filter<Extract = (Response,), Error = Infallible> + Clone
+where
+    F: Filter<Extract = (Response,), Error = Infallible> + Clone + Send + Sync + 'static,
+{
+    warp::method()
+        .and(warp::header::headers_cloned())
+        .and(routes)
+        .then(
+            move |method: Method, request: HeaderMap, response: Response| async move {
+                match enabled && method != Method::HEAD {
+                    true => compress(&request, response, min_bytes).await,
+                    false => response,
+                }
+            },
+        )
+}
+// endregion: Filters
+
+// region: Utils
+async fn compress(request: &HeaderMap, response: Response, min_bytes: usize) -> Response {
+    if !is_compressible(&response) {
+        return response;
+    }
+    let length = response
+        .headers()
+        .get(header::CONTENT_LENGTH)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.parse::<usize>().ok());
+    if length.is_some_and(|length| length < min_bytes) {
+        return response;
+    }
+
+    let (mut parts, body) = response.into_parts();
+    parts
+        .headers
+        .append(header::VARY, HeaderValue::from_static("Accept-Encoding"));
+    let encoding = match negotiate(request) {
+        Some(encoding) => encoding,
+        None => return Response::from_parts(parts, body),
+    };
+
+    // the whole body, the compressible replies are small (JSON, html, css, js)
+    let bytes = match body::to_bytes(body).await {
+        Ok(bytes) => bytes,
+        Err(ex) => {
+            warn!(error = %ex, "reading the reply to compress failed");
+            return StatusCode::INTERNAL_SERVER_ERROR.into_response();
+        }
+    };
+    if bytes.len() < min_bytes {
+        return Response::from_parts(parts, Body::from(bytes));
+    }
+    let compressed = match encoding.compress(&bytes) {
+        Ok(compressed) => compressed,
+        Err(ex) => {
+            warn!(error = %ex, encoding = encoding.as_str(), "compression failed");
+            return Response::from_parts(parts, Body::from(bytes));
+        }
+    };
+
+    let headers = &mut parts.headers;
+    headers.insert(
+        header::CONTENT_ENCODING,
+        HeaderValue::from_static(encoding.as_str()),
+    );
+    headers.insert(header::CONTENT_LENGTH, HeaderValue::from(compressed.len()));
+    // a strong ETag is for the exact bytes, the compressed ones are another representation
+    if let Some(etag) = headers.get(header::ETAG).and_then(|v| v.to_str().ok()) {
+        if !etag.starts_with("W/") {
+            if let Ok(weak) = HeaderValue::from_str(&format!("W/{}", etag)) {
+                headers.insert(header::ETAG, weak);
+            }
+        }
+    }
+    Response::from_parts(parts, Body::from(compressed))
+}
+
+fn is_compressible(response: &Response) -> bool {
+    let status = response.status();
+    let headers = response.headers();
+    // e.g. the 101 of a WebSocket, a 304, a range
+    if status.is_informational()
+        || status.is_redirection()
+        || matches!(status, StatusCode::NO_CONTENT | StatusCode::PARTIAL_CONTENT)
+        || headers.contains_key(header::CONTENT_ENCODING)
+        || headers.contains_key(header::CONTENT_RANGE)
+    {
+        return false;
+    }
+
+    let content_type = headers
+        .get(header::CONTENT_TYPE)
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| v.split(';').next())
+        .map(|v| v.trim().to_ascii_lowercase());
+    match content_type {
+        Some(content_type) => {
+            content_type.starts_with("text/") || COMPRESSIBLE_TYPES.contains(&content_type.as_str())
+        }
+        None => false,
+    }
+}
+
+/// The preferred of brotli and gzip by the `Accept-Encoding` of the request (brotli on a tie), if any.
+fn negotiate(request: &HeaderMap) -> Option<Encoding> {
+    let mut brotli: Option<f32> = None;
+    let mut gzip: Option<f32> = None;
+    let mut any: Option<f32> = None;
+    for value in request.get_all(header::ACCEPT_ENCODING) {
+        let Ok(value) = value.to_str() else {
+            continue;
+        };
+        for item in value.split(',') {
+            let mut params = item.split(';');
+            let name = params
+                .next()
+                .unwrap_or_default()
+                .trim()
+                .to_ascii_lowercase();
+            let q = params
+                .filter_map(|param| param.trim().strip_prefix("q="))
+                .find_map(|q| q.trim().parse::<f32>().ok())
+                .unwrap_or(1.0);
+            match name.as_str() {
+                "br" => brotli = Some(q),
+                "gzip" | "x-gzip" => gzip = Some(q),
+                "*" => any = Some(q),
+                _ => (),
+            }
+        }
+    }
+
+    let brotli = brotli.or(any).unwrap_or(0.0);
+    let gzip = gzip.or(any).unwrap_or(0.0);
+    match (brotli, gzip) {
+        (b, g) if b > 0.0 && b >= g => Some(Encoding::Brotli),
+        (_, g) if g > 0.0 => Some(Encoding::Gzip),
+        _ => None,
+    }
+}
+// endregion: Utils
+
+// region: Test
+#[cfg(test)]
+#[path = "../_tests/web_compress.rs"]
+mod tests;
+// endregion: Test
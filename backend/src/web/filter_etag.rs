@@ -0,0 +1,124 @@
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use serde_json::json;
+use std::{
+    collections::hash_map::DefaultHasher,
+    hash::{Hash, Hasher},
+};
+use warp::{
+    http::{header, HeaderValue, StatusCode},
+    reject::Rejection,
+    reply::{Reply, Response},
+    Filter,
+};
+
+use super::Error;
+
+const HEADER_IF_MATCH: &str = "If-Match";
+const HEADER_IF_NONE_MATCH: &str = "If-None-Match";
+const HEADER_IF_MODIFIED_SINCE: &str = "If-Modified-Since";
+/// HTTP date (RFC 7231, always GMT), e.g. `Sun, 06 Nov 1994 08:49:37 GMT`.
+const HTTP_DATE_FORMAT: &str = "%a, %d %b %Y %H:%M:%S GMT";
+
+/// ETag of a versioned entity, e.g. `"3"` for version 3.
+pub fn etag(version: i64) -> String {
+    format!("\"{}\"", version)
+}
+
+/// Extract the expected version from the `If-Match` header.
+/// `None` when the header is absent or `*` (any version).
+pub fn if_match() -> impl Filter<Extract = (Option<i64>,), Error = Rejection> + Clone {
+    warp::header::optional(HEADER_IF_MATCH).and_then(|if_match: Option<String>| async move {
+        match if_match.as_deref().map(str::trim) {
+            None | Some("*") => Ok::<Option<i64>, Rejection>(None),
+            Some(tag) => parse_etag(tag)
+                .map(Some)
+                .ok_or_else(|| Error::FailPreconditionIfMatch(tag.to_string()).into()),
+        }
+    })
+}
+
+fn parse_etag(tag: &str) -> Option<i64> {
+    let tag = tag.strip_prefix("W/").unwrap_or(tag);
+    tag.strip_prefix('"')?.strip_suffix('"')?.parse().ok()
+}
+
+// region: List
+/// The `If-None-Match` and `If-Modified-Since` of a list request.
+#[derive(Debug, Default)]
+pub struct ListConditions {
+    if_none_match: Option<String>,
+    if_modified_since: Option<DateTime<Utc>>,
+}
+
+/// Extract the `ListConditions` (an unparsable `If-Modified-Since` is ignored).
+pub fn list_conditions() -> impl Filter<Extract = (ListConditions,), Error = Rejection> + Clone {
+    warp::header::optional(HEADER_IF_NONE_MATCH)
+        .and(warp::header::optional(HEADER_IF_MODIFIED_SINCE))
+        .map(
+            |if_none_match: Option<String>, if_modified_since: Option<String>| ListConditions {
+                if_none_match,
+                if_modified_since: if_modified_since
+                    .and_then(|date| DateTime::parse_from_rfc2822(&date).ok())
+                    .map(|date| date.with_timezone(&Utc)),
+            },
+        )
+}
+
+/// The `{"data": data}` JSON reply of a list, with a weak ETag of its content and a `Last-Modified`
+/// (when given, e.g. the latest mtime), or a 304 when `conditions` show the caller has it already.
+/// Note: `If-None-Match` wins over `If-Modified-Since`, which does not see the removed items.
+pub fn list_response<D: Serialize>(
+    data: D,
+    last_modified: Option<DateTime<Utc>>,
+    conditions: ListConditions,
+) -> Response {
+    let body = match serde_json::to_vec(&json!({ "data": data })) {
+        Ok(body) => body,
+        Err(ex) => {
+            tracing::error!(error = %ex, "list reply serialization failed");
+            return StatusCode::INTERNAL_SERVER_ERROR.into_response();
+        }
+    };
+    let mut hasher = DefaultHasher::new();
+    body.hash(&mut hasher);
+    let etag = format!("W/\"{:016x}\"", hasher.finish());
+
+    let not_modified = match (&conditions.if_none_match, conditions.if_modified_since) {
+        (Some(if_none_match), _) => if_none_match.split(',').any(|tag| {
+            let tag = tag.trim();
+            tag == "*" || weak_eq(tag, &etag)
+        }),
+        (None, Some(since)) => last_modified.is_some_and(|lm| lm.timestamp() <= since.timestamp()),
+        (None, None) => false,
+    };
+
+    let mut response = match not_modified {
+        true => StatusCode::NOT_MODIFIED.into_response(),
+        false => {
+            let mut response = Response::new(body.into());
+            response.headers_mut().insert(
+                header::CONTENT_TYPE,
+                HeaderValue::from_static("application/json"),
+            );
+            response
+        }
+    };
+    let headers = response.headers_mut();
+    if let Ok(etag) = HeaderValue::from_str(&etag) {
+        headers.insert(header::ETAG, etag);
+    }
+    if let Some(last_modified) = last_modified {
+        let date = last_modified.format(HTTP_DATE_FORMAT).to_string();
+        if let Ok(date) = HeaderValue::from_str(&date) {
+            headers.insert(header::LAST_MODIFIED, date);
+        }
+    }
+    response
+}
+
+/// Weak comparison (RFC 7232), the `W/` of either tag does not count.
+fn weak_eq(a: &str, b: &str) -> bool {
+    a.strip_prefix("W/").unwrap_or(a) == b.strip_prefix("W/").unwrap_or(b)
+}
+// endregion: List
@@ -0,0 +1,158 @@
+use std::{convert::Infallible, sync::Arc};
+use warp::{
+    http::{header, HeaderMap, HeaderValue, Method, StatusCode},
+    reject::Rejection,
+    reply::{Reply, Response},
+    Filter,
+};
+
+use super::Error;
+
+/// Request headers a cross origin call may send, along with the CORS safelisted ones.
+const CORS_ALLOWED_HEADERS: &str =
+    "Content-Type, If-Match, If-None-Match, If-Modified-Since, X-Auth-Token";
+/// Response headers a cross origin caller may read, along with the CORS safelisted ones.
+const CORS_EXPOSED_HEADERS: &str =
+    "ETag, Last-Modified, Retry-After, RateLimit-Limit, RateLimit-Remaining, RateLimit-Reset, RateLimit-Policy, X-Request-Id";
+/// How long a browser may cache a preflight answer, in seconds.
+const CORS_MAX_AGE: &str = "600";
+
+// region: Policy
+/// Response headers of every route: CORS for the allowed origins, and the security headers.
+pub struct Policy {
+    /// e.g. `https://cookbook.example`, `*` for any, empty for same origin only
+    pub cors_allowed_origins: Vec<String>,
+    pub cors_allowed_methods: Vec<Method>,
+    /// kept when the route sets its own (e.g. the API docs page)
+    pub content_security_policy: String,
+}
+
+impl Policy {
+    fn allowed_origin(&self, headers: &HeaderMap) -> Option<HeaderValue> {
+        let origin = headers.get(header::ORIGIN)?;
+        let allowed = self
+            .cors_allowed_origins
+            .iter()
+            .any(|allowed| allowed == "*" || allowed.as_bytes() == origin.as_bytes());
+        allowed.then(|| origin.clone())
+    }
+
+    /// Add the CORS headers (when `request` comes from an allowed origin) and the security headers.
+    fn apply(&self, request: &HeaderMap, response: &mut HeaderMap) {
+        if let Some(origin) = self.allowed_origin(request) {
+            response.insert(header::ACCESS_CONTROL_ALLOW_ORIGIN, origin);
+            response.insert(
+                header::ACCESS_CONTROL_EXPOSE_HEADERS,
+                HeaderValue::from_static(CORS_EXPOSED_HEADERS),
+            );
+        }
+        if !self.cors_allowed_origins.is_empty() {
+            response.append(header::VARY, HeaderValue::from_static("Origin"));
+        }
+
+        if !response.contains_key(header::CONTENT_SECURITY_POLICY) {
+            if let Ok(csp) = HeaderValue::from_str(&self.content_security_policy) {
+                response.insert(header::CONTENT_SECURITY_POLICY, csp);
+            }
+        }
+        response.insert(
+            header::X_CONTENT_TYPE_OPTIONS,
+            HeaderValue::from_static("nosniff"),
+        );
+        response.insert(
+            header::REFERRER_POLICY,
+            HeaderValue::from_static("strict-origin-when-cross-origin"),
+        );
+        response.insert(header::X_FRAME_OPTIONS, HeaderValue::from_static("DENY"));
+    }
+
+    /// 204 to a CORS preflight (`OPTIONS` with `Access-Control-Request-Method`), with the allowed
+    /// methods and headers when the origin is allowed, none otherwise (the browser then blocks the call).
+    fn preflight(&self, request: &HeaderMap) -> Response {
+        let mut response = StatusCode::NO_CONTENT.into_response();
+        let headers = response.headers_mut();
+        if self.allowed_origin(request).is_some() {
+            let methods = self
+                .cors_allowed_methods
+                .iter()
+                .map(Method::as_str)
+                .collect::<Vec<_>>()
+                .join(", ");
+            if let Ok(methods) = HeaderValue::from_str(&methods) {
+                headers.insert(header::ACCESS_CONTROL_ALLOW_METHODS, methods);
+            }
+            headers.insert(
+                header::ACCESS_CONTROL_ALLOW_HEADERS,
+                HeaderValue::from_static(CORS_ALLOWED_HEADERS),
+            );
+            headers.insert(
+                header::ACCESS_CONTROL_MAX_AGE,
+                HeaderValue::from_static(CORS_MAX_AGE),
+            );
+        }
+        response
+    }
+}
+// endregion: Policy
+
+// region: Filters
+/// Answer the CORS preflights, and add the `policy` headers to the replies of `routes`
+/// (to wrap the recovered routes, so that the error replies get them too).
+pub fn with_policy<F, R>(
+    policy: Arc<Policy>,
+    routes: F,
+) -> impl Filter<Extract = (Response,), Error = Infallible> + Clone
+where
+    F: Filter<Extract = (R,), Error = Infallible> + Clone + Send + Sync + 'static,
+    R: Reply,
+{
+    let preflight_policy = policy.clone();
+    let preflight = warp::options()
+        .and(warp::header::headers_cloned())
+        .and_then(move |headers: HeaderMap| {
+            let policy = preflight_policy.clone();
+            async move {
+                match headers.contains_key(header::ACCESS_CONTROL_REQUEST_METHOD) {
+                    true => Ok::<Response, Rejection>(policy.preflight(&headers)),
+                    false => Err(warp::reject::not_found()),
+                }
+            }
+        });
+    let routes = routes.map(Reply::into_response);
+
+    warp::header::headers_cloned()
+        .and(preflight.or(routes).unify())
+        .map(move |request: HeaderMap, mut response: Response| {
+            policy.apply(&request, response.headers_mut());
+            response
+        })
+}
+
+/// Reject a request with a body over `max_bytes` (413), before any route reads it.
+/// A body without a `Content-Length` (chunked) is rejected as well (411), it could not be checked upfront.
+pub fn body_limit(max_bytes: u64) -> impl Filter<Extract = (), Error = Rejection> + Clone {
+    warp::header::headers_cloned()
+        .and_then(move |headers: HeaderMap| async move {
+            let length = headers
+                .get(header::CONTENT_LENGTH)
+                .and_then(|value| value.to_str().ok())
+                .and_then(|value| value.parse::<u64>().ok());
+            match length {
+                Some(length) if length > max_bytes => {
+                    Err(Error::FailBodyTooLarge(length, max_bytes).into())
+                }
+                None if headers.contains_key(header::TRANSFER_ENCODING) => {
+                    Err(Error::FailBodyLengthRequired.into())
+                }
+                _ => Ok::<(), Rejection>(()),
+            }
+        })
+        .untuple_one()
+}
+// endregion: Filters
+
+// region: Test
+#[cfg(test)]
+#[path = "../_tests/web_policy.rs"]
+mod tests;
+// endregion: Test
@@ -0,0 +1,230 @@
+use std::{
+    collections::HashMap,
+    net::{IpAddr, SocketAddr},
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
+};
+use warp::{http::Method, path::Peek, reject::Rejection, Filter};
+
+use crate::security::UserCtx;
+
+use super::filter_auth::HEADER_XAUTH;
+
+/// Past this many buckets, the full (idle) ones are dropped, so a flood of IPs cannot grow the map forever.
+const MAX_BUCKETS: usize = 10_000;
+const WINDOW: Duration = Duration::from_secs(60);
+
+/// Budget a request is counted against.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum RateClass {
+    /// GET, HEAD, OPTIONS
+    Read,
+    /// POST, PUT, PATCH, DELETE
+    Write,
+    /// requests with an X-Auth-Token that does not authenticate (e.g. guessing tokens)
+    Auth,
+}
+
+impl RateClass {
+    fn as_str(&self) -> &'static str {
+        match self {
+            RateClass::Read => "read",
+            RateClass::Write => "write",
+            RateClass::Auth => "auth",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+enum RateKey {
+    User(i64),
+    Ip(IpAddr),
+}
+
+// region: RateLimiter
+/// Token buckets per user and per client IP, one per class. A budget is the requests per minute,
+/// also the burst (bucket capacity), 0 for no limit.
+pub struct RateLimiter {
+    read: u32,
+    write: u32,
+    auth: u32,
+    buckets: Mutex<HashMap<(RateClass, RateKey), Bucket>>,
+}
+
+struct Bucket {
+    tokens: f64,
+    updated: Instant,
+}
+
+/// Rejection of a request over its budget, a 429 with `Retry-After` and `RateLimit-*` headers.
+#[derive(Debug)]
+pub struct RateLimited {
+    pub class: RateClass,
+    /// budget, per minute
+    pub limit: u32,
+    /// seconds until the next request is allowed
+    pub retry_after: u64,
+}
+impl warp::reject::Reject for RateLimited {}
+
+impl RateLimiter {
+    pub fn new(read: u32, write: u32, auth: u32) -> Self {
+        RateLimiter {
+            read,
+            write,
+            auth,
+            buckets: Mutex::new(HashMap::new()),
+        }
+    }
+
+    fn limit(&self, class: RateClass) -> u32 {
+        match class {
+            RateClass::Read => self.read,
+            RateClass::Write => self.write,
+            RateClass::Auth => self.auth,
+        }
+    }
+
+    fn acquire(&self, class: RateClass, key: RateKey) -> Result<(), RateLimited> {
+        self.acquire_at(class, key, Instant::now())
+    }
+
+    /// Take a token from the bucket of `key` for `class`, refilled at `limit` per minute up to `limit`.
+    fn acquire_at(&self, class: RateClass, key: RateKey, now: Instant) -> Result<(), RateLimited> {
+        let limit = self.limit(class);
+        if limit == 0 {
+            return Ok(());
+        }
+        let capacity = limit as f64;
+        let per_sec = capacity / WINDOW.as_secs_f64();
+
+        let mut buckets = self.buckets.lock().expect("rate limiter lock");
+        if buckets.len() >= MAX_BUCKETS {
+            buckets.retain(|(class, _), bucket| {
+                let capacity = self.limit(*class) as f64;
+                let per_sec = capacity / WINDOW.as_secs_f64();
+                bucket.tokens + now.duration_since(bucket.updated).as_secs_f64() * per_sec
+                    < capacity
+            });
+        }
+        let bucket = buckets.entry((class, key)).or_insert(Bucket {
+            tokens: capacity,
+            updated: now,
+        });
+
+        let elapsed = now.duration_since(bucket.updated).as_secs_f64();
+        bucket.tokens = (bucket.tokens + elapsed * per_sec).min(capacity);
+        bucket.updated = now;
+        if bucket.tokens >= 1.0 {
+            bucket.tokens -= 1.0;
+            Ok(())
+        } else {
+            Err(RateLimited {
+                class,
+                limit,
+                retry_after: ((1.0 - bucket.tokens) / per_sec).ceil() as u64,
+            })
+        }
+    }
+}
+// endregion: RateLimiter
+
+// region: Filters
+/// Throttle the requests with `limiter`: the user of `auth` and the client IP each spend from their read or write budget.
+/// Requests failing `auth` go on (the route rejects them) but spend the auth budget of their IP when they came with a token.
+/// Extracts the user, when `auth` authenticated it.
+/// e.g. `rate_limit(limiter, do_auth())` in place of `do_auth()`, or `rate_limit_api` in front of all the APIs.
+pub fn rate_limit<A>(
+    limiter: Arc<RateLimiter>,
+    auth: A,
+) -> impl Filter<Extract = (Option<UserCtx>,), Error = Rejection> + Clone
+where
+    A: Filter<Extract = (UserCtx,), Error = Rejection> + Clone + Send + Sync + 'static,
+{
+    let auth = auth.map(Some).or(warp::any().map(|| None)).unify();
+
+    warp::method()
+        .and(warp::addr::remote())
+        .and(warp::header::optional::<String>(HEADER_XAUTH))
+        .and(auth)
+        .and_then(
+            move |method: Method,
+                  remote: Option<SocketAddr>,
+                  xauth: Option<String>,
+                  utx: Option<UserCtx>| {
+                let limiter = limiter.clone();
+                async move {
+                    let class = match method {
+                        Method::GET | Method::HEAD | Method::OPTIONS => RateClass::Read,
+                        _ => RateClass::Write,
+                    };
+                    let ip = remote.map(|addr| addr.ip());
+
+                    match (&utx, ip) {
+                        (Some(utx), _) => limiter.acquire(class, RateKey::User(utx.user_id))?,
+                        (None, Some(ip)) if xauth.is_some() => {
+                            limiter.acquire(RateClass::Auth, RateKey::Ip(ip))?
+                        }
+                        (None, _) => (),
+                    }
+                    if let Some(ip) = ip {
+                        limiter.acquire(class, RateKey::Ip(ip))?;
+                    }
+
+                    Ok::<Option<UserCtx>, Rejection>(utx)
+                }
+            },
+        )
+}
+
+/// `rate_limit` with `auth` for the requests under `/{base_path}/` only, to put in front of the API routes.
+pub fn rate_limit_api<A>(
+    base_path: &'static str,
+    limiter: Arc<RateLimiter>,
+    auth: A,
+) -> impl Filter<Extract = (), Error = Rejection> + Clone
+where
+    A: Filter<Extract = (UserCtx,), Error = Rejection> + Clone + Send + Sync + 'static,
+{
+    warp::path::peek()
+        .and_then(move |peek: Peek| async move {
+            match peek.segments().next() == Some(base_path) {
+                true => Ok(()),
+                false => Err(warp::reject::not_found()),
+            }
+        })
+        .untuple_one()
+        .and(rate_limit(limiter, auth))
+        .map(|_utx: Option<UserCtx>| ())
+        .untuple_one()
+}
+// endregion: Filters
+
+// region: Reply
+impl RateLimited {
+    /// `Retry-After` along with the `RateLimit-*` headers (IETF draft), for the 429 reply.
+    pub fn headers(&self) -> Vec<(&'static str, String)> {
+        vec![
+            ("Retry-After", self.retry_after.to_string()),
+            ("RateLimit-Limit", self.limit.to_string()),
+            ("RateLimit-Remaining", "0".to_string()),
+            ("RateLimit-Reset", self.retry_after.to_string()),
+            (
+                "RateLimit-Policy",
+                format!(
+                    "{};w={};comment=\"{}\"",
+                    self.limit,
+                    WINDOW.as_secs(),
+                    self.class.as_str()
+                ),
+            ),
+        ]
+    }
+}
+// endregion: Reply
+
+// region: Test
+#[cfg(test)]
+#[path = "../_tests/web_rate_limit.rs"]
+mod tests;
+// endregion: Test
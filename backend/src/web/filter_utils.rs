@@ -7,3 +7,10 @@ use crate::model::Db;
 pub fn with_db(db: Arc<Db>) -> impl Filter<Extract = (Arc<Db>,), Error = Infallible> + Clone {
     warp::any().map(move || db.clone())
 }
+
+/// Same as `with_db`, for a repository (e.g. `Arc<dyn RecipeRepo>`).
+pub fn with_repo<R: ?Sized + Send + Sync>(
+    repo: Arc<R>,
+) -> impl Filter<Extract = (Arc<R>,), Error = Infallible> + Clone {
+    warp::any().map(move || repo.clone())
+}
@@ -0,0 +1,588 @@
+use async_graphql::dataloader::{DataLoader, Loader};
+use async_graphql::{
+    BatchRequest, Context, EmptySubscription, Enum, ErrorExtensions, InputObject, MaybeUndefined,
+    Object, Schema,
+};
+use chrono::{DateTime, Utc};
+use std::collections::HashMap;
+use std::sync::Arc;
+use warp::reply::Json;
+use warp::Filter;
+
+use crate::model::{
+    self, Db, Ingredient, IngredientMac, IngredientPatch, PatchValue, Recipe, RecipeIngredientMac,
+    RecipeIngredientPatch, RecipeMac, RecipePatch, RecipePatchInner, RecipeSort,
+};
+use crate::security::UserCtx;
+
+use super::filter_auth::do_auth;
+use super::filter_utils::with_db;
+
+pub type CookbookSchema = Schema<QueryRoot, MutationRoot, EmptySubscription>;
+
+type GqlResult<T> = async_graphql::Result<T>;
+
+/// `POST /api/graphql`, a query or a batch (array) of queries, with the same auth as the REST APIs.
+pub fn graphql_filters(
+    base_path: &'static str,
+    db: Arc<Db>,
+    max_depth: usize,
+    max_complexity: usize,
+) -> impl Filter<Extract = impl warp::Reply, Error = warp::Rejection> + Clone {
+    let schema = graphql_schema(db.clone(), max_depth, max_complexity);
+
+    /// QUERY 'POST /api/graphql with body {"query": "{ recipes { title } }"}'
+    warp::path(base_path)
+        .and(warp::path("graphql"))
+        .and(warp::post())
+        .and(warp::path::end())
+        .and(warp::any().map(move || schema.clone()))
+        .and(with_db(db))
+        .and(do_auth())
+        .and(warp::body::json())
+        .and_then(graphql_execute)
+}
+
+/// Queries past `max_depth` (nesting) or `max_complexity` (fields, list relations counting 10x) are rejected before running.
+pub fn graphql_schema(db: Arc<Db>, max_depth: usize, max_complexity: usize) -> CookbookSchema {
+    Schema::build(QueryRoot, MutationRoot, EmptySubscription)
+        .data(db)
+        .limit_depth(max_depth)
+        .limit_complexity(max_complexity)
+        .finish()
+}
+
+async fn graphql_execute(
+    schema: CookbookSchema,
+    db: Arc<Db>,
+    utx: UserCtx,
+    request: BatchRequest,
+) -> Result<Json, warp::Rejection> {
+    // Note: one loader per request, so the batches (and favorites) are those of the calling user
+    let loader = DataLoader::new(
+        GraphLoader {
+            db,
+            utx: utx.clone(),
+        },
+        tokio::spawn,
+    );
+    let request = request.data(utx).data(Arc::new(loader));
+    let response = schema.execute_batch(request).await;
+
+    Ok(warp::reply::json(&response))
+}
+
+// region: Query
+pub struct QueryRoot;
+
+#[Object]
+impl QueryRoot {
+    /// Recipes, only the ones using `ingredientId` when set.
+    async fn recipes(
+        &self,
+        ctx: &Context<'_>,
+        #[graphql(default)] sort: GqlRecipeSort,
+        ingredient_id: Option<i64>,
+    ) -> GqlResult<Vec<GqlRecipe>> {
+        let (db, utx) = db_utx(ctx)?;
+        let mut recipes = RecipeMac::list_recipes(db, utx, sort.into())
+            .await
+            .map_err(gql_error)?;
+        if let Some(ingredient_id) = ingredient_id {
+            let recipe_ids = loader(ctx)?
+                .load_one(RecipesOfIngredient(ingredient_id))
+                .await?
+                .unwrap_or_default();
+            recipes.retain(|recipe| recipe_ids.contains(&recipe.id));
+        }
+
+        Ok(recipes.into_iter().map(GqlRecipe).collect())
+    }
+
+    async fn recipe(&self, ctx: &Context<'_>, id: i64) -> GqlResult<GqlRecipe> {
+        loader(ctx)?
+            .load_one(RecipeId(id))
+            .await?
+            .map(GqlRecipe)
+            .ok_or_else(|| gql_error(model::Error::EntityNotFound("recipes", id.to_string())))
+    }
+
+    async fn ingredients(&self, ctx: &Context<'_>) -> GqlResult<Vec<GqlIngredient>> {
+        let (db, utx) = db_utx(ctx)?;
+        let ingredients = IngredientMac::list(db, utx).await.map_err(gql_error)?;
+
+        Ok(ingredients.into_iter().map(GqlIngredient).collect())
+    }
+
+    async fn ingredient(&self, ctx: &Context<'_>, id: i64) -> GqlResult<GqlIngredient> {
+        loader(ctx)?
+            .load_one(IngredientId(id))
+            .await?
+            .map(GqlIngredient)
+            .ok_or_else(|| gql_error(model::Error::EntityNotFound("ingredients", id.to_string())))
+    }
+}
+// endregion: Query
+
+// region: Mutation
+/// Same as the `IngredientMac`/`RecipeMac` calls, `version` makes it conditional (like `If-Match`).
+pub struct MutationRoot;
+
+#[Object]
+impl MutationRoot {
+    async fn create_ingredient(
+        &self,
+        ctx: &Context<'_>,
+        data: IngredientInput,
+    ) -> GqlResult<GqlIngredient> {
+        let (db, utx) = db_utx(ctx)?;
+        let ingredient = IngredientMac::create(db, utx, data.into())
+            .await
+            .map_err(gql_error)?;
+        Ok(GqlIngredient(ingredient))
+    }
+
+    async fn update_ingredient(
+        &self,
+        ctx: &Context<'_>,
+        id: i64,
+        version: Option<i64>,
+        data: IngredientInput,
+    ) -> GqlResult<GqlIngredient> {
+        let (db, utx) = db_utx(ctx)?;
+        let ingredient = match version {
+            Some(version) => {
+                IngredientMac::update_if_match(db, utx, id, version, data.into()).await
+            }
+            None => IngredientMac::update(db, utx, id, data.into()).await,
+        };
+        Ok(GqlIngredient(ingredient.map_err(gql_error)?))
+    }
+
+    async fn delete_ingredient(
+        &self,
+        ctx: &Context<'_>,
+        id: i64,
+        version: Option<i64>,
+    ) -> GqlResult<GqlIngredient> {
+        let (db, utx) = db_utx(ctx)?;
+        let ingredient = match version {
+            Some(version) => IngredientMac::delete_if_match(db, utx, id, version).await,
+            None => IngredientMac::delete(db, utx, id).await,
+        };
+        Ok(GqlIngredient(ingredient.map_err(gql_error)?))
+    }
+
+    async fn restore_ingredient(&self, ctx: &Context<'_>, id: i64) -> GqlResult<GqlIngredient> {
+        let (db, utx) = db_utx(ctx)?;
+        let ingredient = IngredientMac::restore(db, utx, id)
+            .await
+            .map_err(gql_error)?;
+        Ok(GqlIngredient(ingredient))
+    }
+
+    async fn create_recipe(&self, ctx: &Context<'_>, data: RecipeInput) -> GqlResult<GqlRecipe> {
+        let (db, utx) = db_utx(ctx)?;
+        let (recipe, _) = RecipeMac::create(db, utx, data.into())
+            .await
+            .map_err(gql_error)?;
+        Ok(GqlRecipe(recipe))
+    }
+
+    async fn update_recipe(
+        &self,
+        ctx: &Context<'_>,
+        id: i64,
+        version: Option<i64>,
+        data: RecipeInput,
+    ) -> GqlResult<GqlRecipe> {
+        let (db, utx) = db_utx(ctx)?;
+        let recipe = match version {
+            Some(version) => RecipeMac::update_if_match(db, utx, id, version, data.into()).await,
+            None => RecipeMac::update(db, utx, id, data.into()).await,
+        };
+        Ok(GqlRecipe(recipe.map_err(gql_error)?.0))
+    }
+
+    async fn delete_recipe(
+        &self,
+        ctx: &Context<'_>,
+        id: i64,
+        version: Option<i64>,
+    ) -> GqlResult<GqlRecipe> {
+        let (db, utx) = db_utx(ctx)?;
+        let recipe = match version {
+            Some(version) => RecipeMac::delete_if_match(db, utx, id, version).await,
+            None => RecipeMac::delete(db, utx, id).await,
+        };
+        Ok(GqlRecipe(recipe.map_err(gql_error)?.0))
+    }
+
+    async fn restore_recipe(&self, ctx: &Context<'_>, id: i64) -> GqlResult<GqlRecipe> {
+        let (db, utx) = db_utx(ctx)?;
+        let (recipe, _) = RecipeMac::restore(db, utx, id).await.map_err(gql_error)?;
+        Ok(GqlRecipe(recipe))
+    }
+}
+// endregion: Mutation
+
+// region: Types
+pub struct GqlRecipe(Recipe);
+
+#[Object(name = "Recipe")]
+impl GqlRecipe {
+    async fn id(&self) -> i64 {
+        self.0.id
+    }
+    async fn title(&self) -> &str {
+        &self.0.title
+    }
+    /// owner
+    async fn cid(&self) -> Option<i64> {
+        self.0.cid
+    }
+    async fn ctime(&self) -> DateTime<Utc> {
+        self.0.ctime
+    }
+    async fn mtime(&self) -> DateTime<Utc> {
+        self.0.mtime
+    }
+    /// bumped on every update, the `version` of the conditional mutations
+    async fn version(&self) -> i64 {
+        self.0.version
+    }
+    /// average review rating, null until the first review
+    async fn rating_avg(&self) -> Option<f64> {
+        self.0.rating_avg
+    }
+    async fn rating_count(&self) -> i64 {
+        self.0.rating_count
+    }
+    /// favorite of the calling user
+    async fn is_favorite(&self) -> bool {
+        self.0.is_favorite
+    }
+
+    #[graphql(complexity = "10 * child_complexity")]
+    async fn ingredients(&self, ctx: &Context<'_>) -> GqlResult<Vec<GqlRecipeIngredient>> {
+        let links = loader(ctx)?.load_one(LinksOfRecipe(self.0.id)).await?;
+        Ok(links
+            .unwrap_or_default()
+            .into_iter()
+            .map(GqlRecipeIngredient)
+            .collect())
+    }
+}
+
+pub struct GqlRecipeIngredient(RecipeIngredientMac);
+
+#[Object(name = "RecipeIngredient")]
+impl GqlRecipeIngredient {
+    async fn recipe_id(&self) -> i64 {
+        self.0.recipe_id
+    }
+    async fn ingredient_id(&self) -> i64 {
+        self.0.ingredient_id
+    }
+    async fn ingredient_name(&self) -> &str {
+        &self.0.ingredient_name
+    }
+    async fn quantity(&self) -> &str {
+        &self.0.quantity
+    }
+    async fn cid(&self) -> i64 {
+        self.0.cid
+    }
+    async fn ctime(&self) -> DateTime<Utc> {
+        self.0.ctime
+    }
+    async fn mtime(&self) -> DateTime<Utc> {
+        self.0.mtime
+    }
+
+    async fn recipe(&self, ctx: &Context<'_>) -> GqlResult<Option<GqlRecipe>> {
+        let recipe = loader(ctx)?.load_one(RecipeId(self.0.recipe_id)).await?;
+        Ok(recipe.map(GqlRecipe))
+    }
+
+    async fn ingredient(&self, ctx: &Context<'_>) -> GqlResult<Option<GqlIngredient>> {
+        let ingredient = loader(ctx)?
+            .load_one(IngredientId(self.0.ingredient_id))
+            .await?;
+        Ok(ingredient.map(GqlIngredient))
+    }
+}
+
+pub struct GqlIngredient(Ingredient);
+
+#[Object(name = "Ingredient")]
+impl GqlIngredient {
+    async fn id(&self) -> i64 {
+        self.0.id
+    }
+    async fn name(&self) -> &str {
+        &self.0.name
+    }
+    async fn quantity(&self) -> &str {
+        &self.0.quantity
+    }
+    /// bumped on every update, the `version` of the conditional mutations
+    async fn version(&self) -> i64 {
+        self.0.version
+    }
+
+    /// recipes using this ingredient, newest first
+    #[graphql(complexity = "10 * child_complexity")]
+    async fn recipes(&self, ctx: &Context<'_>) -> GqlResult<Vec<GqlRecipe>> {
+        let loader = loader(ctx)?;
+        let recipe_ids = loader
+            .load_one(RecipesOfIngredient(self.0.id))
+            .await?
+            .unwrap_or_default();
+        let mut recipes = loader
+            .load_many(recipe_ids.iter().copied().map(RecipeId))
+            .await?;
+        Ok(recipe_ids
+            .iter()
+            .filter_map(|id| recipes.remove(&RecipeId(*id)))
+            .map(GqlRecipe)
+            .collect())
+    }
+}
+
+#[derive(Enum, Default, Clone, Copy, PartialEq, Eq)]
+#[graphql(name = "RecipeSort", remote = "RecipeSort")]
+pub enum GqlRecipeSort {
+    /// most recently created first
+    #[default]
+    Newest,
+    /// best rated first, unrated recipes last
+    Rating,
+}
+
+/// Properties left out are untouched, `null` clears them.
+#[derive(InputObject)]
+pub struct IngredientInput {
+    name: MaybeUndefined<String>,
+    quantity: MaybeUndefined<String>,
+}
+
+impl From<IngredientInput> for IngredientPatch {
+    fn from(input: IngredientInput) -> Self {
+        IngredientPatch {
+            name: patch_value(input.name),
+            quantity: patch_value(input.quantity),
+        }
+    }
+}
+
+/// Properties left out are untouched, `null` clears them. `ingredients` replaces all of them when present.
+#[derive(InputObject)]
+pub struct RecipeInput {
+    title: MaybeUndefined<String>,
+    cid: MaybeUndefined<i64>,
+    ingredients: MaybeUndefined<Vec<RecipeIngredientInput>>,
+}
+
+impl From<RecipeInput> for RecipePatch {
+    fn from(input: RecipeInput) -> Self {
+        let ingredients = patch_value(input.ingredients);
+        RecipePatch {
+            recipe_patch: RecipePatchInner {
+                title: patch_value(input.title),
+                cid: patch_value(input.cid),
+            },
+            ingredients: match ingredients {
+                PatchValue::Value(links) => {
+                    PatchValue::Value(links.into_iter().map(Into::into).collect())
+                }
+                PatchValue::Null => PatchValue::Null,
+                PatchValue::Absent => PatchValue::Absent,
+            },
+        }
+    }
+}
+
+#[derive(InputObject)]
+pub struct RecipeIngredientInput {
+    ingredient_id: i64,
+    ingredient_name: String,
+    quantity: String,
+}
+
+impl From<RecipeIngredientInput> for RecipeIngredientPatch {
+    fn from(input: RecipeIngredientInput) -> Self {
+        RecipeIngredientPatch {
+            ingredient_id: input.ingredient_id,
+            ingredient_name: input.ingredient_name,
+            quantity: input.quantity,
+        }
+    }
+}
+// endregion: Types
+
+// region: Loader
+/// Batches the relation lookups of a query into one SQL query per relation (no N+1).
+pub struct GraphLoader {
+    db: Arc<Db>,
+    utx: UserCtx,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Hash)]
+pub struct RecipeId(i64);
+
+#[derive(Clone, Copy, PartialEq, Eq, Hash)]
+pub struct IngredientId(i64);
+
+/// Ingredient links of a recipe.
+#[derive(Clone, Copy, PartialEq, Eq, Hash)]
+pub struct LinksOfRecipe(i64);
+
+/// Ids of the recipes using an ingredient.
+#[derive(Clone, Copy, PartialEq, Eq, Hash)]
+pub struct RecipesOfIngredient(i64);
+
+impl Loader<RecipeId> for GraphLoader {
+    type Value = Recipe;
+    type Error = async_graphql::Error;
+
+    async fn load(&self, keys: &[RecipeId]) -> Result<HashMap<RecipeId, Recipe>, Self::Error> {
+        let ids: Vec<i64> = keys.iter().map(|key| key.0).collect();
+        let recipes = RecipeMac::get_many(&self.db, &self.utx, &ids)
+            .await
+            .map_err(gql_error)?;
+        Ok(recipes
+            .into_iter()
+            .map(|recipe| (RecipeId(recipe.id), recipe))
+            .collect())
+    }
+}
+
+impl Loader<IngredientId> for GraphLoader {
+    type Value = Ingredient;
+    type Error = async_graphql::Error;
+
+    async fn load(
+        &self,
+        keys: &[IngredientId],
+    ) -> Result<HashMap<IngredientId, Ingredient>, Self::Error> {
+        let ids: Vec<i64> = keys.iter().map(|key| key.0).collect();
+        let ingredients = IngredientMac::get_many(&self.db, &self.utx, &ids)
+            .await
+            .map_err(gql_error)?;
+        Ok(ingredients
+            .into_iter()
+            .map(|ingredient| (IngredientId(ingredient.id), ingredient))
+            .collect())
+    }
+}
+
+impl Loader<LinksOfRecipe> for GraphLoader {
+    type Value = Vec<RecipeIngredientMac>;
+    type Error = async_graphql::Error;
+
+    async fn load(
+        &self,
+        keys: &[LinksOfRecipe],
+    ) -> Result<HashMap<LinksOfRecipe, Self::Value>, Self::Error> {
+        let ids: Vec<i64> = keys.iter().map(|key| key.0).collect();
+        let links = RecipeIngredientMac::list_by_recipe_ids(&self.db, &ids)
+            .await
+            .map_err(|ex| gql_error(ex.into()))?;
+        let mut by_recipe: HashMap<LinksOfRecipe, Self::Value> = HashMap::new();
+        for link in links {
+            by_recipe
+                .entry(LinksOfRecipe(link.recipe_id))
+                .or_default()
+                .push(link);
+        }
+        Ok(by_recipe)
+    }
+}
+
+impl Loader<RecipesOfIngredient> for GraphLoader {
+    type Value = Vec<i64>;
+    type Error = async_graphql::Error;
+
+    async fn load(
+        &self,
+        keys: &[RecipesOfIngredient],
+    ) -> Result<HashMap<RecipesOfIngredient, Self::Value>, Self::Error> {
+        let ids: Vec<i64> = keys.iter().map(|key| key.0).collect();
+        let links = RecipeIngredientMac::list_by_ingredient_ids(&self.db, &ids)
+            .await
+            .map_err(|ex| gql_error(ex.into()))?;
+        let mut by_ingredient: HashMap<RecipesOfIngredient, Self::Value> = HashMap::new();
+        for link in links {
+            by_ingredient
+                .entry(RecipesOfIngredient(link.ingredient_id))
+                .or_default()
+                .push(link.recipe_id);
+        }
+        Ok(by_ingredient)
+    }
+}
+// endregion: Loader
+
+// region: Utils
+fn db_utx<'a>(ctx: &'a Context<'_>) -> GqlResult<(&'a Db, &'a UserCtx)> {
+    let db = ctx.data::<Arc<Db>>()?;
+    let utx = ctx.data::<UserCtx>()?;
+    Ok((db, utx))
+}
+
+fn loader<'a>(ctx: &'a Context<'_>) -> GqlResult<&'a DataLoader<GraphLoader>> {
+    Ok(ctx.data::<Arc<DataLoader<GraphLoader>>>()?)
+}
+
+/// Same statuses as the REST rejections, as an `extensions.code` (and `extensions.fields` on validation).
+/// Note: sql, io and security errors keep their details server side, as in `handle_rejection`.
+fn gql_error(ex: model::Error) -> async_graphql::Error {
+    let code = match &ex {
+        model::Error::EntityNotFound(_, _) => "NOT_FOUND",
+        model::Error::VersionMismatch(_, _) => "VERSION_MISMATCH",
+        model::Error::ValidationFailed(_) | model::Error::PatchNullNotAllowed(_, _) => {
+            "VALIDATION_FAILED"
+        }
+        model::Error::AccessDenied(_, _) => "ACCESS_DENIED",
+        model::Error::SqlxError(_) | model::Error::IOError(_) | model::Error::SecurityError(_) => {
+            "INTERNAL"
+        }
+    };
+    let message = match code {
+        "INTERNAL" => {
+            tracing::warn!(cause = %ex, "graphql request failed");
+            "model::Error".to_string()
+        }
+        _ => ex.to_string(),
+    };
+    let fields = match ex {
+        model::Error::ValidationFailed(fields) => serde_json::to_value(fields).ok(),
+        _ => None,
+    };
+
+    async_graphql::Error::new(message).extend_with(|_, extensions| {
+        extensions.set("code", code);
+        if let Some(fields) = fields
+            .clone()
+            .and_then(|f| async_graphql::Value::from_json(f).ok())
+        {
+            extensions.set("fields", fields);
+        }
+    })
+}
+
+fn patch_value<T>(value: MaybeUndefined<T>) -> PatchValue<T> {
+    match value {
+        MaybeUndefined::Undefined => PatchValue::Absent,
+        MaybeUndefined::Null => PatchValue::Null,
+        MaybeUndefined::Value(value) => PatchValue::Value(value),
+    }
+}
+// endregion: Utils
+
+// region: Test
+#[cfg(test)]
+#[path = "../_tests/web_graphql.rs"]
+mod tests;
+// endregion: Test
@@ -0,0 +1,63 @@
+use crate::model::{pending_migrations, ping, Db};
+use serde_json::json;
+use std::sync::Arc;
+use warp::http::StatusCode;
+use warp::Filter;
+
+use super::filter_utils::with_db;
+
+/// `/health/live` and `/health/ready`, at the root and without auth (for the orchestrator probes).
+pub fn health_filters(
+    db: Arc<Db>,
+) -> impl Filter<Extract = impl warp::Reply, Error = warp::Rejection> + Clone {
+    let health_path = warp::path("health");
+
+    /// LIVENESS 'GET /health/live' - the process is up and serving
+    let live = health_path
+        .and(warp::path("live"))
+        .and(warp::get())
+        .and(warp::path::end())
+        .map(|| warp::reply::json(&json!({"status": "ok"})));
+
+    /// READINESS 'GET /health/ready' - the db answers and is migrated
+    let ready = health_path
+        .and(warp::path("ready"))
+        .and(warp::get())
+        .and(warp::path::end())
+        .and(with_db(db))
+        .and_then(health_ready);
+
+    live.or(ready)
+}
+
+async fn health_ready(db: Arc<Db>) -> Result<impl warp::Reply, warp::Rejection> {
+    let db_status = match ping(&db).await {
+        Ok(_) => "ok".to_string(),
+        Err(ex) => ex.to_string(),
+    };
+    let (migrations_status, pending) = match pending_migrations(&db).await {
+        Ok(pending) if pending.is_empty() => ("ok".to_string(), pending),
+        Ok(pending) => ("pending".to_string(), pending),
+        Err(ex) => (ex.to_string(), Vec::new()),
+    };
+
+    let is_ready = db_status == "ok" && migrations_status == "ok";
+    let body = json!({
+        "status": if is_ready { "ready" } else { "not_ready" },
+        "db": db_status,
+        "migrations": {"status": migrations_status, "pending": pending},
+    });
+    let status = if is_ready {
+        StatusCode::OK
+    } else {
+        StatusCode::SERVICE_UNAVAILABLE
+    };
+
+    Ok(warp::reply::with_status(warp::reply::json(&body), status))
+}
+
+// region: Test
+#[cfg(test)]
+#[path = "../_tests/web_health.rs"]
+mod tests;
+// endregion: Test
@@ -1,26 +1,28 @@
-use crate::model::{Db, IngredientMac, IngredientPatch};
-use crate::security::{utx_from_token, UserCtx};
+use crate::model::{IngredientPatch, IngredientRepo};
+use crate::security::UserCtx;
 use serde::Serialize;
 use serde_json::json;
-use std::{convert::Infallible, sync::Arc};
-use warp::reply::Json;
-use warp::{Filter, Rejection};
+use std::sync::Arc;
+use warp::reply::{Json, Response};
+use warp::{Filter, Rejection, Reply};
 
 use super::filter_auth::do_auth;
-use super::filter_utils::with_db;
+use super::filter_etag::{etag, if_match, list_conditions, list_response, ListConditions};
+use super::filter_utils::with_repo;
 
 pub fn ingredient_rest_filters(
     base_path: &'static str,
-    db: Arc<Db>,
+    repo: Arc<dyn IngredientRepo>,
 ) -> impl Filter<Extract = impl warp::Reply, Error = warp::Rejection> + Clone {
     let ingredients_path = warp::path(base_path).and(warp::path("ingredients"));
-    let common = with_db(db.clone()).and(do_auth(db.clone()));
+    let common = with_repo(repo).and(do_auth());
 
     /// LIST ingredients 'GET ingredients/'
     let list = ingredients_path
         .and(warp::get())
         .and(warp::path::end())
         .and(common.clone())
+        .and(list_conditions())
         .and_then(ingredient_list);
 
     /// GET ingredient 'GET /ingredients/1000'
@@ -42,6 +44,7 @@ pub fn ingredient_rest_filters(
         .and(warp::patch())
         .and(common.clone())
         .and(warp::path::param())
+        .and(if_match())
         .and(warp::body::json())
         .and_then(ingredient_update);
 
@@ -50,43 +53,80 @@ pub fn ingredient_rest_filters(
         .and(warp::delete())
         .and(common.clone())
         .and(warp::path::param())
+        .and(if_match())
         .and_then(ingredient_delete);
 
     list.or(get).or(create).or(update).or(delete)
 }
 
-async fn ingredient_list(db: Arc<Db>, utx: UserCtx) -> Result<Json, warp::Rejection> {
+async fn ingredient_list(
+    repo: Arc<dyn IngredientRepo>,
+    utx: UserCtx,
+    conditions: ListConditions,
+) -> Result<Response, warp::Rejection> {
     // FIXME: Add proper error handling
-    let ingredients = IngredientMac::list(&db, &utx).await?;
-    json_response(ingredients)
+    let ingredients = repo.list(&utx).await?;
+    // no mtime on the ingredients, the ETag only
+    Ok(list_response(ingredients, None, conditions))
 }
 
-async fn ingredient_get(db: Arc<Db>, utx: UserCtx, id: i64) -> Result<Json, warp::Rejection> {
-    let ingredient = IngredientMac::get(&db, &utx, id).await?;
-    json_response(ingredient)
+async fn ingredient_get(
+    repo: Arc<dyn IngredientRepo>,
+    utx: UserCtx,
+    id: i64,
+) -> Result<impl Reply, warp::Rejection> {
+    let ingredient = repo.get(&utx, id).await?;
+    let etag = etag(ingredient.version);
+    Ok(warp::reply::with_header(
+        json_response(ingredient)?,
+        "ETag",
+        etag,
+    ))
 }
 
 async fn ingredient_create(
-    db: Arc<Db>,
+    repo: Arc<dyn IngredientRepo>,
     utx: UserCtx,
     patch: IngredientPatch,
-) -> Result<Json, warp::Rejection> {
-    let ingredient = IngredientMac::create(&db, &utx, patch).await?;
-    json_response(ingredient)
+) -> Result<impl Reply, warp::Rejection> {
+    let ingredient = repo.create(&utx, patch).await?;
+    let etag = etag(ingredient.version);
+    Ok(warp::reply::with_header(
+        json_response(ingredient)?,
+        "ETag",
+        etag,
+    ))
 }
 
 async fn ingredient_update(
-    db: Arc<Db>,
+    repo: Arc<dyn IngredientRepo>,
     utx: UserCtx,
     id: i64,
+    if_match: Option<i64>,
     patch: IngredientPatch,
-) -> Result<Json, warp::Rejection> {
-    let ingredient = IngredientMac::update(&db, &utx, id, patch).await?;
-    json_response(ingredient)
+) -> Result<impl Reply, warp::Rejection> {
+    let ingredient = match if_match {
+        Some(version) => repo.update_if_match(&utx, id, version, patch).await?,
+        None => repo.update(&utx, id, patch).await?,
+    };
+    let etag = etag(ingredient.version);
+    Ok(warp::reply::with_header(
+        json_response(ingredient)?,
+        "ETag",
+        etag,
+    ))
 }
 
-async fn ingredient_delete(db: Arc<Db>, utx: UserCtx, id: i64) -> Result<Json, warp::Rejection> {
-    let ingredient = IngredientMac::delete(&db, &utx, id).await?;
+async fn ingredient_delete(
+    repo: Arc<dyn IngredientRepo>,
+    utx: UserCtx,
+    id: i64,
+    if_match: Option<i64>,
+) -> Result<Json, warp::Rejection> {
+    let ingredient = match if_match {
+        Some(version) => repo.delete_if_match(&utx, id, version).await?,
+        None => repo.delete(&utx, id).await?,
+    };
     json_response(ingredient)
 }
 
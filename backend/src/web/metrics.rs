@@ -0,0 +1,75 @@
+use crate::metrics;
+use crate::model::Db;
+use std::sync::Arc;
+use warp::http::StatusCode;
+use warp::Filter;
+
+use super::filter_utils::with_db;
+use super::WebErrorMessage;
+
+/// `/metrics` for Prometheus, at the root (not under /api).
+/// Not served when `enabled` is false, and requires `Authorization: Bearer <token>` when a token is set.
+pub fn metrics_filters(
+    db: Arc<Db>,
+    enabled: bool,
+    token: Option<String>,
+) -> impl Filter<Extract = impl warp::Reply, Error = warp::Rejection> + Clone {
+    let token = Arc::new(token);
+
+    /// SCRAPE 'GET /metrics'
+    warp::path("metrics")
+        .and(warp::get())
+        .and(warp::path::end())
+        .and_then(move || async move {
+            if enabled {
+                Ok(())
+            } else {
+                Err(warp::reject::not_found())
+            }
+        })
+        .untuple_one()
+        .and(warp::header::optional::<String>("Authorization"))
+        .and_then(move |authorization: Option<String>| {
+            let token = token.clone();
+            async move { check_token(token.as_deref(), authorization.as_deref()) }
+        })
+        .untuple_one()
+        .and(with_db(db))
+        .and_then(metrics_scrape)
+}
+
+fn check_token(token: Option<&str>, authorization: Option<&str>) -> Result<(), warp::Rejection> {
+    let token = match token {
+        Some(token) => token,
+        None => return Ok(()),
+    };
+    match authorization.and_then(|value| value.strip_prefix("Bearer ")) {
+        Some(bearer) if bearer == token => Ok(()),
+        _ => Err(WebErrorMessage::rejection_with_status(
+            "web::Error",
+            "Fail authentication, missing or wrong metrics token.".to_string(),
+            StatusCode::UNAUTHORIZED,
+        )),
+    }
+}
+
+async fn metrics_scrape(db: Arc<Db>) -> Result<impl warp::Reply, warp::Rejection> {
+    let body = metrics::render(&db).await.map_err(|ex| {
+        WebErrorMessage::rejection_with_status(
+            "metrics::Error",
+            ex.to_string(),
+            StatusCode::INTERNAL_SERVER_ERROR,
+        )
+    })?;
+    Ok(warp::reply::with_header(
+        body,
+        "Content-Type",
+        "text/plain; version=0.0.4",
+    ))
+}
+
+// region: Test
+#[cfg(test)]
+#[path = "../_tests/web_metrics.rs"]
+mod tests;
+// endregion: Test
@@ -1,71 +1,277 @@
+use audit::audit_rest_filters;
+use change_feed::change_feed_rest_filters;
+use collection::collection_rest_filters;
+use favorite::favorite_rest_filters;
+use filter_auth::do_auth;
+use filter_cache::with_static_cache;
+use filter_compress::with_compression;
+use filter_policy::{body_limit, with_policy, Policy};
+use filter_rate_limit::{rate_limit_api, RateLimited, RateLimiter};
+use graphql::graphql_filters;
+use health::health_filters;
+use hyper::service::{make_service_fn, service_fn, Service};
+use hyper::{Body, Request, Response};
 use ingredient::ingredient_rest_filters;
+use metrics::metrics_filters;
+use openapi::openapi_filters;
+use recipe::recipe_rest_filters;
+use recipe_comment::recipe_comment_rest_filters;
+use recipe_review::recipe_review_rest_filters;
+use recipe_revision::recipe_revision_rest_filters;
 use serde_json::json;
-use warp::{reject::Rejection, reply::Reply, Filter};
+use share::share_rest_filters;
+use tracing::{debug, info, warn};
+use trash::trash_rest_filters;
+use warp::{
+    http::{HeaderValue, StatusCode},
+    reject::Rejection,
+    reply::Reply,
+    Filter,
+};
+use ws::ws_filters;
 
 use crate::{
-    model::{self, Db},
+    config::Config,
+    model::{self, Db, DbRepo},
     security,
+    shutdown::Shutdown,
+};
+use std::{
+    convert::Infallible, future::Future, net::SocketAddr, path::Path, sync::Arc, time::Duration,
 };
-use std::{convert::Infallible, path::Path, sync::Arc};
 
+mod audit;
+mod change_feed;
+mod collection;
+mod favorite;
 mod filter_auth;
+mod filter_cache;
+mod filter_compress;
+mod filter_etag;
+mod filter_policy;
+mod filter_rate_limit;
 mod filter_utils;
+mod graphql;
+mod health;
 mod ingredient;
+mod metrics;
+mod openapi;
 mod recipe;
+mod recipe_comment;
+mod recipe_review;
+mod recipe_revision;
+mod share;
+mod trace;
+mod trash;
+mod ws;
 
-pub async fn start_web(web_folder: &str, web_port: u16, db: Arc<Db>) -> Result<(), Error> {
+pub async fn start_web(
+    web_folder: &str,
+    web_port: u16,
+    db: Arc<Db>,
+    config: &Config,
+    shutdown: Shutdown,
+) -> Result<(), Error> {
     // validate web_folder
     if !Path::new(web_folder).exists() {
         return Err(Error::FailStartWebFolderNotFound(web_folder.to_string()));
     }
 
-    // APIs
-    let apis = ingredient_rest_filters("api", db);
+    // APIs (recipes and ingredients through their repositories), throttled per user and per client IP
+    let repo = Arc::new(DbRepo::new(db.clone()));
+    let limiter = Arc::new(RateLimiter::new(
+        config.rate_limit_read,
+        config.rate_limit_write,
+        config.rate_limit_auth,
+    ));
+    let limited_apis = rate_limit_api("api", limiter, do_auth()).and(
+        ingredient_rest_filters("api", repo.clone())
+            .or(recipe_rest_filters("api", repo))
+            .or(recipe_revision_rest_filters("api", db.clone()))
+            .or(recipe_review_rest_filters("api", db.clone()))
+            .or(recipe_comment_rest_filters("api", db.clone()))
+            .or(favorite_rest_filters("api", db.clone()))
+            .or(collection_rest_filters("api", db.clone()))
+            .or(share_rest_filters("api", db.clone()))
+            .or(change_feed_rest_filters("api", db.clone()))
+            .or(trash_rest_filters("api", db.clone()))
+            .or(audit_rest_filters("api", db.clone()))
+            .or(graphql_filters(
+                "api",
+                db.clone(),
+                config.graphql_max_depth,
+                config.graphql_max_complexity,
+            )),
+    );
+    // the WebSocket (one long lived request) and the docs are not throttled
+    let apis = ws_filters("api", shutdown.clone())
+        .or(openapi_filters("api"))
+        .or(limited_apis);
+    let metrics = metrics_filters(
+        db.clone(),
+        config.metrics_enabled,
+        config.metrics_token.clone(),
+    );
+    let health = health_filters(db);
 
-    // Static content
+    // Static content, fingerprinted files cached for long, the others (index.html first) revalidated
     let content = warp::fs::dir(web_folder.to_string());
     let root_index = warp::get()
         .and(warp::path::end())
         .and(warp::fs::file(format!("{}/index.html", web_folder)));
-    let static_site = content.or(root_index);
+    let static_site = with_static_cache(config.static_max_age_secs, content.or(root_index));
+
+    // Combine all routes, with the CORS and security headers on every reply (errors included)
+    let routes = body_limit(config.max_body_bytes)
+        .and(apis.or(metrics).or(health).or(static_site))
+        .recover(handle_rejection);
+    let policy = Arc::new(Policy {
+        cors_allowed_origins: config.cors_allowed_origins.clone(),
+        cors_allowed_methods: config.cors_allowed_methods.clone(),
+        content_security_policy: config.content_security_policy.clone(),
+    });
+    let routes = with_policy(policy, routes);
+    let routes = with_compression(
+        config.compression_enabled,
+        config.compression_min_bytes,
+        routes,
+    );
 
-    // Combine all routes
-    let routes = apis.or(static_site).recover(handle_rejection);
+    // Every request goes through the tracing layer (request id, span, latency)
+    let addr = ([127, 0, 0, 1], web_port).into();
+    let (addr, server) = bind(
+        warp::service(routes),
+        addr,
+        shutdown,
+        config.shutdown_drain_timeout,
+    )?;
+    info!(%addr, web_folder, "start server");
+    server.await
+}
+
+/// Bind `addr` and return the bound address (e.g. for port 0) along with the server future.
+/// Once `shutdown` is triggered, no new connection is accepted and the in-flight requests
+/// get up to `drain_timeout` to complete before being cut off.
+pub fn bind<S>(
+    svc: S,
+    addr: SocketAddr,
+    shutdown: Shutdown,
+    drain_timeout: Duration,
+) -> Result<(SocketAddr, impl Future<Output = Result<(), Error>>), Error>
+where
+    S: Service<Request<Body>, Response = Response<Body>, Error = Infallible>
+        + Clone
+        + Send
+        + 'static,
+    S::Future: Send,
+{
+    let make_svc = make_service_fn(move |_| {
+        let svc = svc.clone();
+        async move { Ok::<_, Infallible>(service_fn(move |req| trace::traced(svc.clone(), req))) }
+    });
 
-    println!("Start 127.0.0.1:{} at {}", web_port, web_folder);
-    warp::serve(routes).run(([127, 0, 0, 1], web_port)).await;
+    let builder = hyper::Server::try_bind(&addr).map_err(|ex| Error::FailServe(ex.to_string()))?;
+    let server = builder.serve(make_svc);
+    let addr = server.local_addr();
+    let drain_shutdown = shutdown.clone();
+    let server = server.with_graceful_shutdown(async move { shutdown.wait().await });
 
-    Ok(())
+    let serve = async move {
+        tokio::pin!(server);
+        tokio::select! {
+            result = &mut server => {
+                result.map_err(|ex| Error::FailServe(ex.to_string()))?;
+                info!("server drained");
+            }
+            _ = async {
+                drain_shutdown.wait().await;
+                tokio::time::sleep(drain_timeout).await;
+            } => {
+                warn!(drain_timeout_ms = drain_timeout.as_millis() as u64, "server drain timed out, dropping the in-flight requests");
+            }
+        }
+        Ok(())
+    };
+
+    Ok((addr, serve))
 }
 
 async fn handle_rejection(err: Rejection) -> Result<impl Reply, Infallible> {
-    // Print to server side
-    println!("ERROR - {:?}", err);
-
-    // TODO - Call log API to capture and store
+    // Log to server side (within the request span)
+    match (err.find::<WebErrorMessage>(), err.find::<RateLimited>()) {
+        (Some(err), _) if err.status.is_server_error() => warn!(
+            typ = err.typ,
+            status = err.status.as_u16(),
+            message = %err.message,
+            "request failed"
+        ),
+        (Some(err), _) => info!(
+            typ = err.typ,
+            status = err.status.as_u16(),
+            message = %err.message,
+            "request rejected"
+        ),
+        (None, Some(limited)) => info!(
+            class = ?limited.class,
+            retry_after = limited.retry_after,
+            "request rate limited"
+        ),
+        (None, None) => debug!(?err, "request rejected"),
+    }
 
     // Build user message
-    let user_message = match err.find::<WebErrorMessage>() {
-        Some(err) => err.typ.to_string(),
-        None => "Unknown".to_string(),
+    let user_message = match (err.find::<WebErrorMessage>(), err.find::<RateLimited>()) {
+        (Some(err), _) => err.typ.to_string(),
+        (None, Some(_)) => "web::RateLimited".to_string(),
+        (None, None) => "Unknown".to_string(),
     };
 
-    let result = json!({ "errorMessage": user_message });
-    let result = warp::reply::json(&result);
+    let status = match (err.find::<WebErrorMessage>(), err.find::<RateLimited>()) {
+        (Some(err), _) => err.status,
+        (None, Some(_)) => StatusCode::TOO_MANY_REQUESTS,
+        (None, None) => StatusCode::BAD_REQUEST,
+    };
 
-    Ok(warp::reply::with_status(
-        result,
-        warp::http::StatusCode::BAD_REQUEST,
-    ))
+    let mut result = json!({ "errorMessage": user_message });
+    if let Some(err) = err.find::<WebErrorMessage>() {
+        if !err.fields.is_empty() {
+            result["errorFields"] = json!(err.fields);
+        }
+    }
+    let mut response = warp::reply::with_status(warp::reply::json(&result), status).into_response();
+
+    // when to retry, for the rate limited ones
+    if let (None, Some(limited)) = (err.find::<WebErrorMessage>(), err.find::<RateLimited>()) {
+        for (name, value) in limited.headers() {
+            if let Ok(value) = HeaderValue::from_str(&value) {
+                response.headers_mut().insert(name, value);
+            }
+        }
+    }
+
+    Ok(response)
 }
 
+#[allow(clippy::enum_variant_names)]
 #[derive(thiserror::Error, Debug)]
 pub enum Error {
     #[error("Web server failed to start because web-folder '{0}' not found.")]
     FailStartWebFolderNotFound(String),
 
+    #[error("Web server failed while serving. Cause: {0}")]
+    FailServe(String),
+
     #[error("Fail authentication missing X-Auth-Token header.")]
     FailAuthMissingXAuth,
+
+    #[error("Fail precondition, If-Match '{0}' is not a valid ETag.")]
+    FailPreconditionIfMatch(String),
+
+    #[error("Fail request body of {0} bytes, over the {1} bytes limit.")]
+    FailBodyTooLarge(u64, u64),
+
+    #[error("Fail request body without a Content-Length.")]
+    FailBodyLengthRequired,
 }
 
 // region: Warp Custom Error
@@ -73,24 +279,62 @@ pub enum Error {
 pub struct WebErrorMessage {
     pub typ: &'static str,
     pub message: String,
+    pub status: StatusCode,
+    /// field level errors of a request body that failed validation
+    pub fields: Vec<model::FieldError>,
 }
 impl warp::reject::Reject for WebErrorMessage {}
 
 impl WebErrorMessage {
     pub fn rejection(typ: &'static str, message: String) -> warp::Rejection {
-        warp::reject::custom(WebErrorMessage { typ, message })
+        Self::rejection_with_status(typ, message, StatusCode::BAD_REQUEST)
+    }
+
+    pub fn rejection_with_status(
+        typ: &'static str,
+        message: String,
+        status: StatusCode,
+    ) -> warp::Rejection {
+        warp::reject::custom(WebErrorMessage {
+            typ,
+            message,
+            status,
+            fields: Vec::new(),
+        })
     }
 }
 
 impl From<self::Error> for warp::Rejection {
     fn from(other: self::Error) -> Self {
-        WebErrorMessage::rejection("web::Error", format!("{}", other))
+        let status = match other {
+            Error::FailPreconditionIfMatch(_) => StatusCode::PRECONDITION_FAILED,
+            Error::FailBodyTooLarge(_, _) => StatusCode::PAYLOAD_TOO_LARGE,
+            Error::FailBodyLengthRequired => StatusCode::LENGTH_REQUIRED,
+            _ => StatusCode::BAD_REQUEST,
+        };
+        WebErrorMessage::rejection_with_status("web::Error", format!("{}", other), status)
     }
 }
 
 impl From<model::Error> for warp::Rejection {
     fn from(other: model::Error) -> Self {
-        WebErrorMessage::rejection("model::Error", format!("{}", other))
+        let status = match other {
+            model::Error::VersionMismatch(_, _) => StatusCode::PRECONDITION_FAILED,
+            model::Error::ValidationFailed(_) => StatusCode::UNPROCESSABLE_ENTITY,
+            model::Error::AccessDenied(_, _) => StatusCode::FORBIDDEN,
+            _ => StatusCode::BAD_REQUEST,
+        };
+        let message = format!("{}", other);
+        let fields = match other {
+            model::Error::ValidationFailed(fields) => fields,
+            _ => Vec::new(),
+        };
+        warp::reject::custom(WebErrorMessage {
+            typ: "model::Error",
+            message,
+            status,
+            fields,
+        })
     }
 }
 
@@ -100,3 +344,9 @@ impl From<security::Error> for warp::Rejection {
     }
 }
 //endregion: Warp Custom Error
+
+// region: Test
+#[cfg(test)]
+#[path = "../_tests/web_bind.rs"]
+mod tests;
+// endregion: Test
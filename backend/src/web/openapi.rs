@@ -0,0 +1,385 @@
+use serde_json::{json, Value};
+use warp::Filter;
+
+/// Docs UI, a single page rendering the spec (no CDN, works offline).
+const DOCS_HTML: &str = include_str!("openapi_docs.html");
+const DOCS_CSP: &str =
+    "default-src 'self'; script-src 'unsafe-inline'; style-src 'unsafe-inline'; frame-ancestors 'none'";
+
+/// `/api/openapi.json` and the `/api/docs` UI, without auth (the spec is public).
+pub fn openapi_filters(
+    base_path: &'static str,
+) -> impl Filter<Extract = impl warp::Reply, Error = warp::Rejection> + Clone {
+    let base = warp::path(base_path);
+
+    /// SPEC 'GET /api/openapi.json'
+    let spec = base
+        .and(warp::path("openapi.json"))
+        .and(warp::get())
+        .and(warp::path::end())
+        .map(move || warp::reply::json(&openapi_spec(base_path)));
+
+    /// DOCS 'GET /api/docs'
+    let docs = base
+        .and(warp::path("docs"))
+        .and(warp::get())
+        .and(warp::path::end())
+        .map(|| {
+            // the page is a single file, with its inline script and style
+            warp::reply::with_header(
+                warp::reply::html(DOCS_HTML),
+                "Content-Security-Policy",
+                DOCS_CSP,
+            )
+        });
+
+    spec.or(docs)
+}
+
+/// OpenAPI 3.1 document of `ingredient_rest_filters` and `recipe_rest_filters`.
+/// Note: written by hand, `web_openapi_routes_match_spec` fails when it drifts from the filters.
+pub fn openapi_spec(base_path: &str) -> Value {
+    let ingredients = format!("/{}/ingredients", base_path);
+    let recipes = format!("/{}/recipes", base_path);
+
+    json!({
+        "openapi": "3.1.0",
+        "info": {
+            "title": "Cookbook API",
+            "version": env!("CARGO_PKG_VERSION"),
+            "description": "Every response body is `{\"data\": ...}`, or the error body. \
+                Properties missing from a patch are left untouched, `null` clears them.",
+        },
+        "security": [{"authToken": []}],
+        "tags": [
+            {"name": "ingredients"},
+            {"name": "recipes", "description": "A recipe comes with its ingredients, as `[recipe, ingredients]`."},
+        ],
+        "paths": {
+            ingredients.clone(): {
+                "get": {
+                    "tags": ["ingredients"],
+                    "operationId": "ingredient_list",
+                    "summary": "List the ingredients, newest first",
+                    "parameters": [if_none_match_param()],
+                    "responses": {
+                        "200": list_data_response(json!({"type": "array", "items": schema_ref("Ingredient")}), false),
+                        "304": not_modified_response(),
+                        "default": error_response(),
+                    },
+                },
+                "post": {
+                    "tags": ["ingredients"],
+                    "operationId": "ingredient_create",
+                    "summary": "Create an ingredient",
+                    "requestBody": request_body("IngredientPatch", json!({"name": "basil", "quantity": "1 bunch"})),
+                    "responses": {
+                        "200": data_response(schema_ref("Ingredient"), true),
+                        "422": error_response(),
+                        "default": error_response(),
+                    },
+                },
+            },
+            format!("{}/{{id}}", ingredients): {
+                "parameters": [id_param()],
+                "get": {
+                    "tags": ["ingredients"],
+                    "operationId": "ingredient_get",
+                    "summary": "Get an ingredient",
+                    "responses": {
+                        "200": data_response(schema_ref("Ingredient"), true),
+                        "default": error_response(),
+                    },
+                },
+                "patch": {
+                    "tags": ["ingredients"],
+                    "operationId": "ingredient_update",
+                    "summary": "Update an ingredient",
+                    "parameters": [if_match_param()],
+                    "requestBody": request_body("IngredientPatch", json!({"quantity": "2 bunches"})),
+                    "responses": {
+                        "200": data_response(schema_ref("Ingredient"), true),
+                        "412": error_response(),
+                        "422": error_response(),
+                        "default": error_response(),
+                    },
+                },
+                "delete": {
+                    "tags": ["ingredients"],
+                    "operationId": "ingredient_delete",
+                    "summary": "Delete an ingredient (to the trash), along with its recipe links",
+                    "parameters": [if_match_param()],
+                    "responses": {
+                        "200": data_response(schema_ref("Ingredient"), false),
+                        "412": error_response(),
+                        "default": error_response(),
+                    },
+                },
+            },
+            recipes.clone(): {
+                "get": {
+                    "tags": ["recipes"],
+                    "operationId": "recipe_list",
+                    "summary": "List the recipes",
+                    "parameters": [{
+                        "name": "sort",
+                        "in": "query",
+                        "schema": {"type": "string", "enum": ["newest", "rating"], "default": "newest"},
+                        "description": "`rating` is best rated first, unrated recipes last",
+                    }, if_none_match_param(), if_modified_since_param()],
+                    "responses": {
+                        "200": list_data_response(json!({"type": "array", "items": schema_ref("RecipeWithIngredients")}), true),
+                        "304": not_modified_response(),
+                        "default": error_response(),
+                    },
+                },
+                "post": {
+                    "tags": ["recipes"],
+                    "operationId": "recipe_create",
+                    "summary": "Create a recipe",
+                    "requestBody": request_body("RecipePatch", json!({
+                        "recipe_patch": {"title": "tomato soup"},
+                        "ingredients": [{"ingredient_id": 1000, "ingredient_name": "tomatoes", "quantity": "400 g"}],
+                    })),
+                    "responses": {
+                        "200": data_response(schema_ref("RecipeWithIngredients"), true),
+                        "422": error_response(),
+                        "default": error_response(),
+                    },
+                },
+            },
+            format!("{}/{{id}}", recipes): {
+                "parameters": [id_param()],
+                "get": {
+                    "tags": ["recipes"],
+                    "operationId": "recipe_get",
+                    "summary": "Get a recipe",
+                    "responses": {
+                        "200": data_response(schema_ref("RecipeWithIngredients"), true),
+                        "default": error_response(),
+                    },
+                },
+                "patch": {
+                    "tags": ["recipes"],
+                    "operationId": "recipe_update",
+                    "summary": "Update a recipe, `ingredients` replaces all of them when present",
+                    "parameters": [if_match_param()],
+                    "requestBody": request_body("RecipePatch", json!({"recipe_patch": {"title": "cream of tomato"}})),
+                    "responses": {
+                        "200": data_response(schema_ref("RecipeWithIngredients"), true),
+                        "412": error_response(),
+                        "422": error_response(),
+                        "default": error_response(),
+                    },
+                },
+                "delete": {
+                    "tags": ["recipes"],
+                    "operationId": "recipe_delete",
+                    "summary": "Delete a recipe (to the trash)",
+                    "parameters": [if_match_param()],
+                    "responses": {
+                        "200": data_response(schema_ref("RecipeWithIngredients"), false),
+                        "412": error_response(),
+                        "default": error_response(),
+                    },
+                },
+            },
+        },
+        "components": {
+            "securitySchemes": {
+                "authToken": {"type": "apiKey", "in": "header", "name": "X-Auth-Token"},
+            },
+            "schemas": schemas(),
+        },
+    })
+}
+
+fn schemas() -> Value {
+    json!({
+        "Ingredient": {
+            "type": "object",
+            "required": ["id", "name", "quantity", "version"],
+            "properties": {
+                "id": {"type": "integer", "format": "int64"},
+                "name": {"type": "string"},
+                "quantity": {"type": "string"},
+                "version": {"type": "integer", "format": "int64", "description": "bumped on every update, used as the ETag"},
+            },
+        },
+        "IngredientPatch": {
+            "type": "object",
+            "properties": {
+                "name": {"type": "string", "minLength": 1, "maxLength": 255},
+                "quantity": {"type": "string", "minLength": 1, "maxLength": 50},
+            },
+        },
+        "Recipe": {
+            "type": "object",
+            "required": ["id", "title", "cid", "ctime", "mtime", "version", "rating_avg", "rating_count", "is_favorite"],
+            "properties": {
+                "id": {"type": "integer", "format": "int64"},
+                "title": {"type": "string"},
+                "cid": {"type": ["integer", "null"], "format": "int64", "description": "owner"},
+                "ctime": {"type": "string", "format": "date-time"},
+                "mtime": {"type": "string", "format": "date-time"},
+                "version": {"type": "integer", "format": "int64", "description": "bumped on every update, used as the ETag"},
+                "rating_avg": {"type": ["number", "null"], "description": "average review rating, null until the first review"},
+                "rating_count": {"type": "integer", "format": "int64"},
+                "is_favorite": {"type": "boolean", "description": "favorite of the calling user"},
+            },
+        },
+        "RecipeIngredient": {
+            "type": "object",
+            "required": ["recipe_id", "ingredient_id", "ingredient_name", "quantity", "cid", "ctime", "mtime"],
+            "properties": {
+                "recipe_id": {"type": "integer", "format": "int64"},
+                "ingredient_id": {"type": "integer", "format": "int64"},
+                "ingredient_name": {"type": "string"},
+                "quantity": {"type": "string"},
+                "cid": {"type": "integer", "format": "int64"},
+                "ctime": {"type": "string", "format": "date-time"},
+                "mtime": {"type": "string", "format": "date-time"},
+            },
+        },
+        "RecipeWithIngredients": {
+            "type": "array",
+            "prefixItems": [schema_ref("Recipe"), {"type": "array", "items": schema_ref("RecipeIngredient")}],
+            "minItems": 2,
+            "maxItems": 2,
+        },
+        "RecipePatch": {
+            "type": "object",
+            "properties": {
+                "recipe_patch": {
+                    "type": "object",
+                    "properties": {
+                        "title": {"type": "string", "minLength": 1, "maxLength": 255},
+                        "cid": {"type": ["integer", "null"], "format": "int64", "description": "owner, the calling user when absent on create"},
+                    },
+                },
+                "ingredients": {
+                    "type": ["array", "null"],
+                    "items": schema_ref("RecipeIngredientPatch"),
+                    "description": "replaces all the recipe ingredients when present, null clears them",
+                },
+            },
+        },
+        "RecipeIngredientPatch": {
+            "type": "object",
+            "required": ["ingredient_id", "ingredient_name", "quantity"],
+            "properties": {
+                "ingredient_id": {"type": "integer", "format": "int64"},
+                "ingredient_name": {"type": "string", "minLength": 1, "maxLength": 255},
+                "quantity": {"type": "string", "minLength": 1, "maxLength": 50},
+            },
+        },
+        "FieldError": {
+            "type": "object",
+            "required": ["field", "reason"],
+            "properties": {
+                "field": {"type": "string", "description": "path of the property, e.g. \"ingredients[0].quantity\""},
+                "reason": {"type": "string"},
+            },
+        },
+        "Error": {
+            "type": "object",
+            "required": ["errorMessage"],
+            "properties": {
+                "errorMessage": {"type": "string", "description": "error type, e.g. \"model::Error\""},
+                "errorFields": {"type": "array", "items": schema_ref("FieldError"), "description": "only when the body failed validation"},
+            },
+        },
+    })
+}
+
+// region: Spec Utils
+fn schema_ref(name: &str) -> Value {
+    json!({ "$ref": format!("#/components/schemas/{}", name) })
+}
+
+fn data_response(data: Value, with_etag: bool) -> Value {
+    let mut response = json!({
+        "description": "OK",
+        "content": {"application/json": {"schema": {
+            "type": "object",
+            "required": ["data"],
+            "properties": {"data": data},
+        }}},
+    });
+    if with_etag {
+        response["headers"] = json!({
+            "ETag": {"schema": {"type": "string"}, "description": "version of the entity, for If-Match"},
+        });
+    }
+    response
+}
+
+/// `data_response` of a list, with its ETag and (`with_last_modified`) Last-Modified.
+fn list_data_response(data: Value, with_last_modified: bool) -> Value {
+    let mut response = data_response(data, false);
+    response["headers"] = json!({
+        "ETag": {"schema": {"type": "string"}, "description": "of the list content, for If-None-Match"},
+    });
+    if with_last_modified {
+        response["headers"]["Last-Modified"] = json!({
+            "schema": {"type": "string"},
+            "description": "latest change of the listed items, for If-Modified-Since",
+        });
+    }
+    response
+}
+
+fn not_modified_response() -> Value {
+    json!({"description": "Not Modified, the list is the one of If-None-Match (or If-Modified-Since)"})
+}
+
+fn error_response() -> Value {
+    json!({
+        "description": "Error",
+        "content": {"application/json": {"schema": schema_ref("Error")}},
+    })
+}
+
+fn request_body(schema: &str, example: Value) -> Value {
+    json!({
+        "required": true,
+        "content": {"application/json": {"schema": schema_ref(schema), "example": example}},
+    })
+}
+
+fn id_param() -> Value {
+    json!({"name": "id", "in": "path", "required": true, "schema": {"type": "integer", "format": "int64"}})
+}
+
+fn if_match_param() -> Value {
+    json!({
+        "name": "If-Match",
+        "in": "header",
+        "schema": {"type": "string"},
+        "description": "ETag of the version the change is based on, 412 when stale",
+    })
+}
+fn if_none_match_param() -> Value {
+    json!({
+        "name": "If-None-Match",
+        "in": "header",
+        "schema": {"type": "string"},
+        "description": "ETag of the list the caller has, 304 when unchanged",
+    })
+}
+
+fn if_modified_since_param() -> Value {
+    json!({
+        "name": "If-Modified-Since",
+        "in": "header",
+        "schema": {"type": "string"},
+        "description": "Last-Modified of the list the caller has, 304 when unchanged (ignored along with If-None-Match)",
+    })
+}
+// endregion: Spec Utils
+
+// region: Test
+#[cfg(test)]
+#[path = "../_tests/web_openapi.rs"]
+mod tests;
+// endregion: Test
@@ -1,26 +1,29 @@
-use crate::model::{Db, RecipeMac, RecipePatch};
-use crate::security::{utx_from_token, UserCtx};
-use serde::Serialize;
+use crate::model::{RecipePatch, RecipeRepo, RecipeSort};
+use crate::security::UserCtx;
+use serde::{Deserialize, Serialize};
 use serde_json::json;
-use std::{convert::Infallible, sync::Arc};
-use warp::reply::Json;
-use warp::{Filter, Rejection};
+use std::sync::Arc;
+use warp::reply::{Json, Response};
+use warp::{Filter, Rejection, Reply};
 
 use super::filter_auth::do_auth;
-use super::filter_utils::with_db;
+use super::filter_etag::{etag, if_match, list_conditions, list_response, ListConditions};
+use super::filter_utils::with_repo;
 
 pub fn recipe_rest_filters(
     base_path: &'static str,
-    db: Arc<Db>,
+    repo: Arc<dyn RecipeRepo>,
 ) -> impl Filter<Extract = impl warp::Reply, Error = warp::Rejection> + Clone {
     let recipes_path = warp::path(base_path).and(warp::path("recipes"));
-    let common = with_db(db.clone()).and(do_auth(db.clone()));
+    let common = with_repo(repo).and(do_auth());
 
-    /// LIST recipes 'GET /recipes'
+    /// LIST recipes 'GET /recipes' or 'GET /recipes?sort=rating'
     let list = recipes_path
         .and(warp::get())
         .and(warp::path::end())
         .and(common.clone())
+        .and(warp::query::<RecipeListParams>())
+        .and(list_conditions())
         .and_then(recipe_list);
 
     /// GET recipe 'GET /recipes/1000'
@@ -28,6 +31,7 @@ pub fn recipe_rest_filters(
         .and(warp::get())
         .and(common.clone())
         .and(warp::path::param())
+        .and(warp::path::end())
         .and_then(recipe_get);
 
     /// CREATE recipe 'POST /recipes with body RecipePatch'
@@ -42,6 +46,8 @@ pub fn recipe_rest_filters(
         .and(warp::patch())
         .and(common.clone())
         .and(warp::path::param())
+        .and(warp::path::end())
+        .and(if_match())
         .and(warp::body::json())
         .and_then(recipe_update);
 
@@ -50,43 +56,88 @@ pub fn recipe_rest_filters(
         .and(warp::delete())
         .and(common.clone())
         .and(warp::path::param())
+        .and(warp::path::end())
+        .and(if_match())
         .and_then(recipe_delete);
 
     list.or(get).or(create).or(update).or(delete)
 }
 
-async fn recipe_list(db: Arc<Db>, utx: UserCtx) -> Result<Json, warp::Rejection> {
+#[derive(Deserialize)]
+struct RecipeListParams {
+    #[serde(default)]
+    sort: RecipeSort,
+}
+
+async fn recipe_list(
+    repo: Arc<dyn RecipeRepo>,
+    utx: UserCtx,
+    params: RecipeListParams,
+    conditions: ListConditions,
+) -> Result<Response, warp::Rejection> {
     // FIXME: Add proper error handling
-    let recipes = RecipeMac::list(&db, &utx).await?;
-    json_response(recipes)
+    let recipes = repo.list_sorted(&utx, params.sort).await?;
+    let last_modified = recipes.iter().map(|(recipe, _)| recipe.mtime).max();
+    Ok(list_response(recipes, last_modified, conditions))
 }
 
-async fn recipe_get(db: Arc<Db>, utx: UserCtx, id: i64) -> Result<Json, warp::Rejection> {
-    let (recipe, ingredients) = RecipeMac::get(&db, &utx, id).await?;
-    json_response((recipe, ingredients))
+async fn recipe_get(
+    repo: Arc<dyn RecipeRepo>,
+    utx: UserCtx,
+    id: i64,
+) -> Result<impl Reply, warp::Rejection> {
+    let (recipe, ingredients) = repo.get(&utx, id).await?;
+    let etag = etag(recipe.version);
+    Ok(warp::reply::with_header(
+        json_response((recipe, ingredients))?,
+        "ETag",
+        etag,
+    ))
 }
 
 async fn recipe_create(
-    db: Arc<Db>,
+    repo: Arc<dyn RecipeRepo>,
     utx: UserCtx,
     patch: RecipePatch,
-) -> Result<Json, warp::Rejection> {
-    let recipe = RecipeMac::create(&db, &utx, patch).await?;
-    json_response(recipe)
+) -> Result<impl Reply, warp::Rejection> {
+    let recipe = repo.create(&utx, patch).await?;
+    let etag = etag(recipe.0.version);
+    Ok(warp::reply::with_header(
+        json_response(recipe)?,
+        "ETag",
+        etag,
+    ))
 }
 
 async fn recipe_update(
-    db: Arc<Db>,
+    repo: Arc<dyn RecipeRepo>,
     utx: UserCtx,
     id: i64,
+    if_match: Option<i64>,
     patch: RecipePatch,
-) -> Result<Json, warp::Rejection> {
-    let recipe = RecipeMac::update(&db, &utx, id, patch).await?;
-    json_response(recipe)
+) -> Result<impl Reply, warp::Rejection> {
+    let recipe = match if_match {
+        Some(version) => repo.update_if_match(&utx, id, version, patch).await?,
+        None => repo.update(&utx, id, patch).await?,
+    };
+    let etag = etag(recipe.0.version);
+    Ok(warp::reply::with_header(
+        json_response(recipe)?,
+        "ETag",
+        etag,
+    ))
 }
 
-async fn recipe_delete(db: Arc<Db>, utx: UserCtx, id: i64) -> Result<Json, warp::Rejection> {
-    let recipe = RecipeMac::delete(&db, &utx, id).await?;
+async fn recipe_delete(
+    repo: Arc<dyn RecipeRepo>,
+    utx: UserCtx,
+    id: i64,
+    if_match: Option<i64>,
+) -> Result<Json, warp::Rejection> {
+    let recipe = match if_match {
+        Some(version) => repo.delete_if_match(&utx, id, version).await?,
+        None => repo.delete(&utx, id).await?,
+    };
     json_response(recipe)
 }
 
@@ -0,0 +1,129 @@
+use crate::model::{CommentOrder, Db, RecipeCommentMac, RecipeCommentPatch};
+use crate::security::UserCtx;
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use std::sync::Arc;
+use warp::reply::Json;
+use warp::Filter;
+
+use super::filter_auth::do_auth;
+use super::filter_utils::with_db;
+
+pub fn recipe_comment_rest_filters(
+    base_path: &'static str,
+    db: Arc<Db>,
+) -> impl Filter<Extract = impl warp::Reply, Error = warp::Rejection> + Clone {
+    let recipes_path = warp::path(base_path).and(warp::path("recipes"));
+    let common = with_db(db.clone()).and(do_auth());
+
+    /// LIST comment threads 'GET /recipes/1000/comments?order=oldest&limit=20&offset=0'
+    let list = recipes_path
+        .and(warp::get())
+        .and(common.clone())
+        .and(warp::path::param())
+        .and(warp::path("comments"))
+        .and(warp::path::end())
+        .and(warp::query::<CommentListParams>())
+        .and_then(recipe_comment_list);
+
+    /// CREATE comment or reply 'POST /recipes/1000/comments with body RecipeCommentPatch'
+    let create = recipes_path
+        .and(warp::post())
+        .and(common.clone())
+        .and(warp::path::param())
+        .and(warp::path("comments"))
+        .and(warp::path::end())
+        .and(warp::body::json())
+        .and_then(recipe_comment_create);
+
+    /// UPDATE comment 'PATCH /recipes/1000/comments/1 with body RecipeCommentPatch'
+    let update = recipes_path
+        .and(warp::patch())
+        .and(common.clone())
+        .and(warp::path::param())
+        .and(warp::path("comments"))
+        .and(warp::path::param())
+        .and(warp::path::end())
+        .and(warp::body::json())
+        .and_then(recipe_comment_update);
+
+    /// DELETE comment 'DELETE /recipes/1000/comments/1'
+    let delete = recipes_path
+        .and(warp::delete())
+        .and(common.clone())
+        .and(warp::path::param())
+        .and(warp::path("comments"))
+        .and(warp::path::param())
+        .and(warp::path::end())
+        .and_then(recipe_comment_delete);
+
+    list.or(create).or(update).or(delete)
+}
+
+#[derive(Deserialize)]
+struct CommentListParams {
+    #[serde(default)]
+    order: CommentOrder,
+    #[serde(default = "default_limit")]
+    limit: i64,
+    #[serde(default)]
+    offset: i64,
+}
+
+fn default_limit() -> i64 {
+    20
+}
+
+async fn recipe_comment_list(
+    db: Arc<Db>,
+    utx: UserCtx,
+    recipe_id: i64,
+    params: CommentListParams,
+) -> Result<Json, warp::Rejection> {
+    let threads = RecipeCommentMac::list(
+        &db,
+        &utx,
+        recipe_id,
+        params.order,
+        params.limit,
+        params.offset,
+    )
+    .await?;
+    json_response(threads)
+}
+
+async fn recipe_comment_create(
+    db: Arc<Db>,
+    utx: UserCtx,
+    recipe_id: i64,
+    patch: RecipeCommentPatch,
+) -> Result<Json, warp::Rejection> {
+    let comment = RecipeCommentMac::create(&db, &utx, recipe_id, patch).await?;
+    json_response(comment)
+}
+
+async fn recipe_comment_update(
+    db: Arc<Db>,
+    utx: UserCtx,
+    recipe_id: i64,
+    id: i64,
+    patch: RecipeCommentPatch,
+) -> Result<Json, warp::Rejection> {
+    let comment = RecipeCommentMac::update(&db, &utx, recipe_id, id, patch).await?;
+    json_response(comment)
+}
+
+async fn recipe_comment_delete(
+    db: Arc<Db>,
+    utx: UserCtx,
+    recipe_id: i64,
+    id: i64,
+) -> Result<Json, warp::Rejection> {
+    let comment = RecipeCommentMac::delete(&db, &utx, recipe_id, id).await?;
+    json_response(comment)
+}
+
+fn json_response<D: Serialize>(data: D) -> Result<Json, warp::Rejection> {
+    let response = json!({"data": data});
+    Ok(warp::reply::json(&response))
+}
@@ -0,0 +1,108 @@
+use crate::model::{Db, RecipeReviewMac, RecipeReviewPatch};
+use crate::security::UserCtx;
+use serde::Serialize;
+use serde_json::json;
+use std::sync::Arc;
+use warp::reply::Json;
+use warp::Filter;
+
+use super::filter_auth::do_auth;
+use super::filter_utils::with_db;
+
+pub fn recipe_review_rest_filters(
+    base_path: &'static str,
+    db: Arc<Db>,
+) -> impl Filter<Extract = impl warp::Reply, Error = warp::Rejection> + Clone {
+    let recipes_path = warp::path(base_path).and(warp::path("recipes"));
+    let common = with_db(db.clone()).and(do_auth());
+
+    /// LIST recipe reviews 'GET /recipes/1000/reviews'
+    let list = recipes_path
+        .and(warp::get())
+        .and(common.clone())
+        .and(warp::path::param())
+        .and(warp::path("reviews"))
+        .and(warp::path::end())
+        .and_then(recipe_review_list);
+
+    /// GET own review 'GET /recipes/1000/reviews/mine'
+    let get = recipes_path
+        .and(warp::get())
+        .and(common.clone())
+        .and(warp::path::param())
+        .and(warp::path("reviews"))
+        .and(warp::path("mine"))
+        .and(warp::path::end())
+        .and_then(recipe_review_get);
+
+    /// CREATE or REPLACE own review 'PUT /recipes/1000/reviews/mine with body RecipeReviewPatch'
+    let upsert = recipes_path
+        .and(warp::put())
+        .and(common.clone())
+        .and(warp::path::param())
+        .and(warp::path("reviews"))
+        .and(warp::path("mine"))
+        .and(warp::path::end())
+        .and(warp::body::json())
+        .and_then(recipe_review_upsert);
+
+    /// DELETE own review 'DELETE /recipes/1000/reviews/mine'
+    let delete = recipes_path
+        .and(warp::delete())
+        .and(common.clone())
+        .and(warp::path::param())
+        .and(warp::path("reviews"))
+        .and(warp::path("mine"))
+        .and(warp::path::end())
+        .and_then(recipe_review_delete);
+
+    list.or(get).or(upsert).or(delete)
+}
+
+async fn recipe_review_list(
+    db: Arc<Db>,
+    utx: UserCtx,
+    recipe_id: i64,
+) -> Result<Json, warp::Rejection> {
+    let reviews = RecipeReviewMac::list(&db, &utx, recipe_id).await?;
+    json_response(reviews)
+}
+
+async fn recipe_review_get(
+    db: Arc<Db>,
+    utx: UserCtx,
+    recipe_id: i64,
+) -> Result<Json, warp::Rejection> {
+    let review = RecipeReviewMac::get(&db, &utx, recipe_id).await?;
+    json_response(review)
+}
+
+async fn recipe_review_upsert(
+    db: Arc<Db>,
+    utx: UserCtx,
+    recipe_id: i64,
+    patch: RecipeReviewPatch,
+) -> Result<Json, warp::Rejection> {
+    let review = RecipeReviewMac::upsert(&db, &utx, recipe_id, patch).await?;
+    json_response(review)
+}
+
+async fn recipe_review_delete(
+    db: Arc<Db>,
+    utx: UserCtx,
+    recipe_id: i64,
+) -> Result<Json, warp::Rejection> {
+    let review = RecipeReviewMac::delete(&db, &utx, recipe_id).await?;
+    json_response(review)
+}
+
+fn json_response<D: Serialize>(data: D) -> Result<Json, warp::Rejection> {
+    let response = json!({"data": data});
+    Ok(warp::reply::json(&response))
+}
+
+// region: Test
+#[cfg(test)]
+#[path = "../_tests/web_recipe_review.rs"]
+mod tests;
+// endregion: Test
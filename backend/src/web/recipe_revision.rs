@@ -0,0 +1,94 @@
+use crate::model::{Db, RecipeRevisionMac};
+use crate::security::UserCtx;
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use std::sync::Arc;
+use warp::reply::Json;
+use warp::Filter;
+
+use super::filter_auth::do_auth;
+use super::filter_utils::with_db;
+
+/// Note: over the db, the revisions are not part of the `RecipeRepo`.
+pub fn recipe_revision_rest_filters(
+    base_path: &'static str,
+    db: Arc<Db>,
+) -> impl Filter<Extract = impl warp::Reply, Error = warp::Rejection> + Clone {
+    let recipes_path = warp::path(base_path).and(warp::path("recipes"));
+    let common = with_db(db).and(do_auth());
+
+    /// LIST recipe revisions 'GET /recipes/1000/revisions'
+    let revision_list = recipes_path
+        .and(warp::get())
+        .and(common.clone())
+        .and(warp::path::param())
+        .and(warp::path("revisions"))
+        .and(warp::path::end())
+        .and_then(recipe_revision_list);
+
+    /// DIFF two recipe revisions 'GET /recipes/1000/revisions/diff?from=1&to=2'
+    let revision_diff = recipes_path
+        .and(warp::get())
+        .and(common.clone())
+        .and(warp::path::param())
+        .and(warp::path("revisions"))
+        .and(warp::path("diff"))
+        .and(warp::path::end())
+        .and(warp::query::<RevisionDiffParams>())
+        .and_then(recipe_revision_diff);
+
+    /// RESTORE recipe revision as a new revision 'POST /recipes/1000/revisions/1/restore'
+    let revision_restore = recipes_path
+        .and(warp::post())
+        .and(common.clone())
+        .and(warp::path::param())
+        .and(warp::path("revisions"))
+        .and(warp::path::param())
+        .and(warp::path("restore"))
+        .and(warp::path::end())
+        .and_then(recipe_revision_restore);
+
+    revision_list.or(revision_diff).or(revision_restore)
+}
+
+#[derive(Deserialize)]
+struct RevisionDiffParams {
+    from: i64,
+    to: i64,
+}
+
+async fn recipe_revision_list(db: Arc<Db>, utx: UserCtx, id: i64) -> Result<Json, warp::Rejection> {
+    let revisions = RecipeRevisionMac::list(&db, &utx, id).await?;
+    json_response(revisions)
+}
+
+async fn recipe_revision_diff(
+    db: Arc<Db>,
+    utx: UserCtx,
+    id: i64,
+    params: RevisionDiffParams,
+) -> Result<Json, warp::Rejection> {
+    let diff = RecipeRevisionMac::diff(&db, &utx, id, params.from, params.to).await?;
+    json_response(diff)
+}
+
+async fn recipe_revision_restore(
+    db: Arc<Db>,
+    utx: UserCtx,
+    id: i64,
+    rev: i64,
+) -> Result<Json, warp::Rejection> {
+    let recipe = RecipeRevisionMac::restore(&db, &utx, id, rev).await?;
+    json_response(recipe)
+}
+
+fn json_response<D: Serialize>(data: D) -> Result<Json, warp::Rejection> {
+    let response = json!({"data": data});
+    Ok(warp::reply::json(&response))
+}
+
+// region: Test
+#[cfg(test)]
+#[path = "../_tests/web_recipe_revision.rs"]
+mod tests;
+// endregion: Test
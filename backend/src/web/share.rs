@@ -0,0 +1,88 @@
+use crate::model::{Db, ShareLinkMac, ShareLinkPatch};
+use crate::security::UserCtx;
+use serde::Serialize;
+use serde_json::json;
+use std::sync::Arc;
+use warp::reply::Json;
+use warp::Filter;
+
+use super::filter_auth::do_auth;
+use super::filter_utils::with_db;
+
+pub fn share_rest_filters(
+    base_path: &'static str,
+    db: Arc<Db>,
+) -> impl Filter<Extract = impl warp::Reply, Error = warp::Rejection> + Clone {
+    let shares_path = warp::path(base_path).and(warp::path("shares"));
+    let common = with_db(db.clone()).and(do_auth());
+
+    /// LIST own share links 'GET /shares'
+    let list = shares_path
+        .and(warp::get())
+        .and(warp::path::end())
+        .and(common.clone())
+        .and_then(share_list);
+
+    /// CREATE share link 'POST /shares with body ShareLinkPatch'
+    let create = shares_path
+        .and(warp::post())
+        .and(warp::path::end())
+        .and(common.clone())
+        .and(warp::body::json())
+        .and_then(share_create);
+
+    /// REVOKE share link 'DELETE /shares/1000'
+    let revoke = shares_path
+        .and(warp::delete())
+        .and(common.clone())
+        .and(warp::path::param())
+        .and(warp::path::end())
+        .and_then(share_revoke);
+
+    /// VIEW shared content 'GET /shared/<token>'
+    /// Note: public, no do_auth, the token is the credential
+    let view = warp::path(base_path)
+        .and(warp::path("shared"))
+        .and(warp::get())
+        .and(with_db(db))
+        .and(warp::path::param())
+        .and(warp::path::end())
+        .and_then(share_view);
+
+    list.or(create).or(revoke).or(view)
+}
+
+async fn share_list(db: Arc<Db>, utx: UserCtx) -> Result<Json, warp::Rejection> {
+    let links = ShareLinkMac::list(&db, &utx).await?;
+    json_response(links)
+}
+
+async fn share_create(
+    db: Arc<Db>,
+    utx: UserCtx,
+    patch: ShareLinkPatch,
+) -> Result<Json, warp::Rejection> {
+    let link = ShareLinkMac::create(&db, &utx, patch).await?;
+    json_response(link)
+}
+
+async fn share_revoke(db: Arc<Db>, utx: UserCtx, id: i64) -> Result<Json, warp::Rejection> {
+    let link = ShareLinkMac::revoke(&db, &utx, id).await?;
+    json_response(link)
+}
+
+async fn share_view(db: Arc<Db>, token: String) -> Result<Json, warp::Rejection> {
+    let shared = ShareLinkMac::view(&db, &token).await?;
+    json_response(shared)
+}
+
+fn json_response<D: Serialize>(data: D) -> Result<Json, warp::Rejection> {
+    let response = json!({"data": data});
+    Ok(warp::reply::json(&response))
+}
+
+// region: Test
+#[cfg(test)]
+#[path = "../_tests/web_share.rs"]
+mod tests;
+// endregion: Test
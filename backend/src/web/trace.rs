@@ -0,0 +1,101 @@
+use futures::future::poll_fn;
+use hyper::{header::HeaderValue, service::Service, Body, Request, Response};
+use rand::{distributions::Alphanumeric, Rng};
+use std::{convert::Infallible, time::Instant};
+use tracing::{info, info_span, Instrument};
+
+use crate::metrics;
+
+pub const HEADER_REQUEST_ID: &str = "X-Request-Id";
+const REQUEST_ID_LEN: usize = 16;
+/// Longer ids sent by the client are replaced (the audit log column is VARCHAR(64))
+const REQUEST_ID_MAX_LEN: usize = 64;
+/// Query params never written to the logs
+const REDACTED_PARAMS: &[&str] = &["token"];
+
+/// Serve one request through the routes inside a span carrying its request id,
+/// log and count its status and latency, and return the id in the X-Request-Id response header.
+pub async fn traced<S>(mut svc: S, mut req: Request<Body>) -> Result<Response<Body>, Infallible>
+where
+    S: Service<Request<Body>, Response = Response<Body>, Error = Infallible>,
+{
+    let request_id = req
+        .headers()
+        .get(HEADER_REQUEST_ID)
+        .and_then(|value| value.to_str().ok())
+        .filter(|id| is_valid_request_id(id))
+        .map(str::to_string)
+        .unwrap_or_else(new_request_id);
+    let request_id_value = HeaderValue::from_str(&request_id).expect("request id is ascii");
+    // so the routes (e.g. do_auth) see the same id, even when generated here
+    req.headers_mut()
+        .insert(HEADER_REQUEST_ID, request_id_value.clone());
+
+    let span = info_span!(
+        "request",
+        request_id = %request_id,
+        method = %req.method(),
+        path = %req.uri().path(),
+        query = %redact_query(req.uri().query().unwrap_or_default()),
+    );
+    let method = req.method().to_string();
+    let path = req.uri().path().to_string();
+    let start = Instant::now();
+
+    let mut res = async move {
+        poll_fn(|cx| svc.poll_ready(cx)).await?;
+        svc.call(req).await
+    }
+    .instrument(span.clone())
+    .await?;
+
+    let elapsed = start.elapsed();
+    let status = res.status().as_u16();
+    span.in_scope(|| {
+        info!(
+            status,
+            latency_ms = elapsed.as_secs_f64() * 1000.0,
+            "response"
+        )
+    });
+    metrics::observe_request(&path, &method, status, elapsed);
+    res.headers_mut()
+        .insert(HEADER_REQUEST_ID, request_id_value);
+
+    Ok(res)
+}
+
+/// Used when the client (or a proxy in front) did not send a usable X-Request-Id.
+pub fn new_request_id() -> String {
+    rand::thread_rng()
+        .sample_iter(&Alphanumeric)
+        .take(REQUEST_ID_LEN)
+        .map(char::from)
+        .collect()
+}
+
+fn is_valid_request_id(id: &str) -> bool {
+    !id.is_empty()
+        && id.len() <= REQUEST_ID_MAX_LEN
+        && id
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_' || c == '.')
+}
+
+/// Query string with the values of the secret params (e.g. the WebSocket `?token=`) masked.
+pub fn redact_query(query: &str) -> String {
+    query
+        .split('&')
+        .map(|pair| match pair.split_once('=') {
+            Some((name, _)) if REDACTED_PARAMS.contains(&name) => format!("{}=***", name),
+            _ => pair.to_string(),
+        })
+        .collect::<Vec<String>>()
+        .join("&")
+}
+
+// region: Test
+#[cfg(test)]
+#[path = "../_tests/web_trace.rs"]
+mod tests;
+// endregion: Test
@@ -0,0 +1,77 @@
+use crate::model::{Db, IngredientMac, RecipeMac, TrashMac};
+use crate::security::UserCtx;
+use serde::Serialize;
+use serde_json::json;
+use std::sync::Arc;
+use warp::reply::Json;
+use warp::Filter;
+
+use super::filter_auth::do_auth;
+use super::filter_utils::with_db;
+
+pub fn trash_rest_filters(
+    base_path: &'static str,
+    db: Arc<Db>,
+) -> impl Filter<Extract = impl warp::Reply, Error = warp::Rejection> + Clone {
+    let trash_path = warp::path(base_path).and(warp::path("trash"));
+    let common = with_db(db.clone()).and(do_auth());
+
+    /// LIST trash 'GET /trash'
+    let list = trash_path
+        .and(warp::get())
+        .and(warp::path::end())
+        .and(common.clone())
+        .and_then(trash_list);
+
+    /// RESTORE recipe 'POST /trash/recipes/1000/restore'
+    let restore_recipe = trash_path
+        .and(warp::post())
+        .and(common.clone())
+        .and(warp::path("recipes"))
+        .and(warp::path::param())
+        .and(warp::path("restore"))
+        .and(warp::path::end())
+        .and_then(trash_restore_recipe);
+
+    /// RESTORE ingredient 'POST /trash/ingredients/1000/restore'
+    let restore_ingredient = trash_path
+        .and(warp::post())
+        .and(common.clone())
+        .and(warp::path("ingredients"))
+        .and(warp::path::param())
+        .and(warp::path("restore"))
+        .and(warp::path::end())
+        .and_then(trash_restore_ingredient);
+
+    list.or(restore_recipe).or(restore_ingredient)
+}
+
+async fn trash_list(db: Arc<Db>, utx: UserCtx) -> Result<Json, warp::Rejection> {
+    let items = TrashMac::list(&db, &utx).await?;
+    json_response(items)
+}
+
+async fn trash_restore_recipe(db: Arc<Db>, utx: UserCtx, id: i64) -> Result<Json, warp::Rejection> {
+    let recipe = RecipeMac::restore(&db, &utx, id).await?;
+    json_response(recipe)
+}
+
+async fn trash_restore_ingredient(
+    db: Arc<Db>,
+    utx: UserCtx,
+    id: i64,
+) -> Result<Json, warp::Rejection> {
+    let ingredient = IngredientMac::restore(&db, &utx, id).await?;
+    json_response(ingredient)
+}
+
+fn json_response<D: Serialize>(data: D) -> Result<Json, warp::Rejection> {
+    let response = json!({"data": data});
+    Ok(warp::reply::json(&response))
+}
+
+// region: Test
+#[cfg(test)]
+#[path = "../_tests/web_trash.rs"]
+mod tests;
+// endregion: Test
@@ -0,0 +1,66 @@
+use crate::model::EventBus;
+use crate::security::UserCtx;
+use crate::shutdown::Shutdown;
+use futures::{SinkExt, StreamExt};
+use serde_json::json;
+use tokio::sync::broadcast::error::RecvError;
+use warp::ws::{Message, WebSocket, Ws};
+use warp::Filter;
+
+use super::filter_auth::do_auth_ws;
+
+const WS_CLOSE_GOING_AWAY: u16 = 1001;
+
+pub fn ws_filters(
+    base_path: &'static str,
+    shutdown: Shutdown,
+) -> impl Filter<Extract = impl warp::Reply, Error = warp::Rejection> + Clone {
+    /// CHANGE EVENTS 'GET /ws' (WebSocket upgrade)
+    warp::path(base_path)
+        .and(warp::path("ws"))
+        .and(warp::path::end())
+        .and(warp::ws())
+        .and(do_auth_ws())
+        .map(move |ws: Ws, utx: UserCtx| {
+            let shutdown = shutdown.clone();
+            ws.on_upgrade(move |socket| ws_session(socket, utx, shutdown))
+        })
+}
+
+/// Forward the change events to the socket until either side closes, or the server shuts down.
+/// Note: every live recipe and ingredient is visible to every user, so all the events go through.
+async fn ws_session(socket: WebSocket, _utx: UserCtx, shutdown: Shutdown) {
+    let (mut ws_tx, mut ws_rx) = socket.split();
+    let mut events = EventBus::subscribe();
+
+    loop {
+        tokio::select! {
+            event = events.recv() => {
+                let text = match event {
+                    Ok(event) => json!(event).to_string(),
+                    // too slow, some events were dropped, the client should reload everything
+                    Err(RecvError::Lagged(count)) => json!({ "lagged": count }).to_string(),
+                    Err(RecvError::Closed) => break,
+                };
+                if ws_tx.send(Message::text(text)).await.is_err() {
+                    break;
+                }
+            }
+            msg = ws_rx.next() => match msg {
+                Some(Ok(msg)) if !msg.is_close() => (),
+                _ => break,
+            },
+            // "going away", so the client reconnects (to another instance)
+            _ = shutdown.wait() => {
+                let _ = ws_tx.send(Message::close_with(WS_CLOSE_GOING_AWAY, "server shutting down")).await;
+                break;
+            }
+        }
+    }
+}
+
+// region: Test
+#[cfg(test)]
+#[path = "../_tests/web_ws.rs"]
+mod tests;
+// endregion: Test